@@ -3,8 +3,14 @@
 //! Parses arguments using clap and provides the [`Cli`] struct containing
 //! all user-specified options.
 
-use crate::config::Layout;
-use clap::Parser;
+use crate::config::{Config, Layout};
+use crate::error::{EXIT_CODES, PanoutError, Result};
+use crate::listing::ListFormat;
+use crate::run::RunOptions;
+use crate::shell;
+use clap::{ArgGroup, Parser, Subcommand};
+use serde::Serialize;
+use std::path::PathBuf;
 
 /// Command-line arguments for panout.
 ///
@@ -14,20 +20,52 @@ use clap::Parser;
 /// # Run a bundle with 3 vertical panes
 /// panout -b dev.frontend -n 3 -v
 ///
+/// # Same thing, with the bundle given positionally
+/// panout dev.frontend -n 3 -v
+///
 /// # Run a workspace (multiple windows)
 /// panout -w myproject
 ///
+/// # Connect to a server and run its commands
+/// panout -s prod
+///
 /// # List all available bundles and workspaces
 /// panout --list
+///
+/// # Create a starter config
+/// panout init
+///
+/// # Check the environment for common problems
+/// panout doctor
+///
+/// # Repeat the previous run
+/// panout last
 /// ```
 #[derive(Parser, Debug)]
 #[command(name = "panout")]
 #[command(version)]
 #[command(about = "Tmux pane orchestrator - create panes and windows from config")]
-#[command(long_about = "Panout creates tmux panes and windows based on TOML configuration.\n\n\
+#[command(group(ArgGroup::new("layout_flag").args(["vertical", "horizontal"])))]
+#[command(group(ArgGroup::new("bundle_source").args(["bundle", "target", "tag", "select"])))]
+#[command(
+    long_about = "Panout creates tmux panes and windows based on TOML configuration.\n\n\
     Define bundles for local commands or workspaces for multi-window SSH setups,\n\
-    then spawn them with a single command.")]
+    then spawn them with a single command."
+)]
+#[command(after_help = examples_help())]
 pub struct Cli {
+    /// Subcommand to run instead of a bundle or workspace.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Bundle or workspace to run.
+    ///
+    /// Treated as a bundle path, or as a workspace name when no bundle
+    /// matches. Use `-b`/`-w` to disambiguate when both exist. When omitted
+    /// at a terminal, panout offers a menu to pick from.
+    #[arg(value_name = "TARGET", conflicts_with_all = ["bundle", "workspace"])]
+    pub target: Option<String>,
+
     /// Bundle to run (format: group.name).
     ///
     /// Bundles are defined in your config as `[group.name]` sections.
@@ -41,10 +79,48 @@ pub struct Cli {
     #[arg(short = 'w', long, value_name = "NAME")]
     pub workspace: Option<String>,
 
-    /// Number of panes to create.
-    #[arg(short, long, value_name = "COUNT")]
+    /// Server to connect to in the current pane.
+    ///
+    /// Servers are defined under `[servers.name]`. Panout SSHes to the
+    /// host, runs its `cmd`, and exits afterwards if `disconnect = true`.
+    #[arg(short = 's', long, value_name = "NAME", conflicts_with_all = ["bundle", "workspace"])]
+    pub server: Option<String>,
+
+    /// Run every bundle tagged TAG, one after another across the panes.
+    ///
+    /// Bundles declare tags with `tags = ["rust"]`. With `--list`, only
+    /// bundles carrying the tag are listed.
+    #[arg(
+        long,
+        value_name = "TAG",
+        conflicts_with_all = ["target", "bundle", "workspace", "server"]
+    )]
+    pub tag: Option<String>,
+
+    /// Pick a bundle from a numbered menu read from stdin.
+    ///
+    /// An empty answer or end of input exits without running anything.
+    #[arg(short = 'S', long, conflicts_with_all = ["workspace", "server", "list", "print_config"])]
+    pub select: bool,
+
+    /// Only create some of the workspace's windows.
+    ///
+    /// Comma-separated indices (`0,1`), ranges (`0-2`), or window names
+    /// (`build,logs`).
+    #[arg(long, value_name = "LIST")]
+    pub windows: Option<String>,
+
+    /// Number of panes to create (requires a bundle).
+    ///
+    /// Defaults to as many panes as the bundle assigns commands to. This is a
+    /// floor: a bundle that assigns panes past it still gets them all.
+    #[arg(short, long, value_name = "COUNT", requires = "bundle_source")]
     pub num: Option<u32>,
 
+    /// Cap panes at `-n`, merging commands for later panes into the last one.
+    #[arg(long, requires = "num")]
+    pub clamp: bool,
+
     /// Use vertical layout (side-by-side panes).
     #[arg(short = 'v', help = "Vertical split (panes side by side)")]
     pub vertical: bool,
@@ -53,18 +129,283 @@ pub struct Cli {
     #[arg(short = 'H', help = "Horizontal split (panes stacked)")]
     pub horizontal: bool,
 
+    /// Layout for the panes, overriding the bundle and defaults.
+    #[arg(long, value_enum, value_name = "LAYOUT", conflicts_with_all = ["vertical", "horizontal"])]
+    pub layout: Option<Layout>,
+
+    /// Milliseconds to wait between commands sent to the same pane.
+    ///
+    /// Overrides any `delay_ms` set on the bundle.
+    #[arg(long, value_name = "MS")]
+    pub delay: Option<u64>,
+
+    /// Build the bundle in a new window instead of splitting the current one.
+    ///
+    /// The window is named after the bundle unless a name is given with
+    /// `--new-window=NAME`. Focus returns to the original window afterwards
+    /// unless `--stay` is passed.
+    #[arg(
+        short = 'W',
+        long,
+        value_name = "NAME",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with_all = ["workspace", "server"]
+    )]
+    pub new_window: Option<Option<String>>,
+
+    /// Stay in the window created by `--new-window`.
+    #[arg(long, requires = "new_window")]
+    pub stay: bool,
+
+    /// Working directory for every pane.
+    ///
+    /// Overrides any `dir` set on the bundle.
+    #[arg(long, value_name = "PATH")]
+    pub dir: Option<String>,
+
+    /// Environment variable to export in every pane (repeatable).
+    ///
+    /// Overrides the bundle's `env` entry with the same key.
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    pub env: Vec<(String, String)>,
+
+    /// Pane to select after setup (0-based).
+    ///
+    /// Overrides any `focus` set on the bundle.
+    #[arg(long, value_name = "PANE")]
+    pub focus: Option<u32>,
+
+    /// Command to send to every pane of a bundle once setup is done (repeatable).
+    ///
+    /// A one-shot alternative to `--sync`, e.g. `--broadcast "source .envrc"`.
+    #[arg(long, value_name = "CMD")]
+    pub broadcast: Vec<String>,
+
+    /// Mirror keystrokes across all panes once setup is done.
+    ///
+    /// Turns on tmux's `synchronize-panes` in every window panout builds,
+    /// after each pane has received its own commands.
+    #[arg(long)]
+    pub sync: bool,
+
+    /// Seconds to wait for each tmux command before giving up.
+    ///
+    /// Protects against a wedged tmux server. Raise it if `run_shell` hooks
+    /// take longer, since tmux waits for them to finish.
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = crate::tmux::DEFAULT_TIMEOUT.as_secs(),
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub tmux_timeout: u64,
+
+    /// Don't ask before building a layout past `confirm_threshold`.
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Turn config problems into errors (for CI).
+    ///
+    /// Enables every strict check: empty bundles, blank commands, bundles
+    /// setting the same pane, config warnings such as unbalanced quotes or a
+    /// misspelled top-level key, and `{git_root}` outside a git repository
+    /// all fail the run.
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Write a JSON summary of the run to this file, even if the run fails.
+    ///
+    /// Records the target, windows and panes created, commands sent, and
+    /// the error message on failure.
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+
+    /// Config file to use instead of the discovered one.
+    ///
+    /// Overrides the `PANOUT_CONFIG` environment variable. The file must
+    /// exist; there is no fallback to the default locations.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print the loaded config as TOML and exit.
+    ///
+    /// Shows what the parser understood, with keys sorted and every bundle
+    /// and window layout filled in. Misspelled sections show up as bundle
+    /// groups.
+    #[arg(long, conflicts_with_all = ["target", "bundle", "workspace", "server", "list"])]
+    pub print_config: bool,
+
+    /// Print how many panes and windows the target would create, then exit.
+    ///
+    /// Nothing is sent to tmux.
+    #[arg(long, conflicts_with_all = ["list", "print_config"])]
+    pub count_only: bool,
+
     /// List all available bundles, workspaces, and servers.
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["target", "bundle", "workspace", "server", "num"]
+    )]
     pub list: bool,
+
+    /// Show each target's first command in `--list` output.
+    #[arg(long, requires = "list")]
+    pub long: bool,
+
+    /// Output format for `--list`.
+    ///
+    /// `plain` prints one name per line; `json` prints an object with
+    /// `bundles`, `workspaces`, and `servers` arrays of `name`,
+    /// `description`, and `command` entries.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t,
+        requires = "list"
+    )]
+    pub format: ListFormat,
+}
+
+/// Usage examples as `(description, command line)` pairs.
+///
+/// Shared by `--help` and the man page so the two can't drift apart.
+pub const EXAMPLES: &[(&str, &str)] = &[
+    (
+        "Run a bundle with 3 vertical panes",
+        "panout -b dev.frontend -n 3 -v",
+    ),
+    (
+        "Same thing, with the bundle given positionally",
+        "panout dev.frontend -n 3 -v",
+    ),
+    ("Run a workspace (multiple windows)", "panout -w myproject"),
+    ("Connect to a server and run its commands", "panout -s prod"),
+    ("List all available bundles and workspaces", "panout --list"),
+    ("Create a starter config", "panout init"),
+    ("Check the environment for common problems", "panout doctor"),
+    ("Repeat the previous run", "panout last"),
+];
+
+/// Render [`EXAMPLES`] and the exit codes for the end of `--help`.
+fn examples_help() -> String {
+    let mut help = String::from("Examples:");
+    for (description, command) in EXAMPLES {
+        help.push_str(&format!("\n  # {}\n  {}\n", description, command));
+    }
+    help.push_str("\nExit status:");
+    for (code, meaning) in EXIT_CODES {
+        help.push_str(&format!("\n  {}  {}", code, meaning));
+    }
+    help
+}
+
+/// Subcommands.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Check tmux, the config file, and tmux settings for common problems.
+    Doctor,
+
+    /// Write a commented starter config if none exists yet.
+    Init,
+
+    /// Repeat the last successful run.
+    Last {
+        /// Print the last run instead of repeating it.
+        #[arg(long)]
+        show: bool,
+    },
+
+    /// Print a shell completion script.
+    ///
+    /// For example, `panout completions bash > /etc/bash_completion.d/panout`.
+    Completions {
+        /// Shell to generate completions for.
+        #[arg(value_enum)]
+        shell: crate::completions::Shell,
+    },
+
+    /// Print the panout(1) man page in roff format.
+    #[command(hide = true)]
+    Man,
+}
+
+/// Parse a `KEY=VALUE` argument for `--env`.
+fn parse_env_var(s: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", s))?;
+    if !shell::is_valid_env_key(key) {
+        return Err(format!("invalid environment variable name '{}'", key));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// What the user asked panout to run.
+///
+/// Serializes as `{"kind": "bundle", "name": "dev.all"}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "lowercase")]
+pub enum Target {
+    /// Run a bundle by `group.name` path.
+    Bundle(String),
+    /// Run every bundle carrying a tag.
+    Tag(String),
+    /// Run a workspace by name.
+    Workspace(String),
+    /// Connect to a server by name.
+    Server(String),
 }
 
 impl Cli {
+    /// Determine which bundle or workspace to run.
+    ///
+    /// `-s`, `-w`, `-b` and `--tag` are taken as-is. A positional target is looked up in the
+    /// config: bundles win, then workspaces. Returns `Ok(None)` when nothing
+    /// was specified.
+    ///
+    /// # Errors
+    ///
+    /// - [`PanoutError::AmbiguousTarget`] if the positional name is both a bundle and a workspace
+    /// - [`PanoutError::BundleNotFound`] if the positional name matches neither
+    pub fn target(&self, config: &Config) -> Result<Option<Target>> {
+        if let Some(ref server) = self.server {
+            return Ok(Some(Target::Server(server.clone())));
+        }
+        if let Some(ref ws) = self.workspace {
+            return Ok(Some(Target::Workspace(ws.clone())));
+        }
+        if let Some(ref bundle) = self.bundle {
+            return Ok(Some(Target::Bundle(bundle.clone())));
+        }
+        if let Some(ref tag) = self.tag {
+            return Ok(Some(Target::Tag(tag.clone())));
+        }
+        let Some(ref name) = self.target else {
+            return Ok(None);
+        };
+
+        let is_bundle = config.get_bundle(name).is_some() || config.aliases.contains_key(name);
+        let is_workspace = config.get_workspace(name).is_some();
+        match (is_bundle, is_workspace) {
+            (true, true) => Err(PanoutError::AmbiguousTarget(name.clone())),
+            (true, false) => Ok(Some(Target::Bundle(name.clone()))),
+            (false, true) => Ok(Some(Target::Workspace(name.clone()))),
+            (false, false) => Err(PanoutError::BundleNotFound(name.clone())),
+        }
+    }
+
     /// Determine the layout from CLI flags.
     ///
-    /// Returns `Some(Layout)` if `-v` or `-H` was specified, `None` otherwise.
-    /// When `None`, the layout falls back to bundle config or defaults.
+    /// Returns `Some(Layout)` if `--layout`, `-v`, or `-H` was specified,
+    /// `None` otherwise. When `None`, the layout falls back to bundle config or
+    /// defaults.
     pub fn layout(&self) -> Option<Layout> {
-        if self.vertical {
+        if self.layout.is_some() {
+            self.layout
+        } else if self.vertical {
             Some(Layout::Vertical)
         } else if self.horizontal {
             Some(Layout::Horizontal)
@@ -72,4 +413,230 @@ impl Cli {
             None
         }
     }
+
+    /// The run settings given on the command line.
+    pub fn run_options(&self) -> RunOptions {
+        RunOptions {
+            num: self.num,
+            clamp: self.clamp,
+            layout: self.layout(),
+            delay_ms: self.delay,
+            new_window: self.new_window.is_some(),
+            window_name: self.new_window.clone().flatten(),
+            stay: self.stay,
+            dir: self.dir.clone(),
+            env: self.env.clone(),
+            focus: self.focus,
+            broadcast: self.broadcast.clone(),
+            sync: self.sync,
+            strict: self.strict,
+            windows: self.windows.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn config() -> Config {
+        Config::from_str(
+            r#"
+            [dev.frontend]
+            cmd = "npm run dev"
+
+            [dev.both]
+            cmd = "echo bundle"
+
+            [workspace.myproject]
+            windows = [{ panes = 2 }]
+
+            [workspace."dev.both"]
+            windows = [{ panes = 1 }]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_positional_bundle() {
+        let cli = Cli::try_parse_from(["panout", "dev.frontend", "-n", "3", "-v"]).unwrap();
+        assert_eq!(
+            cli.target(&config()).unwrap(),
+            Some(Target::Bundle("dev.frontend".into()))
+        );
+        assert_eq!(cli.num, Some(3));
+        assert_eq!(cli.layout(), Some(Layout::Vertical));
+    }
+
+    #[test]
+    fn test_positional_workspace() {
+        let cli = Cli::try_parse_from(["panout", "myproject"]).unwrap();
+        assert_eq!(
+            cli.target(&config()).unwrap(),
+            Some(Target::Workspace("myproject".into()))
+        );
+    }
+
+    #[test]
+    fn test_positional_ambiguous() {
+        let cli = Cli::try_parse_from(["panout", "dev.both"]).unwrap();
+        assert!(matches!(
+            cli.target(&config()),
+            Err(PanoutError::AmbiguousTarget(name)) if name == "dev.both"
+        ));
+    }
+
+    #[test]
+    fn test_positional_unknown() {
+        let cli = Cli::try_parse_from(["panout", "nope.nothing"]).unwrap();
+        assert!(matches!(
+            cli.target(&config()),
+            Err(PanoutError::BundleNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_flags_still_work() {
+        let cli = Cli::try_parse_from(["panout", "-b", "dev.both"]).unwrap();
+        assert_eq!(
+            cli.target(&config()).unwrap(),
+            Some(Target::Bundle("dev.both".into()))
+        );
+
+        let cli = Cli::try_parse_from(["panout", "-w", "dev.both"]).unwrap();
+        assert_eq!(
+            cli.target(&config()).unwrap(),
+            Some(Target::Workspace("dev.both".into()))
+        );
+    }
+
+    #[test]
+    fn test_layout_option() {
+        let cli =
+            Cli::try_parse_from(["panout", "dev.frontend", "--layout", "main-vertical"]).unwrap();
+        assert_eq!(cli.layout(), Some(Layout::MainVertical));
+        assert!(Cli::try_parse_from(["panout", "--layout", "tiled", "-v"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "--layout", "sideways"]).is_err());
+    }
+
+    #[test]
+    fn test_new_window_short_flag() {
+        let cli = Cli::try_parse_from(["panout", "dev.frontend", "-W", "--stay"]).unwrap();
+        assert_eq!(cli.new_window, Some(None));
+        assert!(cli.stay);
+
+        let cli = Cli::try_parse_from(["panout", "dev.frontend", "-W=web"]).unwrap();
+        assert_eq!(cli.new_window, Some(Some("web".into())));
+    }
+
+    #[test]
+    fn test_tag_target() {
+        let cli = Cli::try_parse_from(["panout", "--tag", "rust", "-n", "4"]).unwrap();
+        assert_eq!(
+            cli.target(&config()).unwrap(),
+            Some(Target::Tag("rust".into()))
+        );
+
+        assert!(Cli::try_parse_from(["panout", "--tag", "rust", "-b", "dev.api"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "dev.api", "--tag", "rust"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "--list", "--tag", "rust"]).is_ok());
+    }
+
+    #[test]
+    fn test_select_flag() {
+        let cli = Cli::try_parse_from(["panout", "-S", "-n", "4"]).unwrap();
+        assert!(cli.select);
+        assert_eq!(cli.target(&config()).unwrap(), None);
+
+        assert!(Cli::try_parse_from(["panout", "--select", "-b", "dev.api"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "--select", "dev.api"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "--select", "-w", "proj"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "--select", "--list"]).is_err());
+    }
+
+    #[test]
+    fn test_doctor_subcommand() {
+        let cli = Cli::try_parse_from(["panout", "doctor"]).unwrap();
+        assert_eq!(cli.command, Some(Command::Doctor));
+        assert_eq!(cli.target, None);
+    }
+
+    #[test]
+    fn test_last_subcommand() {
+        let cli = Cli::try_parse_from(["panout", "last", "--show"]).unwrap();
+        assert_eq!(cli.command, Some(Command::Last { show: true }));
+        assert_eq!(cli.target, None);
+    }
+
+    #[test]
+    fn test_env_args() {
+        let cli = Cli::try_parse_from([
+            "panout",
+            "-b",
+            "dev.backend",
+            "--env",
+            "RUST_LOG=debug",
+            "--env",
+            "GREETING=hello world",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.env,
+            vec![
+                ("RUST_LOG".to_string(), "debug".to_string()),
+                ("GREETING".to_string(), "hello world".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_env_args_rejects_bad_shape() {
+        assert!(Cli::try_parse_from(["panout", "--env", "NOVALUE"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "--env", "BAD-KEY=1"]).is_err());
+    }
+
+    #[test]
+    fn test_tmux_timeout_must_be_positive() {
+        assert!(Cli::try_parse_from(["panout", "--tmux-timeout", "0"]).is_err());
+        let cli = Cli::try_parse_from(["panout", "--tmux-timeout", "1"]).unwrap();
+        assert_eq!(cli.tmux_timeout, 1);
+    }
+
+    #[test]
+    fn test_layout_flags_conflict() {
+        assert!(Cli::try_parse_from(["panout", "-b", "dev.x", "-v", "-H"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "-b", "dev.x", "-v"]).is_ok());
+        assert!(Cli::try_parse_from(["panout", "-b", "dev.x", "-H"]).is_ok());
+    }
+
+    #[test]
+    fn test_list_conflicts_with_targets() {
+        assert!(Cli::try_parse_from(["panout", "--list"]).is_ok());
+        for extra in [
+            &["-b", "dev.x"][..],
+            &["-w", "proj"],
+            &["-s", "prod"],
+            &["dev.x"],
+            &["-n", "2"],
+        ] {
+            let mut args = vec!["panout", "--list"];
+            args.extend_from_slice(extra);
+            assert!(Cli::try_parse_from(&args).is_err(), "{:?}", extra);
+        }
+    }
+
+    #[test]
+    fn test_num_requires_bundle() {
+        assert!(Cli::try_parse_from(["panout", "-n", "3"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "-w", "proj", "-n", "3"]).is_err());
+        assert!(Cli::try_parse_from(["panout", "-b", "dev.x", "-n", "3"]).is_ok());
+        assert!(Cli::try_parse_from(["panout", "dev.x", "-n", "3"]).is_ok());
+    }
+
+    #[test]
+    fn test_positional_conflicts_with_flags() {
+        assert!(Cli::try_parse_from(["panout", "dev.frontend", "-b", "dev.both"]).is_err());
+    }
 }
@@ -0,0 +1,37 @@
+//! Shell completion scripts (`panout completions`).
+//!
+//! Generated from the clap definition of [`Cli`], so every flag and
+//! subcommand completes. Bundle and workspace names aren't completed yet;
+//! `panout --list --format plain` prints them for custom completers.
+
+use crate::cli::Cli;
+use clap::CommandFactory;
+pub use clap_complete::Shell;
+use std::io::{self, Write};
+
+/// Write the completion script for `shell` to `out`.
+pub fn render(shell: Shell, out: &mut dyn Write) -> io::Result<()> {
+    // clap_complete panics on write errors (such as a closed pipe), so the
+    // script is built in memory first
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cmd, name, &mut script);
+    out.write_all(&script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_every_shell() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let mut out = Vec::new();
+            render(shell, &mut out).unwrap();
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("panout"), "{:?}", shell);
+            assert!(script.contains("workspace"), "{:?}", shell);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Platform conditions for commands (`when = "os == linux"`) and bundles
+//! (`when = { hostname = "work-*" }`).
+//!
+//! A command condition compares one property of the machine panout runs on
+//! against a value: `os == macos`, `hostname != build-box`. The OS names are
+//! Rust's (`linux`, `macos`, `freebsd`, ...), as reported by
+//! [`std::env::consts::OS`].
+//!
+//! A bundle's [`BundleWhen`] is checked against a [`Machine`], which tests
+//! can fake instead of reading the real hostname and environment.
+
+use crate::error::{PanoutError, Result};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Operating system names as reported by [`std::env::consts::OS`].
+pub const OS_NAMES: &[&str] = &[
+    "linux",
+    "macos",
+    "ios",
+    "android",
+    "freebsd",
+    "dragonfly",
+    "netbsd",
+    "openbsd",
+    "solaris",
+    "illumos",
+    "haiku",
+    "windows",
+];
+
+/// The machine properties a condition can test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    /// Operating system name, e.g. `linux` or `macos`.
+    pub os: String,
+    /// Short hostname, if it could be determined.
+    pub hostname: Option<String>,
+}
+
+impl Platform {
+    /// The machine panout is running on, looked up once per process.
+    pub fn current() -> &'static Platform {
+        static CURRENT: OnceLock<Platform> = OnceLock::new();
+        CURRENT.get_or_init(|| Platform {
+            os: std::env::consts::OS.to_string(),
+            hostname: current_hostname(),
+        })
+    }
+}
+
+/// Which property a [`Condition`] tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    Os,
+    Hostname,
+}
+
+/// A parsed `when` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub subject: Subject,
+    /// `true` for `!=`.
+    pub negated: bool,
+    pub value: String,
+}
+
+impl Condition {
+    /// Parse `<os|hostname> <==|!=> <value>`.
+    ///
+    /// # Errors
+    ///
+    /// [`PanoutError::InvalidConfig`] describing the expected form.
+    pub fn parse(when: &str) -> Result<Condition> {
+        let invalid = || {
+            PanoutError::InvalidConfig(format!(
+                "invalid condition `{}`: expected `os == NAME` or `hostname == NAME` \
+                 (or `!=`)",
+                when
+            ))
+        };
+
+        let (subject, negated, value) = match (when.split_once("!="), when.split_once("==")) {
+            (Some((subject, value)), _) => (subject, true, value),
+            (None, Some((subject, value))) => (subject, false, value),
+            (None, None) => return Err(invalid()),
+        };
+        let subject = match subject.trim() {
+            "os" => Subject::Os,
+            "hostname" => Subject::Hostname,
+            _ => return Err(invalid()),
+        };
+        let value = value.trim();
+        if value.is_empty() || value.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+
+        Ok(Condition {
+            subject,
+            negated,
+            value: value.to_string(),
+        })
+    }
+
+    /// Whether the condition holds on `platform`.
+    ///
+    /// Hostnames compare case-insensitively; an unknown hostname never
+    /// equals anything.
+    pub fn holds(&self, platform: &Platform) -> bool {
+        let equal = match self.subject {
+            Subject::Os => platform.os == self.value,
+            Subject::Hostname => platform
+                .hostname
+                .as_deref()
+                .is_some_and(|h| h.eq_ignore_ascii_case(&self.value)),
+        };
+        equal != self.negated
+    }
+}
+
+/// Where panout runs, as far as a [`BundleWhen`] is concerned.
+pub trait Machine {
+    /// Short hostname, if it could be determined.
+    fn hostname(&self) -> Option<&str>;
+    /// Value of the environment variable `key`, if set.
+    fn var(&self, key: &str) -> Option<String>;
+}
+
+/// The real machine: [`Platform::current`]'s hostname and the process
+/// environment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalMachine;
+
+impl Machine for LocalMachine {
+    fn hostname(&self) -> Option<&str> {
+        Platform::current().hostname.as_deref()
+    }
+
+    fn var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+}
+
+/// A bundle's `when` table: the bundle only runs where every key set holds.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleWhen {
+    /// Glob the hostname must match, case-insensitively (`workstation-*`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Environment variable that must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Value `env` must have, rather than just being set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+}
+
+impl BundleWhen {
+    /// Check that the hostname glob parses and `equals` comes with `env`.
+    ///
+    /// # Errors
+    ///
+    /// [`PanoutError::InvalidConfig`] describing the problem.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref pattern) = self.hostname {
+            glob::Pattern::new(pattern).map_err(|e| {
+                PanoutError::InvalidConfig(format!("invalid hostname pattern `{}`: {}", pattern, e))
+            })?;
+        }
+        if self.equals.is_some() && self.env.is_none() {
+            return Err(PanoutError::InvalidConfig(
+                "`equals` needs an `env` to compare".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the bundle is active on `machine`.
+    ///
+    /// An unknown hostname matches no pattern, and a bad pattern (only seen
+    /// when validation was skipped) matches nothing.
+    pub fn holds(&self, machine: &dyn Machine) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::default()
+        };
+        let hostname_ok = self.hostname.as_ref().is_none_or(|pattern| {
+            let pattern = glob::Pattern::new(pattern).ok();
+            pattern
+                .zip(machine.hostname())
+                .is_some_and(|(p, h)| p.matches_with(h, options))
+        });
+        let env_ok = self
+            .env
+            .as_ref()
+            .is_none_or(|key| match (machine.var(key), &self.equals) {
+                (None, _) => false,
+                (Some(value), Some(equals)) => value == *equals,
+                (Some(_), None) => true,
+            });
+        hostname_ok && env_ok
+    }
+}
+
+/// Evaluate `when` on `platform`, treating an unparseable condition as false.
+///
+/// Conditions are checked when the config is validated, so this only sees
+/// bad ones for configs that skipped validation.
+pub fn holds(when: &str, platform: &Platform) -> bool {
+    Condition::parse(when).is_ok_and(|c| c.holds(platform))
+}
+
+/// The short hostname from `hostname`, falling back to `$HOSTNAME`.
+fn current_hostname() -> Option<String> {
+    let output = Command::new("hostname").output().ok();
+    let from_command = output
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned());
+    from_command
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|h| h.trim().split('.').next().unwrap_or_default().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeMachine {
+        hostname: Option<&'static str>,
+        vars: &'static [(&'static str, &'static str)],
+    }
+
+    impl Machine for FakeMachine {
+        fn hostname(&self) -> Option<&str> {
+            self.hostname
+        }
+
+        fn var(&self, key: &str) -> Option<String> {
+            self.vars
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    fn platform(os: &str, hostname: Option<&str>) -> Platform {
+        Platform {
+            os: os.into(),
+            hostname: hostname.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!(
+            Condition::parse("os == linux").unwrap(),
+            Condition {
+                subject: Subject::Os,
+                negated: false,
+                value: "linux".into()
+            }
+        );
+        assert_eq!(
+            Condition::parse("hostname!=build").unwrap(),
+            Condition {
+                subject: Subject::Hostname,
+                negated: true,
+                value: "build".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_conditions() {
+        for when in [
+            "linux",
+            "arch == x86_64",
+            "os ==",
+            "os == mac os",
+            "os = linux",
+        ] {
+            assert!(
+                matches!(Condition::parse(when), Err(PanoutError::InvalidConfig(_))),
+                "{when}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_condition_holds() {
+        let linux = platform("linux", Some("Workstation"));
+        assert!(holds("os == linux", &linux));
+        assert!(!holds("os == macos", &linux));
+        assert!(holds("os != macos", &linux));
+        assert!(holds("hostname == workstation", &linux));
+        assert!(!holds("hostname != workstation", &linux));
+        assert!(!holds("not a condition", &linux));
+
+        let unknown = platform("macos", None);
+        assert!(!holds("hostname == workstation", &unknown));
+        assert!(holds("hostname != workstation", &unknown));
+    }
+
+    #[test]
+    fn test_bundle_when_holds() {
+        let work = FakeMachine {
+            hostname: Some("Workstation-3"),
+            vars: &[("CI", "true")],
+        };
+        let laptop = FakeMachine {
+            hostname: None,
+            vars: &[("CI", "")],
+        };
+        let when = |hostname: Option<&str>, env: Option<&str>, equals: Option<&str>| BundleWhen {
+            hostname: hostname.map(Into::into),
+            env: env.map(Into::into),
+            equals: equals.map(Into::into),
+        };
+
+        assert!(when(None, None, None).holds(&laptop));
+        assert!(when(Some("workstation-*"), None, None).holds(&work));
+        assert!(!when(Some("workstation-*"), None, None).holds(&laptop));
+        assert!(when(None, Some("CI"), None).holds(&laptop));
+        assert!(!when(None, Some("HOME"), None).holds(&work));
+        assert!(when(None, Some("CI"), Some("true")).holds(&work));
+        assert!(!when(None, Some("CI"), Some("true")).holds(&laptop));
+        assert!(!when(Some("build-*"), Some("CI"), None).holds(&work));
+    }
+
+    #[test]
+    fn test_bundle_when_validate() {
+        let bad_glob = BundleWhen {
+            hostname: Some("work-[".into()),
+            ..BundleWhen::default()
+        };
+        let equals_alone = BundleWhen {
+            equals: Some("true".into()),
+            ..BundleWhen::default()
+        };
+        assert!(bad_glob.validate().is_err());
+        assert!(equals_alone.validate().is_err());
+    }
+
+    #[test]
+    fn test_current_platform_os() {
+        let os = &Platform::current().os;
+        assert_eq!(os == "linux", cfg!(target_os = "linux"));
+        assert_eq!(os == "macos", cfg!(target_os = "macos"));
+    }
+}
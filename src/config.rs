@@ -29,8 +29,19 @@
 //! ]
 //! ```
 
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::condition::{self, BundleWhen, Machine, Platform};
+use crate::error::{PanoutError, Result as PanoutResult};
+use crate::interpolate;
+use crate::resolver;
+use crate::shell;
+use crate::ssh::{self, SshTarget};
+use crate::tmux;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
 
 /// Command field that accepts either a single string or array of strings.
 ///
@@ -39,22 +50,220 @@ use std::collections::HashMap;
 /// cmd = "single command"
 /// # or
 /// cmd = ["command 1", "command 2"]
+/// # or open $EDITOR on a file (relative to the pane's directory)
+/// cmd = { edit = "src/main.rs" }
+/// # or press tmux keys, without Enter
+/// cmd = { keys = ["C-c", "Escape"] }
+/// # or only run on some machines
+/// cmd = { cmd = "xclip -o", when = "os == linux" }
+/// # or pick commands by operating system
+/// cmd = { linux = "xdg-open .", macos = "open .", default = "echo unsupported" }
 /// ```
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Cmd {
     /// A single command string.
     Single(String),
     /// Multiple commands executed in sequence.
     Multiple(Vec<String>),
+    /// Open the user's editor on a file.
+    Edit {
+        /// File to open, relative to the pane's working directory.
+        edit: String,
+    },
+    /// Press tmux keys (`C-c`, `Escape`, `Up`, ...) without pressing Enter.
+    Keys {
+        /// tmux key names, pressed in order.
+        keys: Vec<String>,
+    },
+    /// Commands that only run where a [condition](condition::Condition) holds.
+    When {
+        /// The commands, in any of the other forms.
+        cmd: Box<Cmd>,
+        /// `os == NAME`, `hostname == NAME`, or the `!=` forms.
+        when: String,
+    },
+    /// Commands per operating system (`linux`, `macos`, ...), with an
+    /// optional `default` for the rest.
+    PerPlatform(BTreeMap<String, Cmd>),
 }
 
 impl Cmd {
-    /// Convert to a `Vec<String>`, normalizing both variants.
+    /// Convert to a `Vec<String>`, normalizing all variants.
+    ///
+    /// `Edit` expands to an editor command using the `EDITOR` environment
+    /// variable (see [`editor_command`]). `Keys` becomes a single
+    /// [key sequence](crate::tmux::key_sequence_command). `When` is evaluated
+    /// against the [current platform](Platform::current), and `PerPlatform`
+    /// picks the current OS's commands.
     pub fn to_vec(&self) -> Vec<String> {
+        self.to_vec_on(Platform::current())
+    }
+
+    /// Like [`to_vec`](Self::to_vec), evaluating `when` conditions on `platform`.
+    ///
+    /// A `When` whose condition fails yields no commands, as does a
+    /// `PerPlatform` with nothing for `platform.os`.
+    pub fn to_vec_on(&self, platform: &Platform) -> Vec<String> {
         match self {
             Cmd::Single(s) => vec![s.clone()],
             Cmd::Multiple(v) => v.clone(),
+            Cmd::Edit { edit } => {
+                let editor = std::env::var("EDITOR").ok();
+                vec![editor_command(edit, editor.as_deref())]
+            }
+            Cmd::Keys { keys } => vec![tmux::key_sequence_command(keys)],
+            Cmd::When { cmd, when } => match condition::holds(when, platform) {
+                true => cmd.to_vec_on(platform),
+                false => Vec::new(),
+            },
+            Cmd::PerPlatform(_) => self
+                .for_os(&platform.os)
+                .map_or_else(Vec::new, |cmd| cmd.to_vec_on(platform)),
+        }
+    }
+
+    /// The entry of a `PerPlatform` table for `os`, or its `default`.
+    ///
+    /// Other variants return themselves.
+    pub fn for_os(&self, os: &str) -> Option<&Cmd> {
+        match self {
+            Cmd::PerPlatform(table) => table.get(os).or_else(|| table.get("default")),
+            cmd => Some(cmd),
+        }
+    }
+
+    /// Fail if a `PerPlatform` table has neither `os` nor a `default`.
+    ///
+    /// # Errors
+    ///
+    /// [`PanoutError::InvalidConfig`] naming `what` (e.g. `bundle dev.open`).
+    pub fn check_platform(&self, os: &str, what: &str) -> PanoutResult<()> {
+        match (self, self.for_os(os)) {
+            (_, None) => Err(PanoutError::InvalidConfig(format!(
+                "{} has no command for {} (add `{} = ...` or `default = ...` to its cmd)",
+                what, os, os
+            ))),
+            (Cmd::When { cmd, .. }, _) => cmd.check_platform(os, what),
+            (Cmd::PerPlatform(_), Some(cmd)) => cmd.check_platform(os, what),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether any of the forms here presses `keys`, including ones under a
+    /// `when` or a platform table.
+    pub fn has_keys(&self) -> bool {
+        match self {
+            Cmd::Keys { .. } => true,
+            Cmd::When { cmd, .. } => cmd.has_keys(),
+            Cmd::PerPlatform(table) => table.values().any(Cmd::has_keys),
+            _ => false,
+        }
+    }
+
+    /// Whether this is an explicitly empty command list (`cmd = []`).
+    ///
+    /// Unlike checking [`to_vec`](Self::to_vec), a `When` whose condition
+    /// fails here doesn't count.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Cmd::Multiple(v) => v.is_empty(),
+            Cmd::When { cmd, .. } => cmd.is_empty(),
+            Cmd::PerPlatform(table) => !table.is_empty() && table.values().all(Cmd::is_empty),
+            _ => false,
+        }
+    }
+
+    /// Apply `f` to every command string (or the `edit` file), keeping the variant.
+    ///
+    /// Key names are left alone.
+    pub fn try_map<F>(&self, f: F) -> PanoutResult<Cmd>
+    where
+        F: Fn(&str) -> PanoutResult<String>,
+    {
+        self.try_map_dyn(&f)
+    }
+
+    fn try_map_dyn(&self, f: &dyn Fn(&str) -> PanoutResult<String>) -> PanoutResult<Cmd> {
+        Ok(match self {
+            Cmd::Single(s) => Cmd::Single(f(s)?),
+            Cmd::Multiple(v) => Cmd::Multiple(v.iter().map(|s| f(s)).collect::<PanoutResult<_>>()?),
+            Cmd::Edit { edit } => Cmd::Edit { edit: f(edit)? },
+            Cmd::Keys { keys } => Cmd::Keys { keys: keys.clone() },
+            Cmd::When { cmd, when } => Cmd::When {
+                cmd: Box::new(cmd.try_map_dyn(f)?),
+                when: when.clone(),
+            },
+            Cmd::PerPlatform(table) => Cmd::PerPlatform(
+                table
+                    .iter()
+                    .map(|(os, cmd)| Ok((os.clone(), cmd.try_map_dyn(f)?)))
+                    .collect::<PanoutResult<_>>()?,
+            ),
+        })
+    }
+}
+
+/// Build the command that opens `file` in `editor`, falling back to `vi`.
+///
+/// An unset or blank editor uses the fallback. The file is
+/// [quoted](shell::quote_path), keeping a leading `~` for the shell.
+pub fn editor_command(file: &str, editor: Option<&str>) -> String {
+    let editor = editor
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .unwrap_or("vi");
+    format!("{} {}", editor, shell::quote_path(file))
+}
+
+/// Size of a single pane, used when splitting it off.
+///
+/// Written as a percentage of the window (`size = 70` or `size = "70%"`) or a
+/// number of terminal cells (`size = { cells = 10 }`), which become
+/// `split-window -p` and `-l` respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawPaneSize")]
+pub enum PaneSize {
+    /// Percentage of the window; clamped to 1-99 when splitting.
+    Percent(u8),
+    /// Lines or columns, depending on the split direction.
+    Cells(u32),
+}
+
+/// The accepted spellings of a [`PaneSize`].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawPaneSize {
+    Percent(u8),
+    Text(String),
+    Cells { cells: u32 },
+}
+
+impl TryFrom<RawPaneSize> for PaneSize {
+    type Error = String;
+
+    fn try_from(raw: RawPaneSize) -> Result<Self, Self::Error> {
+        match raw {
+            RawPaneSize::Percent(p) => Ok(PaneSize::Percent(p)),
+            RawPaneSize::Cells { cells } => Ok(PaneSize::Cells(cells)),
+            RawPaneSize::Text(text) => text
+                .strip_suffix('%')
+                .and_then(|p| p.trim().parse().ok())
+                .map(PaneSize::Percent)
+                .ok_or_else(|| format!("invalid pane size '{}', expected e.g. \"70%\"", text)),
+        }
+    }
+}
+
+impl Serialize for PaneSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PaneSize::Percent(p) => serializer.serialize_u8(*p),
+            PaneSize::Cells(cells) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("cells", cells)?;
+                map.end()
+            }
         }
     }
 }
@@ -65,8 +274,10 @@ impl Cmd {
 /// - `Tiled`: Spread panes evenly in both directions
 /// - `Vertical`: Side-by-side panes (tmux's "even-horizontal")
 /// - `Horizontal`: Stacked panes (tmux's "even-vertical")
-#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
-#[serde(rename_all = "lowercase")]
+/// - `MainVertical`: One large pane on the left, the rest stacked on the right
+/// - `MainHorizontal`: One large pane on top, the rest side by side below
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
 pub enum Layout {
     /// Spread panes evenly (tmux: "tiled").
     #[default]
@@ -75,6 +286,10 @@ pub enum Layout {
     Vertical,
     /// Stacked panes (tmux: "even-vertical").
     Horizontal,
+    /// Large left pane, others stacked beside it (tmux: "main-vertical").
+    MainVertical,
+    /// Large top pane, others side by side below it (tmux: "main-horizontal").
+    MainHorizontal,
 }
 
 impl Layout {
@@ -84,15 +299,253 @@ impl Layout {
             Layout::Tiled => "tiled",
             Layout::Vertical => "even-horizontal",
             Layout::Horizontal => "even-vertical",
+            Layout::MainVertical => "main-vertical",
+            Layout::MainHorizontal => "main-horizontal",
+        }
+    }
+
+    /// The name used in the config and for `--layout`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Tiled => "tiled",
+            Layout::Vertical => "vertical",
+            Layout::Horizontal => "horizontal",
+            Layout::MainVertical => "main-vertical",
+            Layout::MainHorizontal => "main-horizontal",
+        }
+    }
+}
+
+/// Direction of each `split-window` while panes are created.
+///
+/// Named after tmux's own flags, which are the reverse of [`Layout`]'s names:
+/// - `Horizontal`: new pane beside the split one (`split-window -h`)
+/// - `Vertical`: new pane below the split one (`split-window -v`)
+///
+/// The final layout still decides the geometry; the direction only matters
+/// for layouts that keep it, such as sized splits.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitDir {
+    /// Side by side (`-h`).
+    Horizontal,
+    /// Stacked (`-v`).
+    Vertical,
+}
+
+impl SplitDir {
+    /// The `split-window` flag for this direction.
+    pub fn to_tmux_flag(&self) -> &'static str {
+        match self {
+            SplitDir::Horizontal => "-h",
+            SplitDir::Vertical => "-v",
         }
     }
 }
 
 /// Global default settings applied when not overridden.
-#[derive(Debug, Deserialize, Default, Clone)]
+///
+/// # Example
+///
+/// ```toml
+/// [defaults]
+/// layout = "tiled"
+///
+/// [defaults.layout_per_count]
+/// 2 = "vertical"
+/// 4 = "tiled"
+/// ```
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct Defaults {
     /// Default layout for panes when not specified elsewhere.
     pub layout: Option<Layout>,
+    /// Layout to use for a given pane count, consulted before `layout`.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_count_map",
+        serialize_with = "serialize_sorted",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub layout_per_count: HashMap<u32, Layout>,
+    /// Raw tmux layout for bundles and windows without their own
+    /// `layout_string` (see [`BundleEntry::layout_string`]).
+    #[serde(default)]
+    pub layout_string: Option<String>,
+    /// Enable every strict check (same as `--strict`).
+    #[serde(default)]
+    pub strict: Option<bool>,
+    /// Local setup commands run before any bundle creates panes.
+    #[serde(default)]
+    pub before: Option<Cmd>,
+    /// Entry a bare `@group` reference resolves to.
+    ///
+    /// When unset, `default` is tried first, then `main`.
+    #[serde(default)]
+    pub default_entry: Option<String>,
+    /// Shell started after `cd` in workspace panes that connect over SSH.
+    ///
+    /// See [`Workspace::shell`].
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// Ask before a run that creates more than this many panes.
+    ///
+    /// Unset means never ask; `--yes` skips the prompt.
+    #[serde(default)]
+    pub confirm_threshold: Option<u32>,
+    /// Width of the large pane in `main-vertical` layouts.
+    #[serde(default)]
+    pub main_pane_width: Option<PaneSize>,
+    /// Height of the large pane in `main-horizontal` layouts.
+    #[serde(default)]
+    pub main_pane_height: Option<PaneSize>,
+    /// Text put before every bundle command, for bundles without a `prefix`.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Text put after every bundle command, for bundles without a `suffix`.
+    #[serde(default)]
+    pub suffix: Option<String>,
+    /// Milliseconds to wait after creating panes, before sending them
+    /// anything, for shells that are slow to start.
+    #[serde(default)]
+    pub settle_ms: Option<u64>,
+}
+
+/// Which strict checks are enabled.
+///
+/// Everything is off by default; `--strict` or `[defaults] strict = true`
+/// turns on every check via [`Strictness::all`]. The checks are:
+///
+/// - `empty_bundles`: a bundle with no commands is an error
+/// - `empty_commands`: a blank command string is an error
+/// - `pane_conflicts`: two bundles setting the same `pane` in one run,
+///   without sharing a `role`, is an error
+/// - `warnings`: anything reported by [`Config::warnings`] is an error,
+///   including a misspelled top-level key like `[default]`
+/// - `git_root`: `{git_root}` outside a git repository is an error
+///
+/// Unknown fields inside a bundle, server, workspace, window, pane, or
+/// `[defaults]` are rejected while parsing, strict or not.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Strictness {
+    /// Reject bundles whose `cmd` is empty.
+    pub empty_bundles: bool,
+    /// Reject blank command strings.
+    pub empty_commands: bool,
+    /// Reject bundles that set the same `pane` as another in one run.
+    pub pane_conflicts: bool,
+    /// Promote config warnings to errors.
+    pub warnings: bool,
+    /// Reject `{git_root}` when not inside a git repository.
+    pub git_root: bool,
+}
+
+impl Strictness {
+    /// Every strict check enabled.
+    pub fn all() -> Self {
+        Strictness {
+            empty_bundles: true,
+            empty_commands: true,
+            pane_conflicts: true,
+            warnings: true,
+            git_root: true,
+        }
+    }
+}
+
+/// Deserialize a table keyed by pane count.
+///
+/// TOML table keys are always strings, so `2 = "vertical"` arrives as `"2"`
+/// and has to be parsed into a number here.
+fn deserialize_count_map<'de, D>(deserializer: D) -> Result<HashMap<u32, Layout>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw: HashMap<String, Layout> = HashMap::deserialize(deserializer)?;
+    raw.into_iter()
+        .map(|(key, layout)| {
+            key.parse::<u32>()
+                .map(|count| (count, layout))
+                .map_err(|_| serde::de::Error::custom(format!("invalid pane count '{}'", key)))
+        })
+        .collect()
+}
+
+/// Deserialize a list of strings that may also be written as one string.
+fn deserialize_one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(path) => vec![path],
+        OneOrMany::Many(paths) => paths,
+    })
+}
+
+/// Serialize a map with its keys in sorted order.
+///
+/// `HashMap` iteration order changes between runs; sorting keeps printed
+/// configs stable so they can be diffed. Keys are written as strings, as
+/// TOML requires.
+fn serialize_sorted<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + ToString,
+    V: Serialize,
+    S: Serializer,
+{
+    let sorted: BTreeMap<&K, &V> = map.iter().collect();
+    let mut out = serializer.serialize_map(Some(sorted.len()))?;
+    for (key, value) in sorted {
+        out.serialize_entry(&key.to_string(), value)?;
+    }
+    out.end()
+}
+
+impl Defaults {
+    /// Overlay `other`: each of its fields that is set replaces this one's,
+    /// and its `layout_per_count` entries replace those for the same count.
+    pub fn merge(&mut self, other: Defaults) {
+        fn set<T>(field: &mut Option<T>, value: Option<T>) {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+        set(&mut self.layout, other.layout);
+        self.layout_per_count.extend(other.layout_per_count);
+        set(&mut self.layout_string, other.layout_string);
+        set(&mut self.strict, other.strict);
+        set(&mut self.before, other.before);
+        set(&mut self.default_entry, other.default_entry);
+        set(&mut self.shell, other.shell);
+        set(&mut self.confirm_threshold, other.confirm_threshold);
+        set(&mut self.main_pane_width, other.main_pane_width);
+        set(&mut self.main_pane_height, other.main_pane_height);
+        set(&mut self.prefix, other.prefix);
+        set(&mut self.suffix, other.suffix);
+        set(&mut self.settle_ms, other.settle_ms);
+    }
+
+    /// Pick the default layout for a window with `count` panes.
+    ///
+    /// Checks `layout_per_count` first, then falls back to `layout`.
+    pub fn layout_for(&self, count: u32) -> Option<Layout> {
+        self.layout_per_count.get(&count).copied().or(self.layout)
+    }
+
+    /// Entry names a bare `@group` reference may resolve to, in order.
+    pub fn default_entries(&self) -> Vec<&str> {
+        match self.default_entry {
+            Some(ref name) => vec![name.as_str()],
+            None => vec!["default", "main"],
+        }
+    }
 }
 
 /// A single bundle entry defining commands for a pane.
@@ -108,19 +561,112 @@ pub struct Defaults {
 /// pane = 0
 /// layout = "vertical"
 /// ```
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct BundleEntry {
     /// Commands to execute. Can reference other bundles with `@group.name`.
     pub cmd: Cmd,
+    /// Short summary shown next to the bundle in `--list` (alias `desc`).
+    #[serde(default, alias = "desc")]
+    pub description: Option<String>,
+    /// Labels for running or listing bundles together with `--tag`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Bundles (or aliases) this one starts after when a `@group.*` or
+    /// `@group.each` reference expands them together.
+    ///
+    /// Accepts one path (`after = "dev.db"`) or a list.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub after: Vec<String>,
+    /// Only run on matching machines; elsewhere the bundle is skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<BundleWhen>,
     /// Target pane index (0-based logical index, auto-assigned if omitted).
     #[serde(default)]
     pub pane: Option<u32>,
-    /// Optional role identifier (e.g., "primary", "secondary").
+    /// Role name (e.g., "logs"); bundles with the same role share a pane.
+    ///
+    /// The first bundle resolved with a role fixes its pane, from its own
+    /// `pane` or the default; later bundles with that role join it, even if
+    /// they set a different `pane`.
     #[serde(default)]
     pub role: Option<String>,
     /// Layout override for this bundle.
     #[serde(default)]
     pub layout: Option<Layout>,
+    /// A raw tmux layout, as printed by `tmux list-windows -F '#{window_layout}'`.
+    ///
+    /// Applied once all panes exist, replacing `layout` (which is still used
+    /// while splitting). `-v`/`-H`/`--layout` take precedence over it.
+    #[serde(default)]
+    pub layout_string: Option<String>,
+    /// Text put before each of this bundle's own commands, e.g. `nix develop -c`.
+    ///
+    /// Overrides `[defaults] prefix`; `""` turns it off. Commands pulled in
+    /// with `@ref`s get their own bundle's prefix instead.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Text put after each of this bundle's own commands, e.g. `; read`.
+    ///
+    /// Works like `prefix`.
+    #[serde(default)]
+    pub suffix: Option<String>,
+    /// Milliseconds to wait between successive commands sent to the pane.
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    /// Pane to select (0-based logical index) once setup is complete.
+    #[serde(default)]
+    pub focus: Option<u32>,
+    /// Working directory for the pane(s) the bundle runs in.
+    ///
+    /// When the bundle is referenced, its pane starts here; the referencing
+    /// bundle's `dir` covers panes without one.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Environment variables exported in each pane before the commands run.
+    #[serde(
+        default,
+        serialize_with = "serialize_sorted",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub env: HashMap<String, String>,
+    /// Local setup commands run once before panes are created (alias `pre`).
+    ///
+    /// Runs after `[defaults] before`. A failing command aborts the run.
+    #[serde(default, alias = "pre")]
+    pub before: Option<Cmd>,
+    /// Local commands run once the panes are set up.
+    ///
+    /// A failing command is reported as a warning; the run still succeeds.
+    #[serde(default)]
+    pub post: Option<Cmd>,
+    /// Commands for `tmux run-shell` once panes are created and commands sent.
+    #[serde(default)]
+    pub run_shell: Option<Cmd>,
+    /// Title shown in the border of the pane running this bundle's commands.
+    ///
+    /// Only visible when tmux's `pane-border-status` option is on.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Mirror keystrokes across every pane of the bundle's window once commands are sent.
+    #[serde(default)]
+    pub synchronize: bool,
+    /// Size of this bundle's pane.
+    ///
+    /// Percentages leave what's left to share evenly among panes without a
+    /// `size`. Setting any size replaces the layout with sized splits, unless
+    /// `-v`/`-H` forces an even layout.
+    #[serde(default)]
+    pub size: Option<PaneSize>,
+    /// Direction of each split while the bundle's panes are created.
+    ///
+    /// Unset leaves the choice to tmux (no `-h`/`-v` flag).
+    #[serde(default)]
+    pub split_direction: Option<SplitDir>,
 }
 
 /// SSH server configuration for remote connections.
@@ -130,37 +676,303 @@ pub struct BundleEntry {
 /// ```toml
 /// [servers.prod]
 /// host = "admin@192.168.1.100"
+/// port = 2222
+/// identity = "~/.ssh/work_ed25519"
+/// options = ["StrictHostKeyChecking=accept-new"]
 /// disconnect = true
 /// cmd = "cd /var/log && tail -f app.log"
 /// ```
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ServerConfig {
     /// SSH host in `user@ip` format.
     pub host: String,
-    /// Whether to disconnect (send "exit") after commands complete.
+    /// SSH port (`ssh -p`).
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Identity file (`ssh -i`); `~` is expanded.
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// Extra `ssh -o` options, e.g. `"StrictHostKeyChecking=accept-new"`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    /// Whether to disconnect after commands complete.
+    ///
+    /// The commands are sent as one `cmd1 && cmd2; exit` line so the remote
+    /// shell exits only once they finish.
     #[serde(default)]
     pub disconnect: bool,
     /// Commands to run after connecting.
     #[serde(default)]
     pub cmd: Option<Cmd>,
+    /// Short summary shown next to the server in `--list` (alias `desc`).
+    #[serde(default, alias = "desc")]
+    pub description: Option<String>,
+}
+
+/// How a window's `cmd` list maps onto its panes.
+///
+/// - `broadcast` (default): every pane runs every command, in order
+/// - `round_robin`: pane `i` runs command `i % len`, so with 6 panes and
+///   `["a", "b"]` panes 0, 2, 4 run `a` and 1, 3, 5 run `b`
+/// - `positional`: pane `i` runs command `i`; panes past the end of the list
+///   run nothing
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributeMode {
+    /// Send every command to every pane.
+    #[default]
+    Broadcast,
+    /// Cycle through the commands, one per pane.
+    RoundRobin,
+    /// One command per pane, by position.
+    Positional,
+}
+
+/// The panes of a workspace window: either a count or one entry per pane.
+///
+/// ```toml
+/// panes = 3
+/// # or
+/// panes = [{ cmd = "nvim ." }, { cmd = "cargo watch -x test", dir = "~/src/api" }]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Panes {
+    /// A number of identical panes.
+    Count(u32),
+    /// One definition per pane, in pane order.
+    List(Vec<PaneDef>),
+}
+
+/// Dispatch on the TOML type rather than trying each variant, so a bad pane
+/// entry reports its own error (such as an unknown field) instead of
+/// "did not match any variant".
+impl<'de> Deserialize<'de> for Panes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct PanesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PanesVisitor {
+            type Value = Panes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a pane count or a list of panes")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, n: i64) -> Result<Panes, E> {
+                u32::try_from(n)
+                    .map(Panes::Count)
+                    .map_err(|_| E::custom(format!("invalid pane count {}", n)))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, n: u64) -> Result<Panes, E> {
+                self.visit_i64(i64::try_from(n).unwrap_or(-1))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Panes, A::Error> {
+                Vec::deserialize(serde::de::value::SeqAccessDeserializer::new(seq)).map(Panes::List)
+            }
+        }
+
+        deserializer.deserialize_any(PanesVisitor)
+    }
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Panes::Count(0)
+    }
+}
+
+impl Panes {
+    /// Whether there are no panes, as when `panes` is left out.
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Number of panes.
+    pub fn count(&self) -> u32 {
+        match self {
+            Panes::Count(n) => *n,
+            Panes::List(panes) => panes.len() as u32,
+        }
+    }
+
+    /// Definition of logical pane `pane`, if the panes were listed.
+    pub fn get(&self, pane: usize) -> Option<&PaneDef> {
+        match self {
+            Panes::Count(_) => None,
+            Panes::List(panes) => panes.get(pane),
+        }
+    }
+}
+
+/// One pane of a window declared with a pane list.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaneDef {
+    /// Commands for this pane only, run after the window's `cmd`.
+    #[serde(default)]
+    pub cmd: Option<Cmd>,
+    /// Working directory; overrides the window and workspace `dir`.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Pane border title; overrides the window `title`.
+    #[serde(default, alias = "name")]
+    pub title: Option<String>,
+    /// Size of this pane (see [`PaneSize`]).
+    #[serde(default)]
+    pub size: Option<PaneSize>,
+}
+
+/// The value of a window's `focus`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum WindowFocus {
+    /// `focus = true`: select this window once the workspace is built.
+    Window(bool),
+    /// `focus = N`: select logical pane `N` of this window.
+    Pane(u32),
 }
 
 /// A window definition within a workspace.
 ///
 /// Each window in a workspace can have its own pane count, layout, and commands.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct WindowDef {
-    /// Number of panes to create in this window.
-    pub panes: u32,
+    /// Panes to create in this window: a count, or a list of [`PaneDef`]s.
+    ///
+    /// May be left out when `hosts` is set, which then gives the count.
+    #[serde(default, skip_serializing_if = "Panes::is_empty")]
+    pub panes: Panes,
     /// Layout for panes (defaults to workspace default or tiled).
     #[serde(default)]
     pub layout: Option<Layout>,
+    /// A raw tmux layout applied once the window's panes exist (see
+    /// [`BundleEntry::layout_string`]).
+    #[serde(default)]
+    pub layout_string: Option<String>,
     /// Commands to run in each pane of this window.
     #[serde(default)]
     pub cmd: Option<Cmd>,
     /// Optional tmux window name.
     #[serde(default)]
     pub name: Option<String>,
+    /// `true` to end the run on this window; a number selects that pane, like
+    /// `focus_pane`.
+    ///
+    /// At most one window in a workspace may set `focus = true`.
+    #[serde(default)]
+    pub focus: Option<WindowFocus>,
+    /// Pane to select (0-based logical index) once the window is set up.
+    #[serde(default)]
+    pub focus_pane: Option<u32>,
+    /// Environment variables exported in each pane before `cmd` runs.
+    #[serde(
+        default,
+        serialize_with = "serialize_sorted",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub env: HashMap<String, String>,
+    /// Title shown in the border of each pane in this window.
+    ///
+    /// Only visible when tmux's `pane-border-status` option is on.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Mirror keystrokes across the window's panes once commands are sent.
+    #[serde(default)]
+    pub synchronize: bool,
+    /// How `cmd` is spread across the panes (default: broadcast).
+    #[serde(default)]
+    pub distribute: Option<DistributeMode>,
+    /// SSH host for this window's panes; overrides the workspace `host`.
+    ///
+    /// [`LOCAL_HOST`] (`"local"`) keeps the window on this machine even when
+    /// the workspace has a host.
+    #[serde(default)]
+    pub host: Option<String>,
+    /// One SSH host per pane, e.g. `["ops@10.0.0.1", "ops@10.0.0.2"]`:
+    /// pane `i` connects to `hosts[i]`. Overrides `host`.
+    ///
+    /// Entries may also name `[servers.*]`, and `"local"` keeps a pane on
+    /// this machine.
+    #[serde(default)]
+    pub hosts: Option<Vec<String>>,
+    /// Working directory for this window's panes; overrides the workspace `dir`.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Relative size of each pane, e.g. `[60, 20, 20]`; one entry per pane.
+    ///
+    /// Replaces the layout with sized splits.
+    #[serde(default)]
+    pub sizes: Option<Vec<u32>>,
+    /// Direction of each split while the window's panes are created.
+    #[serde(default)]
+    pub split_direction: Option<SplitDir>,
+}
+
+impl WindowDef {
+    /// Number of panes in this window: from `panes`, else one per host.
+    pub fn pane_count(&self) -> u32 {
+        match self.hosts {
+            Some(ref hosts) if self.panes.is_empty() => hosts.len() as u32,
+            _ => self.panes.count(),
+        }
+    }
+
+    /// The commands for logical pane `pane`.
+    ///
+    /// The window's `cmd` is spread per [`DistributeMode`], followed by the
+    /// pane's own `cmd` when the panes are listed.
+    pub fn commands_for_pane(&self, pane: usize) -> Vec<String> {
+        let cmds = self.cmd.as_ref().map(Cmd::to_vec).unwrap_or_default();
+        let mut cmds = match self.distribute.unwrap_or_default() {
+            DistributeMode::Broadcast => cmds,
+            DistributeMode::RoundRobin if cmds.is_empty() => cmds,
+            DistributeMode::RoundRobin => vec![cmds[pane % cmds.len()].clone()],
+            DistributeMode::Positional => cmds.into_iter().skip(pane).take(1).collect(),
+        };
+        if let Some(own) = self.panes.get(pane).and_then(|p| p.cmd.as_ref()) {
+            cmds.extend(own.to_vec());
+        }
+        cmds
+    }
+
+    /// Per-pane sizes, if any pane in the list sets one.
+    pub fn pane_sizes(&self) -> Option<Vec<Option<PaneSize>>> {
+        match &self.panes {
+            Panes::List(panes) if panes.iter().any(|p| p.size.is_some()) => {
+                Some(panes.iter().map(|p| p.size).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the run should end on this window (`focus = true`).
+    pub fn is_focused(&self) -> bool {
+        self.focus == Some(WindowFocus::Window(true))
+    }
+
+    /// Pane to select once the window is set up: `focus_pane`, else a
+    /// numeric `focus`.
+    pub fn focused_pane(&self) -> Option<u32> {
+        self.focus_pane.or(match self.focus {
+            Some(WindowFocus::Pane(pane)) => Some(pane),
+            _ => None,
+        })
+    }
+
+    /// Border title for logical pane `pane`: the pane's own, else the window's.
+    pub fn pane_title(&self, pane: usize) -> Option<&str> {
+        self.panes
+            .get(pane)
+            .and_then(|p| p.title.as_deref())
+            .or(self.title.as_deref())
+    }
 }
 
 /// A workspace with multiple windows, optionally connected via SSH.
@@ -179,56 +991,466 @@ pub struct WindowDef {
 ///     { panes = 4 },                        # Window 2: 4 tiled panes
 /// ]
 /// ```
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Workspace {
+    /// Short summary shown next to the workspace in `--list` (alias `desc`).
+    #[serde(default, alias = "desc")]
+    pub description: Option<String>,
     /// SSH host (`user@ip`). If set, each pane will SSH to this host.
     #[serde(default)]
     pub host: Option<String>,
-    /// Base directory. Combined with `host`, creates: `ssh -t host "cd dir && exec $SHELL -l"`
+    /// Base directory. Combined with `host`, creates: `ssh -t host 'cd dir && exec $SHELL -l'`
     #[serde(default)]
     pub dir: Option<String>,
+    /// Shell to `exec` after `cd` on the host, instead of the login `$SHELL`.
+    ///
+    /// [`NO_SHELL`] (`"none"`) runs `$SHELL` without `exec` or `-l`, for
+    /// hosts without login-shell support. Overrides `[defaults] shell`.
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// Layout for windows that don't set their own (also accepted as `layout`).
+    #[serde(default, alias = "layout")]
+    pub default_layout: Option<Layout>,
+    /// Close the remote shell of each SSH pane once its commands finish.
+    ///
+    /// Only applies when `host` is set. Instead of attaching a persistent
+    /// remote tmux session, each pane connects and runs the window commands
+    /// chained with `exit` (`cmd1 && cmd2; exit`).
+    #[serde(default)]
+    pub disconnect: Option<bool>,
+    /// Seconds to wait for SSH to connect (`-o ConnectTimeout`) before giving up.
+    #[serde(default)]
+    pub connect_timeout: Option<u32>,
+    /// Seconds between SSH keepalives (`-o ServerAliveInterval`), so a dead
+    /// connection is noticed instead of hanging.
+    #[serde(default)]
+    pub server_alive_interval: Option<u32>,
+    /// Commands for `tmux run-shell` once every window is set up.
+    #[serde(default)]
+    pub run_shell: Option<Cmd>,
+    /// Build the windows in their own tmux session with this name.
+    ///
+    /// The session is created detached if needed, reused (windows are added)
+    /// if it already exists, and switched to at the end. For workspaces that
+    /// attach to a remote session, this names the remote session instead.
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Kill and rebuild `session` if it already exists, instead of reusing it.
+    #[serde(default)]
+    pub recreate: bool,
+    /// End on the last window created instead of returning to the starting
+    /// one, when no window sets `focus = true`.
+    #[serde(default)]
+    pub stay: bool,
+    /// Local setup commands run before any window is created (alias `pre`).
+    ///
+    /// A failing command aborts the run.
+    #[serde(default, alias = "pre")]
+    pub before: Option<Cmd>,
+    /// Local commands run once every window is set up.
+    ///
+    /// A failing command is reported as a warning; the run still succeeds.
+    #[serde(default)]
+    pub post: Option<Cmd>,
+    /// Environment variables exported in every pane; windows override keys.
+    #[serde(
+        default,
+        serialize_with = "serialize_sorted",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub env: HashMap<String, String>,
     /// Window definitions for this workspace.
     pub windows: Vec<WindowDef>,
 }
 
-/// Top-level configuration structure.
-///
-/// Parsed from `~/.config/panout/config.toml` (or XDG equivalent).
-/// Reserved keys are `defaults`, `servers`, and `workspace`.
-/// All other top-level keys are treated as bundle groups.
-#[derive(Debug, Default)]
-pub struct Config {
-    /// Global default settings.
-    pub defaults: Defaults,
-    /// Named SSH server configurations.
-    pub servers: HashMap<String, ServerConfig>,
-    /// Bundle groups: `group_name` -> `entry_name` -> `BundleEntry`.
-    pub bundles: HashMap<String, HashMap<String, BundleEntry>>,
-    /// Named workspaces for multi-window configurations.
+impl Workspace {
+    /// Extra `ssh` options from `connect_timeout` and `server_alive_interval`.
+    ///
+    /// Empty unless one of them is set.
+    pub fn ssh_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if let Some(secs) = self.connect_timeout {
+            options.push(format!("-o ConnectTimeout={}", secs));
+        }
+        if let Some(secs) = self.server_alive_interval {
+            options.push(format!("-o ServerAliveInterval={}", secs));
+        }
+        options
+    }
+
+    /// Resolve the layout for one of this workspace's windows.
+    ///
+    /// Precedence: window `layout` > workspace `default_layout` > `defaults`
+    /// (the layout for the window's pane count, then the plain `layout`) >
+    /// tiled.
+    pub fn window_layout(&self, win: &WindowDef, defaults: &Defaults) -> Layout {
+        win.layout
+            .or(self.default_layout)
+            .or_else(|| defaults.layout_for(win.pane_count()))
+            .unwrap_or(Layout::Tiled)
+    }
+
+    /// Raw tmux layout for one of this workspace's windows, if any.
+    ///
+    /// Precedence: window `layout_string` > `defaults` `layout_string`.
+    pub fn window_layout_string<'a>(
+        &self,
+        win: &'a WindowDef,
+        defaults: &'a Defaults,
+    ) -> Option<&'a str> {
+        win.layout_string
+            .as_deref()
+            .or(defaults.layout_string.as_deref())
+    }
+
+    /// Environment for one of this workspace's windows.
+    ///
+    /// The workspace `env` with the window's `env` taking precedence.
+    pub fn window_env(&self, win: &WindowDef) -> HashMap<String, String> {
+        let mut env = self.env.clone();
+        env.extend(win.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        env
+    }
+
+    /// Resolve the SSH host for one of this workspace's windows.
+    ///
+    /// Precedence: window `host` > workspace `host`. Returns `None` for
+    /// windows that run locally, including those with `host = "local"`.
+    pub fn window_host<'a>(&'a self, win: &'a WindowDef) -> Option<&'a str> {
+        match win.host.as_deref() {
+            Some(LOCAL_HOST) => None,
+            Some(host) => Some(host),
+            None => self.host.as_deref(),
+        }
+    }
+
+    /// Resolve the SSH host for logical pane `pane` of a window.
+    ///
+    /// Precedence: the window's `hosts` entry for the pane > window `host` >
+    /// workspace `host`. Returns `None` for panes that run locally.
+    pub fn pane_host<'a>(&'a self, win: &'a WindowDef, pane: usize) -> Option<&'a str> {
+        match win.hosts {
+            Some(ref hosts) => hosts
+                .get(pane)
+                .map(String::as_str)
+                .filter(|h| *h != LOCAL_HOST),
+            None => self.window_host(win),
+        }
+    }
+
+    /// The SSH target for logical pane `pane` of a window: its
+    /// [host](Self::pane_host), with `@servers.*` references
+    /// [resolved](ssh::resolve_target) against `config`.
+    ///
+    /// # Errors
+    ///
+    /// [`PanoutError::ServerNotFound`] for a reference to a missing server.
+    pub fn pane_target(
+        &self,
+        config: &Config,
+        win: &WindowDef,
+        pane: usize,
+    ) -> PanoutResult<Option<SshTarget>> {
+        self.pane_host(win, pane)
+            .map(|host| ssh::resolve_target(config, host))
+            .transpose()
+    }
+
+    /// Fail if a pane would send `{user}`/`{host}` tokens (in its commands
+    /// or directory) over a connection to a host without a `user@` part.
+    ///
+    /// `name` is the workspace name, used in the error; hosts are
+    /// [resolved](Self::pane_target) against `config`.
+    ///
+    /// # Errors
+    ///
+    /// [`PanoutError::InvalidConfig`] naming the window, pane, and host, or
+    /// [`PanoutError::ServerNotFound`] for a missing server.
+    pub fn check_host_tokens(&self, config: &Config, name: &str) -> PanoutResult<()> {
+        for (i, win) in self.windows.iter().enumerate() {
+            for pane in 0..win.pane_count() as usize {
+                let Some(target) = self.pane_target(config, win, pane)? else {
+                    continue;
+                };
+                let host = target.host;
+                if interpolate::parse_host(&host).is_some() {
+                    continue;
+                }
+                let dir = self.pane_dir(win, pane).into_iter().map(str::to_string);
+                let mut texts = win.commands_for_pane(pane).into_iter().chain(dir);
+                if texts.any(|t| interpolate::has_host_tokens(&t)) {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "workspace {} window {} pane {} uses {{user}}/{{host}} but its host \
+                         `{}` isn't `user@host`",
+                        name, i, pane, host
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Host for a single persistent remote tmux session, if this workspace
+    /// uses one.
+    ///
+    /// That's the case when the workspace has a `host`, doesn't
+    /// [disconnect](Self::disconnects), and no window picks its own host;
+    /// otherwise windows are built locally and each pane connects itself.
+    pub fn remote_session_host(&self) -> Option<&str> {
+        let overridden = self
+            .windows
+            .iter()
+            .any(|w| w.host.is_some() || w.hosts.is_some());
+        self.host
+            .as_deref()
+            .filter(|_| !self.disconnects() && !overridden)
+    }
+
+    /// Resolve the directory for one of this workspace's windows.
+    ///
+    /// Precedence: window `dir` > workspace `dir`.
+    pub fn window_dir<'a>(&'a self, win: &'a WindowDef) -> Option<&'a str> {
+        win.dir.as_deref().or(self.dir.as_deref())
+    }
+
+    /// Resolve the directory for logical pane `pane` of a window.
+    ///
+    /// Precedence: pane `dir` > window `dir` > workspace `dir`.
+    pub fn pane_dir<'a>(&'a self, win: &'a WindowDef, pane: usize) -> Option<&'a str> {
+        win.panes
+            .get(pane)
+            .and_then(|p| p.dir.as_deref())
+            .or_else(|| self.window_dir(win))
+    }
+
+    /// A copy with `[vars]` placeholders expanded in every `dir` and `cmd`.
+    ///
+    /// `name` is the workspace name, used in errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::UnknownVariable`] for a placeholder that isn't
+    /// in `vars` (see [`interpolate::expand_vars`]).
+    pub fn with_vars(&self, vars: &HashMap<String, String>, name: &str) -> PanoutResult<Workspace> {
+        let expand = |s: &str| interpolate::expand_vars(s, vars, &format!("workspace {}", name));
+        let expand_dir = |dir: &Option<String>| dir.as_deref().map(expand).transpose();
+        let expand_cmd = |cmd: &Option<Cmd>| cmd.as_ref().map(|c| c.try_map(expand)).transpose();
+
+        let mut workspace = self.clone();
+        workspace.dir = expand_dir(&self.dir)?;
+        for win in &mut workspace.windows {
+            win.dir = expand_dir(&win.dir)?;
+            win.cmd = expand_cmd(&win.cmd)?;
+            if let Panes::List(ref mut panes) = win.panes {
+                for pane in panes {
+                    pane.dir = expand_dir(&pane.dir)?;
+                    pane.cmd = expand_cmd(&pane.cmd)?;
+                }
+            }
+        }
+        Ok(workspace)
+    }
+
+    /// Keep only the windows matched by `spec`, preserving workspace order.
+    ///
+    /// `spec` is a comma-separated list of window indices (`0,2`), inclusive
+    /// index ranges (`0-2`), or window names (`build,logs`), freely mixed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::WindowNotFound`] for an out-of-range index or
+    /// unknown name, so nothing is created for a partially valid filter.
+    pub fn filter_windows(&self, spec: &str) -> PanoutResult<Workspace> {
+        let count = self.windows.len();
+        let mut keep = vec![false; count];
+
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let range = item
+                .split_once('-')
+                .and_then(|(a, b)| Some((a.parse::<usize>().ok()?, b.parse::<usize>().ok()?)));
+
+            if let Some((start, end)) = range {
+                if start > end || end >= count {
+                    return Err(PanoutError::WindowNotFound(item.to_string()));
+                }
+                keep[start..=end].iter_mut().for_each(|k| *k = true);
+            } else if let Ok(index) = item.parse::<usize>() {
+                *keep
+                    .get_mut(index)
+                    .ok_or_else(|| PanoutError::WindowNotFound(item.to_string()))? = true;
+            } else {
+                let index = self
+                    .windows
+                    .iter()
+                    .position(|w| w.name.as_deref() == Some(item))
+                    .ok_or_else(|| PanoutError::WindowNotFound(item.to_string()))?;
+                keep[index] = true;
+            }
+        }
+
+        let mut filtered = self.clone();
+        filtered.windows = self
+            .windows
+            .iter()
+            .zip(keep)
+            .filter(|(_, k)| *k)
+            .map(|(w, _)| w.clone())
+            .collect();
+        Ok(filtered)
+    }
+
+    /// Whether panes should disconnect from the host after their commands.
+    ///
+    /// Always `false` for local workspaces.
+    pub fn disconnects(&self) -> bool {
+        self.host.is_some() && self.disconnect.unwrap_or(false)
+    }
+}
+
+/// Window `host` value that opts a window out of the workspace's SSH host.
+pub const LOCAL_HOST: &str = "local";
+
+/// `shell` value that skips `exec` and starts the remote `$SHELL` plainly.
+pub const NO_SHELL: &str = "none";
+
+/// Newest config format version this build understands.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Top-level keys that aren't bundle groups.
+pub const RESERVED_KEYS: &[&str] = &[
+    "version",
+    "include",
+    "defaults",
+    "vars",
+    "aliases",
+    "servers",
+    "workspace",
+];
+
+/// What changed in each config format version, for upgrade warnings.
+///
+/// Entry `(v, note)` describes what moving up to version `v` involves.
+const VERSION_CHANGES: &[(u32, &str)] = &[(
+    1,
+    "version 1 is the first versioned format; add `version = 1` once the config works",
+)];
+
+/// Top-level configuration structure.
+///
+/// Parsed from `~/.config/panout/config.toml` (or XDG equivalent).
+/// Reserved keys are `version`, `include`, `defaults`, `vars`, `aliases`,
+/// `servers`, and `workspace`.
+/// All other top-level keys are treated as bundle groups.
+///
+/// Serializes back to the same layout, with every table's keys sorted.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    /// Config format `version`; `None` when the file doesn't declare one.
+    pub version: Option<u32>,
+    /// Other config files to load, relative to this one; globs are allowed.
+    ///
+    /// [`Config::from_str`] only records these. The
+    /// [loader](crate::loader::load_config) reads and merges them.
+    pub include: Vec<String>,
+    /// Global default settings.
+    pub defaults: Defaults,
+    /// User variables from `[vars]`, substituted as `{name}` placeholders.
+    pub vars: HashMap<String, String>,
+    /// Short bundle names from `[aliases]`: alias -> bundle path (or alias).
+    pub aliases: HashMap<String, String>,
+    /// Named SSH server configurations.
+    pub servers: HashMap<String, ServerConfig>,
+    /// Bundle groups: `group_name` -> `entry_name` -> `BundleEntry`, in the
+    /// order the config declares them.
+    pub bundles: IndexMap<String, IndexMap<String, BundleEntry>>,
+    /// Named workspaces for multi-window configurations.
     pub workspaces: HashMap<String, Workspace>,
+    /// Problems noticed while parsing, such as a likely misspelled reserved
+    /// key. Reported by [`Config::warnings`]; not part of the TOML.
+    pub parse_warnings: Vec<String>,
 }
 
-impl Config {
+impl Serialize for Config {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(version) = self.version {
+            map.serialize_entry("version", &version)?;
+        }
+        if !self.include.is_empty() {
+            map.serialize_entry("include", &self.include)?;
+        }
+        map.serialize_entry("defaults", &self.defaults)?;
+        if !self.vars.is_empty() {
+            let vars: BTreeMap<_, _> = self.vars.iter().collect();
+            map.serialize_entry("vars", &vars)?;
+        }
+        if !self.aliases.is_empty() {
+            let aliases: BTreeMap<_, _> = self.aliases.iter().collect();
+            map.serialize_entry("aliases", &aliases)?;
+        }
+        if !self.servers.is_empty() {
+            let servers: BTreeMap<_, _> = self.servers.iter().collect();
+            map.serialize_entry("servers", &servers)?;
+        }
+        if !self.workspaces.is_empty() {
+            let workspaces: BTreeMap<_, _> = self.workspaces.iter().collect();
+            map.serialize_entry("workspace", &workspaces)?;
+        }
+        let groups: BTreeMap<_, _> = self.bundles.iter().collect();
+        for (group, entries) in groups {
+            let entries: BTreeMap<_, _> = entries.iter().collect();
+            map.serialize_entry(group, &entries)?;
+        }
+        map.end()
+    }
+}
+
+impl FromStr for Config {
+    type Err = toml::de::Error;
+
     /// Parse config from a TOML string.
     ///
-    /// Reserved keys (`defaults`, `servers`, `workspace`) are parsed into their
-    /// respective fields. All other keys are treated as bundle groups.
+    /// Reserved keys (`version`, `include`, `defaults`, `vars`, `aliases`,
+    /// `servers`, `workspace`) are parsed into their respective fields. All
+    /// other keys are treated as bundle groups.
+    ///
+    /// A `version` newer than [`CONFIG_VERSION`] stops parsing there: the rest
+    /// may use fields this build doesn't know. [`Config::validate`] then
+    /// reports [`PanoutError::UnsupportedConfigVersion`].
     ///
     /// # Errors
     ///
     /// Returns `toml::de::Error` if the TOML is malformed or doesn't match
     /// the expected structure.
-    pub fn from_str(toml_str: &str) -> Result<Self, toml::de::Error> {
+    fn from_str(toml_str: &str) -> Result<Self, toml::de::Error> {
         let raw: toml::Value = toml::from_str(toml_str)?;
-        let table = raw.as_table().cloned().unwrap_or_default();
+        let mut table = raw.as_table().cloned().unwrap_or_default();
 
         let mut config = Config::default();
 
+        if let Some(version) = table.remove("version") {
+            let version: u32 = version.try_into()?;
+            config.version = Some(version);
+            if version > CONFIG_VERSION {
+                return Ok(config);
+            }
+        }
+
         for (key, value) in table {
             match key.as_str() {
+                "include" => {
+                    config.include = value.try_into()?;
+                }
                 "defaults" => {
                     config.defaults = value.try_into()?;
                 }
+                "vars" => {
+                    config.vars = value.try_into()?;
+                }
+                "aliases" => {
+                    config.aliases = value.try_into()?;
+                }
                 "servers" => {
                     config.servers = value.try_into()?;
                 }
@@ -236,7 +1458,25 @@ impl Config {
                     config.workspaces = value.try_into()?;
                 }
                 _ => {
-                    let entries: HashMap<String, BundleEntry> = value.try_into()?;
+                    let reserved = misspelled_reserved_key(&key);
+                    let parsed: Result<IndexMap<String, BundleEntry>, _> = value.try_into();
+                    let entries = match (parsed, reserved) {
+                        (Ok(entries), _) => entries,
+                        // Read as a bundle group it makes no sense: blame the typo
+                        (Err(_), Some(reserved)) => {
+                            return Err(serde::de::Error::custom(format!(
+                                "unknown top-level key `{}` (did you mean `{}`?)",
+                                key, reserved
+                            )));
+                        }
+                        (Err(e), None) => return Err(e),
+                    };
+                    if let Some(reserved) = reserved {
+                        config.parse_warnings.push(format!(
+                            "top-level key `{}` is read as a bundle group; did you mean `{}`?",
+                            key, reserved
+                        ));
+                    }
                     config.bundles.insert(key, entries);
                 }
             }
@@ -244,6 +1484,78 @@ impl Config {
 
         Ok(config)
     }
+}
+
+impl Config {
+    /// Overlay `other` on this config, as for a project-local override.
+    ///
+    /// Bundle groups with the same name merge entry by entry, and `other`'s
+    /// entry wins when both define one. Workspaces, servers, `[vars]`, and
+    /// `[aliases]` are replaced by name. `defaults` merge field by field (see
+    /// [`Defaults::merge`]). `other`'s `version` wins if it has one; its
+    /// `include` list and parse warnings are appended.
+    pub fn merge(&mut self, other: Config) {
+        if other.version.is_some() {
+            self.version = other.version;
+        }
+        self.include.extend(other.include);
+        self.defaults.merge(other.defaults);
+        self.vars.extend(other.vars);
+        self.aliases.extend(other.aliases);
+        self.servers.extend(other.servers);
+        for (group, entries) in other.bundles {
+            self.bundles.entry(group).or_default().extend(entries);
+        }
+        self.workspaces.extend(other.workspaces);
+        self.parse_warnings.extend(other.parse_warnings);
+    }
+
+    /// Render the config as TOML, with keys in a stable sorted order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::InvalidConfig`] if serialization fails.
+    pub fn to_toml_string(&self) -> PanoutResult<String> {
+        toml::to_string(self).map_err(|e| PanoutError::InvalidConfig(e.to_string()))
+    }
+
+    /// A copy with every bundle and window layout made explicit.
+    ///
+    /// Bundles without a `layout` get the one a run would use: the
+    /// `[defaults]` layout for their pane count, or tiled. Windows get
+    /// [`Workspace::window_layout`]. Both get the `[defaults]`
+    /// `layout_string` when they have none of their own.
+    pub fn with_resolved_layouts(&self) -> Config {
+        let mut resolved = self.clone();
+        for (group, entries) in resolved.bundles.iter_mut() {
+            for (name, entry) in entries.iter_mut() {
+                if entry.layout.is_none() {
+                    let path = format!("{}.{}", group, name);
+                    let count = resolver::resolve_with_panes(self, &path)
+                        .map(|panes| resolver::required_panes(&panes))
+                        .unwrap_or(1);
+                    entry.layout = Some(self.defaults.layout_for(count).unwrap_or(Layout::Tiled));
+                }
+                if entry.layout_string.is_none() {
+                    entry.layout_string = self.defaults.layout_string.clone();
+                }
+            }
+        }
+        for workspace in resolved.workspaces.values_mut() {
+            let layouts: Vec<_> = workspace
+                .windows
+                .iter()
+                .map(|win| workspace.window_layout(win, &self.defaults))
+                .collect();
+            for (win, layout) in workspace.windows.iter_mut().zip(layouts) {
+                win.layout = Some(layout);
+                if win.layout_string.is_none() {
+                    win.layout_string = self.defaults.layout_string.clone();
+                }
+            }
+        }
+        resolved
+    }
 
     /// Look up a bundle by its `group.name` path.
     ///
@@ -261,8 +1573,97 @@ impl Config {
         self.bundles.get(group).and_then(|g| g.get(name))
     }
 
-    /// Get all bundles in a group.
-    pub fn get_group(&self, group: &str) -> Option<&HashMap<String, BundleEntry>> {
+    /// The bundle path `name` stands for, following `[aliases]`.
+    ///
+    /// Names that aren't aliases are returned unchanged. An alias may point
+    /// to another alias.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::CircularRef`] if aliases point at each other.
+    pub fn resolve_alias(&self, name: &str) -> PanoutResult<String> {
+        let mut chain = vec![name];
+        let mut current = name;
+        while let Some(target) = self.aliases.get(current) {
+            if chain.contains(&target.as_str()) {
+                chain.push(target);
+                return Err(PanoutError::CircularRef(format!(
+                    "alias {}",
+                    chain.join(" -> ")
+                )));
+            }
+            chain.push(target);
+            current = target;
+        }
+        Ok(current.to_string())
+    }
+
+    /// Paths of the bundles carrying `tag`, sorted.
+    pub fn bundles_with_tag(&self, tag: &str) -> Vec<String> {
+        self.list_bundles()
+            .into_iter()
+            .filter(|path| {
+                self.get_bundle(path)
+                    .is_some_and(|b| b.tags.iter().any(|t| t == tag))
+            })
+            .collect()
+    }
+
+    /// Whether the bundle at `path` (or alias) exists but its `when` fails on
+    /// `machine`.
+    pub fn bundle_skipped(&self, path: &str, machine: &dyn Machine) -> bool {
+        let bundle = self
+            .resolve_alias(path)
+            .ok()
+            .and_then(|p| self.get_bundle(&p));
+        bundle
+            .and_then(|b| b.when.as_ref())
+            .is_some_and(|when| !when.holds(machine))
+    }
+
+    /// Aliases that lead to the bundle at `path`, sorted.
+    pub fn aliases_for(&self, path: &str) -> Vec<String> {
+        let mut aliases: Vec<String> = self
+            .aliases
+            .keys()
+            .filter(|alias| self.resolve_alias(alias).is_ok_and(|target| target == path))
+            .cloned()
+            .collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// Find the default entry of a group, for bare `@group` references.
+    ///
+    /// Returns the full `group.name` path of the first entry from
+    /// [`Defaults::default_entries`] that exists in the group.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::BundleNotFound`] if the group doesn't exist or
+    /// has no default entry; the message lists the group's entries.
+    pub fn default_bundle_path(&self, group: &str) -> PanoutResult<String> {
+        let entries = self
+            .get_group(group)
+            .ok_or_else(|| PanoutError::BundleNotFound(format!("group '{}'", group)))?;
+
+        let candidates = self.defaults.default_entries();
+        if let Some(name) = candidates.iter().find(|name| entries.contains_key(**name)) {
+            return Ok(format!("{}.{}", group, name));
+        }
+
+        let mut available: Vec<_> = entries.keys().map(String::as_str).collect();
+        available.sort();
+        Err(PanoutError::BundleNotFound(format!(
+            "group '{}' has no default entry ({}); available: {}",
+            group,
+            candidates.join(" or "),
+            available.join(", ")
+        )))
+    }
+
+    /// Get all bundles in a group, in declared order.
+    pub fn get_group(&self, group: &str) -> Option<&IndexMap<String, BundleEntry>> {
         self.bundles.get(group)
     }
 
@@ -296,4 +1697,1705 @@ impl Config {
         result.sort();
         result
     }
+
+    /// Shell for a workspace's SSH panes.
+    ///
+    /// Precedence: workspace `shell` > `[defaults] shell`. `None` means the
+    /// remote login `$SHELL`.
+    pub fn workspace_shell<'a>(&'a self, workspace: &'a Workspace) -> Option<&'a str> {
+        workspace
+            .shell
+            .as_deref()
+            .or(self.defaults.shell.as_deref())
+    }
+
+    /// Determine which strict checks apply.
+    ///
+    /// `cli_strict` (the `--strict` flag) or `[defaults] strict = true`
+    /// enables everything; otherwise all checks are off.
+    pub fn strictness(&self, cli_strict: bool) -> Strictness {
+        if cli_strict || self.defaults.strict.unwrap_or(false) {
+            Strictness::all()
+        } else {
+            Strictness::default()
+        }
+    }
+
+    /// Run the strict checks enabled in `strict` over the whole config.
+    ///
+    /// # Errors
+    ///
+    /// - [`PanoutError::UnsupportedConfigVersion`] if `version` is newer than
+    ///   [`CONFIG_VERSION`]
+    /// - [`PanoutError::InvalidConfig`] describing the first violation
+    pub fn validate(&self, strict: &Strictness) -> PanoutResult<()> {
+        if let Some(found) = self.version
+            && found > CONFIG_VERSION
+        {
+            return Err(PanoutError::UnsupportedConfigVersion {
+                found,
+                supported: CONFIG_VERSION,
+            });
+        }
+
+        let builtin = |name: &&String| interpolate::BUILTIN_TOKENS.contains(&name.as_str());
+        if let Some(name) = self.vars.keys().filter(builtin).min() {
+            return Err(PanoutError::InvalidConfig(format!(
+                "vars.{} shadows the built-in {{{}}} placeholder",
+                name, name
+            )));
+        }
+
+        let mut aliases: Vec<_> = self.aliases.keys().collect();
+        aliases.sort();
+        for alias in aliases {
+            let target = self.resolve_alias(alias)?;
+            if self.get_bundle(&target).is_none() {
+                return Err(PanoutError::InvalidConfig(format!(
+                    "alias {} points to missing bundle {}",
+                    alias, target
+                )));
+            }
+        }
+
+        for path in self.list_bundles() {
+            let Some(bundle) = self.get_bundle(&path) else {
+                continue;
+            };
+            check_conditions(Some(&bundle.cmd), &format!("bundle {}", path))?;
+            if let Some(ref when) = bundle.when {
+                when.validate().map_err(|e| match e {
+                    PanoutError::InvalidConfig(msg) => {
+                        PanoutError::InvalidConfig(format!("bundle {}: {}", path, msg))
+                    }
+                    e => e,
+                })?;
+            }
+            let cmds = bundle.cmd.to_vec();
+            if strict.empty_bundles && bundle.cmd.is_empty() {
+                return Err(PanoutError::InvalidConfig(format!(
+                    "bundle {} has no commands",
+                    path
+                )));
+            }
+            if strict.empty_commands && cmds.iter().any(|c| c.trim().is_empty()) {
+                return Err(PanoutError::InvalidConfig(format!(
+                    "bundle {} has an empty command",
+                    path
+                )));
+            }
+            check_env_keys(&bundle.env, &format!("bundle {}", path))?;
+            for dep in &bundle.after {
+                if self
+                    .resolve_alias(dep)
+                    .ok()
+                    .and_then(|d| self.get_bundle(&d))
+                    .is_none()
+                {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "bundle {} starts after missing bundle {}",
+                        path, dep
+                    )));
+                }
+            }
+        }
+        resolver::start_order(self, &self.list_bundles())?;
+        if strict.pane_conflicts {
+            for path in self.list_bundles() {
+                // Broken references are reported by the run itself
+                if let Ok(Some(conflict)) = resolver::pane_conflict(self, &path) {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "{} in {}",
+                        conflict, path
+                    )));
+                }
+            }
+        }
+
+        for name in self.list_workspaces() {
+            let workspace = &self.workspaces[&name];
+            check_env_keys(&workspace.env, &format!("workspace {}", name))?;
+            for (i, win) in workspace.windows.iter().enumerate() {
+                let what = format!("workspace {} window {}", name, i);
+                check_env_keys(&win.env, &what)?;
+                check_conditions(win.cmd.as_ref(), &what)?;
+                if let Panes::List(ref panes) = win.panes {
+                    for pane in panes {
+                        check_conditions(pane.cmd.as_ref(), &what)?;
+                    }
+                }
+            }
+        }
+
+        for (name, server) in &self.servers {
+            check_conditions(server.cmd.as_ref(), &format!("server {}", name))?;
+        }
+
+        // Hooks and run_shell go to a shell, and disconnect chains commands
+        // into one line; none of them can press keys
+        check_no_keys(self.defaults.before.as_ref(), "[defaults]", "before")?;
+        for path in self.list_bundles() {
+            let Some(bundle) = self.get_bundle(&path) else {
+                continue;
+            };
+            let what = format!("bundle {}", path);
+            check_no_keys(bundle.before.as_ref(), &what, "before")?;
+            check_no_keys(bundle.post.as_ref(), &what, "post")?;
+            check_no_keys(bundle.run_shell.as_ref(), &what, "run_shell")?;
+        }
+        for name in self.list_workspaces() {
+            let workspace = &self.workspaces[&name];
+            let what = format!("workspace {}", name);
+            check_no_keys(workspace.before.as_ref(), &what, "before")?;
+            check_no_keys(workspace.post.as_ref(), &what, "post")?;
+            check_no_keys(workspace.run_shell.as_ref(), &what, "run_shell")?;
+            if !workspace.disconnect.unwrap_or(false) {
+                continue;
+            }
+            for (i, win) in workspace.windows.iter().enumerate() {
+                // Broken references are reported by the run itself
+                let Ok(resolved) = resolver::resolve_window(self, win) else {
+                    continue;
+                };
+                let what = format!("workspace {} window {}", name, i);
+                for pane in 0..resolved.pane_count() as usize {
+                    if workspace.pane_host(win, pane).is_some() {
+                        check_no_chained_keys(&resolved.commands_for_pane(pane), &what)?;
+                    }
+                }
+            }
+        }
+        let mut servers: Vec<_> = self.servers.iter().filter(|(_, s)| s.disconnect).collect();
+        servers.sort_by_key(|(name, _)| *name);
+        for (name, server) in servers {
+            let commands = server.cmd.as_ref().map(|c| c.to_vec()).unwrap_or_default();
+            if let Ok(commands) = resolver::resolve_commands(self, &commands) {
+                check_no_chained_keys(&commands, &format!("server {}", name))?;
+            }
+        }
+
+        for name in self.list_workspaces() {
+            for (i, win) in self.workspaces[&name].windows.iter().enumerate() {
+                let hosts = win.hosts.as_ref().map_or(0, Vec::len);
+                if win.pane_count() == 0 {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "workspace {} window {} has no panes",
+                        name, i
+                    )));
+                }
+                if hosts > 0 && win.pane_count() as usize != hosts {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "workspace {} window {} has {} panes for {} hosts",
+                        name,
+                        i,
+                        win.pane_count(),
+                        hosts
+                    )));
+                }
+            }
+        }
+
+        for name in self.list_workspaces() {
+            let windows = &self.workspaces[&name].windows;
+            let focused: Vec<_> = (0..windows.len())
+                .filter(|&i| windows[i].is_focused())
+                .collect();
+            if let [first, second, ..] = focused[..] {
+                return Err(PanoutError::InvalidConfig(format!(
+                    "workspace {} sets focus on both window {} and window {}",
+                    name, first, second
+                )));
+            }
+        }
+
+        for name in self.list_workspaces() {
+            for (i, win) in self.workspaces[&name].windows.iter().enumerate() {
+                let Some(ref sizes) = win.sizes else {
+                    continue;
+                };
+                if win.pane_sizes().is_some() {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "workspace {} window {} sets both sizes and per-pane size",
+                        name, i
+                    )));
+                }
+                if sizes.len() != win.pane_count() as usize {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "workspace {} window {} has {} sizes for {} panes",
+                        name,
+                        i,
+                        sizes.len(),
+                        win.pane_count()
+                    )));
+                }
+                if sizes.contains(&0) {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "workspace {} window {} has a zero size",
+                        name, i
+                    )));
+                }
+            }
+        }
+
+        if strict.warnings
+            && let Some(warning) = self.warnings().into_iter().next()
+        {
+            return Err(PanoutError::InvalidConfig(warning));
+        }
+
+        Ok(())
+    }
+
+    /// Collect non-fatal problems found in the config.
+    ///
+    /// This flags an outdated config `version`, along with what changed
+    /// since, and bundle commands with shell-quoting hazards (unbalanced
+    /// quotes, trailing backslash) that tend to get mangled when typed into a
+    /// pane via `send-keys`. The checks are heuristic, so callers should
+    /// report them as warnings rather than refusing to run.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.parse_warnings.clone();
+        if let Some(found) = self.version {
+            for (version, change) in VERSION_CHANGES {
+                if found < *version {
+                    warnings.push(format!("config version {} is outdated: {}", found, change));
+                }
+            }
+        }
+        for path in self.list_bundles() {
+            let Some(bundle) = self.get_bundle(&path) else {
+                continue;
+            };
+            for cmd in bundle.cmd.to_vec() {
+                if let Some(problem) = quoting_hazard(&cmd) {
+                    warnings.push(format!("bundle {}: {} in command: {}", path, problem, cmd));
+                }
+            }
+        }
+        for name in self.list_workspaces() {
+            for (i, win) in self.workspaces[&name].windows.iter().enumerate() {
+                let count = win.cmd.as_ref().map_or(0, |c| c.to_vec().len());
+                let positional = win.distribute == Some(DistributeMode::Positional);
+                if positional && count > win.pane_count() as usize {
+                    warnings.push(format!(
+                        "workspace {} window {}: {} positional commands for {} panes; \
+                         the extras never run",
+                        name,
+                        i,
+                        count,
+                        win.pane_count()
+                    ));
+                }
+            }
+        }
+        warnings
+    }
+}
+
+/// The reserved key `key` is probably a typo of, if any.
+///
+/// Matches within edit distance 2, or 1 for the short `vars`, so that group
+/// names like `bar` or `cats` aren't flagged.
+fn misspelled_reserved_key(key: &str) -> Option<&'static str> {
+    RESERVED_KEYS.iter().copied().find(|reserved| {
+        let max = if reserved.len() < 5 { 1 } else { 2 };
+        edit_distance(key, reserved) <= max
+    })
+}
+
+/// Levenshtein distance between two strings, by character.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev + usize::from(ca != *cb);
+            prev = row[j + 1];
+            row[j + 1] = substitute.min(prev + 1).min(row[j] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Reject `keys` in commands that aren't sent to a pane, like hooks.
+///
+/// `field` names where the commands go, e.g. `before` or `run_shell`.
+fn check_no_keys(cmd: Option<&Cmd>, what: &str, field: &str) -> PanoutResult<()> {
+    match cmd {
+        Some(cmd) if cmd.has_keys() => Err(PanoutError::InvalidConfig(format!(
+            "{} can't use keys in {}; keys can only be sent to a pane",
+            what, field
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Reject resolved commands with a key sequence that would be chained
+/// into one line for `disconnect`.
+fn check_no_chained_keys(commands: &[String], what: &str) -> PanoutResult<()> {
+    if commands.iter().any(|c| tmux::key_sequence(c).is_some()) {
+        return Err(PanoutError::InvalidConfig(format!(
+            "{} can't use keys with disconnect = true; keys can't be chained before exit",
+            what
+        )));
+    }
+    Ok(())
+}
+
+/// Reject `env` keys that can't be exported from a shell.
+fn check_env_keys(env: &HashMap<String, String>, what: &str) -> PanoutResult<()> {
+    let mut keys: Vec<_> = env.keys().collect();
+    keys.sort();
+    match keys.into_iter().find(|k| !shell::is_valid_env_key(k)) {
+        Some(key) => Err(PanoutError::InvalidConfig(format!(
+            "{} has an invalid env name: {}",
+            what, key
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reject `when` conditions that don't parse and per-platform tables keyed by
+/// unknown platforms, so typos don't silently skip commands.
+fn check_conditions(cmd: Option<&Cmd>, what: &str) -> PanoutResult<()> {
+    match cmd {
+        Some(Cmd::When { cmd, when }) => {
+            condition::Condition::parse(when).map_err(|e| match e {
+                PanoutError::InvalidConfig(msg) => {
+                    PanoutError::InvalidConfig(format!("{} has an {}", what, msg))
+                }
+                e => e,
+            })?;
+            check_conditions(Some(cmd), what)
+        }
+        Some(Cmd::PerPlatform(table)) => {
+            for (os, cmd) in table {
+                if os != "default" && !condition::OS_NAMES.contains(&os.as_str()) {
+                    return Err(PanoutError::InvalidConfig(format!(
+                        "{} has an unknown platform `{}` in its cmd (expected one of {}, \
+                         or default)",
+                        what,
+                        os,
+                        condition::OS_NAMES.join(", ")
+                    )));
+                }
+                check_conditions(Some(cmd), what)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Detect quoting that a POSIX shell would choke on.
+///
+/// Tracks single quotes, double quotes, and backslash escapes (which are
+/// literal inside single quotes). Returns a short description of the first
+/// problem found.
+fn quoting_hazard(cmd: &str) -> Option<&'static str> {
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut escaped = false;
+
+    for c in cmd.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if !in_single => escaped = true,
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            _ => {}
+        }
+    }
+
+    if in_single {
+        Some("unbalanced single quote")
+    } else if in_double {
+        Some("unbalanced double quote")
+    } else if escaped {
+        Some("trailing backslash")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(toml: &str) -> WindowDef {
+        let config = Config::from_str(toml).unwrap();
+        config.get_workspace("ws").unwrap().windows[0].clone()
+    }
+
+    #[test]
+    fn test_distribute_round_robin() {
+        let win = window(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 6, cmd = ["worker-a", "worker-b"], distribute = "round_robin" }]
+            "#,
+        );
+        let per_pane: Vec<_> = (0..6).map(|pane| win.commands_for_pane(pane)).collect();
+        assert_eq!(
+            per_pane,
+            vec![
+                vec!["worker-a"],
+                vec!["worker-b"],
+                vec!["worker-a"],
+                vec!["worker-b"],
+                vec!["worker-a"],
+                vec!["worker-b"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_distribute_broadcast_and_positional() {
+        let broadcast = window(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 2, cmd = ["a", "b"] }]
+            "#,
+        );
+        assert_eq!(broadcast.commands_for_pane(1), vec!["a", "b"]);
+
+        let positional = window(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 3, cmd = ["a", "b"], distribute = "positional" }]
+            "#,
+        );
+        assert_eq!(positional.commands_for_pane(1), vec!["b"]);
+        assert!(positional.commands_for_pane(2).is_empty());
+    }
+
+    #[test]
+    fn test_window_pane_list() {
+        let win = window(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            cmd = "clear"
+            title = "dev"
+            panes = [
+                { cmd = "nvim ." },
+                { cmd = "cargo watch -x test", dir = "~/src/api", name = "tests" },
+                {},
+            ]
+            "#,
+        );
+        assert_eq!(win.pane_count(), 3);
+        assert_eq!(win.commands_for_pane(0), vec!["clear", "nvim ."]);
+        assert_eq!(win.commands_for_pane(2), vec!["clear"]);
+        assert_eq!(win.pane_title(1), Some("tests"));
+        assert_eq!(win.pane_title(2), Some("dev"));
+    }
+
+    #[test]
+    fn test_positional_overflow_warns() {
+        let config = Config::from_str(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 1, cmd = ["a", "b"], distribute = "positional" }]
+            "#,
+        )
+        .unwrap();
+        let warnings = config.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("workspace ws window 0: 2 positional commands"));
+    }
+
+    #[test]
+    fn test_merge_bundle_groups_entry_by_entry() {
+        let mut base = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            [dev.api]
+            cmd = "cargo run"
+            [ops.logs]
+            cmd = "journalctl -f"
+            "#,
+        )
+        .unwrap();
+        let overlay = Config::from_str(
+            r#"
+            [dev.api]
+            cmd = "cargo watch -x run"
+            pane = 1
+            [dev.db]
+            cmd = "postgres"
+            [test.unit]
+            cmd = "cargo test"
+            "#,
+        )
+        .unwrap();
+        base.merge(overlay);
+
+        let cmd = |path| base.get_bundle(path).unwrap().cmd.to_vec();
+        assert_eq!(
+            base.list_bundles(),
+            vec!["dev.api", "dev.db", "dev.web", "ops.logs", "test.unit"]
+        );
+        assert_eq!(cmd("dev.api"), vec!["cargo watch -x run"]);
+        assert_eq!(base.get_bundle("dev.api").unwrap().pane, Some(1));
+        assert_eq!(cmd("dev.web"), vec!["npm run dev"]);
+    }
+
+    #[test]
+    fn test_merge_replaces_workspaces_servers_and_vars_by_name() {
+        let mut base = Config::from_str(
+            r#"
+            [vars]
+            root = "~/src"
+            port = "8080"
+            [servers.prod]
+            host = "deploy@prod"
+            [servers.staging]
+            host = "deploy@staging"
+            [workspace.app]
+            windows = [{ panes = 2 }]
+            "#,
+        )
+        .unwrap();
+        let overlay = Config::from_str(
+            r#"
+            [vars]
+            port = "3000"
+            [servers.prod]
+            host = "admin@prod"
+            [workspace.app]
+            windows = [{ panes = 4 }]
+            "#,
+        )
+        .unwrap();
+        base.merge(overlay);
+
+        assert_eq!(base.vars["root"], "~/src");
+        assert_eq!(base.vars["port"], "3000");
+        assert_eq!(base.servers["prod"].host, "admin@prod");
+        assert_eq!(base.servers["staging"].host, "deploy@staging");
+        assert_eq!(base.workspaces["app"].windows[0].pane_count(), 4);
+    }
+
+    #[test]
+    fn test_merge_defaults_only_overrides_set_fields() {
+        let mut base = Config::from_str(
+            r#"
+            [defaults]
+            layout = "vertical"
+            shell = "zsh"
+            confirm_threshold = 8
+            [defaults.layout_per_count]
+            2 = "vertical"
+            4 = "tiled"
+            "#,
+        )
+        .unwrap();
+        let overlay = Config::from_str(
+            r#"
+            [defaults]
+            shell = "fish"
+            settle_ms = 200
+            layout_string = "5e3b,191x50,0,0{95x50,0,0,1,95x50,96,0,2}"
+            [defaults.layout_per_count]
+            4 = "main-vertical"
+            "#,
+        )
+        .unwrap();
+        base.merge(overlay);
+
+        let defaults = &base.defaults;
+        assert_eq!(defaults.layout, Some(Layout::Vertical));
+        assert_eq!(defaults.shell.as_deref(), Some("fish"));
+        assert_eq!(defaults.confirm_threshold, Some(8));
+        assert_eq!(defaults.settle_ms, Some(200));
+        assert_eq!(
+            defaults.layout_string.as_deref(),
+            Some("5e3b,191x50,0,0{95x50,0,0,1,95x50,96,0,2}")
+        );
+        assert_eq!(defaults.layout_for(2), Some(Layout::Vertical));
+        assert_eq!(defaults.layout_for(4), Some(Layout::MainVertical));
+    }
+
+    #[test]
+    fn test_to_toml_string_is_sorted_and_round_trips() {
+        let config = Config::from_str(
+            r#"
+            [zeta.b]
+            cmd = "b"
+            env = { Z = "1", A = "2" }
+
+            [alpha.a]
+            cmd = ["a1", "a2"]
+            pane = 1
+
+            [servers.prod]
+            host = "me@10.0.0.1"
+
+            [defaults.layout_per_count]
+            4 = "tiled"
+            2 = "vertical"
+            "#,
+        )
+        .unwrap();
+
+        let printed = config.to_toml_string().unwrap();
+        let order: Vec<_> = ["[defaults", "[servers.prod]", "[alpha.a]", "[zeta.b]"]
+            .iter()
+            .map(|key| printed.find(key).unwrap())
+            .collect();
+        assert!(order.windows(2).all(|w| w[0] < w[1]), "{}", printed);
+        assert!(printed.find("A = ").unwrap() < printed.find("Z = ").unwrap());
+        assert!(printed.find("2 = ").unwrap() < printed.find("4 = ").unwrap());
+        assert_eq!(printed, config.to_toml_string().unwrap());
+
+        let reparsed = Config::from_str(&printed).unwrap();
+        assert_eq!(reparsed.list_bundles(), config.list_bundles());
+        assert_eq!(reparsed.get_bundle("alpha.a").unwrap().pane, Some(1));
+        assert_eq!(reparsed.defaults.layout_for(2), Some(Layout::Vertical));
+    }
+
+    #[test]
+    fn test_to_toml_string_round_trips_representative_config() {
+        let config = Config::from_str(
+            r#"
+            version = 1
+
+            [defaults]
+            layout = "main-vertical"
+            layout_per_count = { 2 = "vertical" }
+            layout_string = "5e3b,191x50,0,0{95x50,0,0,1,95x50,96,0,2}"
+            strict = true
+            default_entry = "dev.front"
+            shell = "zsh"
+            confirm_threshold = 12
+            main_pane_width = "60%"
+            main_pane_height = 30
+            prefix = "source .env"
+            suffix = "clear"
+            before = "echo up"
+            settle_ms = 100
+
+            [vars]
+            root = "~/src"
+
+            [aliases]
+            f = "dev.front"
+
+            [servers.prod]
+            host = "deploy@prod:2222"
+            cmd = ["cd /srv", "@dev.front"]
+            description = "production"
+
+            [dev.front]
+            cmd = "npm run dev"
+            dir = "{root}/web"
+            tags = ["web"]
+            size = { cells = 20 }
+
+            [dev.back]
+            cmd = ["cargo build", "cargo run"]
+            env = { RUST_LOG = "debug" }
+            after = "dev.front"
+            when = { env = "WORK" }
+
+            [dev.edit]
+            cmd = { edit = "src/main.rs" }
+
+            [dev.reset]
+            cmd = { keys = ["C-c", "C-l"] }
+
+            [dev.clip]
+            cmd = { cmd = "xclip -o", when = "os == linux" }
+
+            [dev.open]
+            cmd = { linux = "xdg-open .", macos = ["open ."], default = "true" }
+
+            [workspace.app]
+            host = "deploy@app"
+            dir = "/srv/app"
+            windows = [
+                { name = "edit", panes = [{ cmd = "nvim" }, { size = 30 }] },
+                { hosts = ["a@1.1.1.1", "local"], cmd = "uptime", layout = "vertical" },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let printed = config.to_toml_string().unwrap();
+        let reparsed = Config::from_str(&printed).unwrap();
+        assert_eq!(reparsed, config);
+        assert_eq!(reparsed.to_toml_string().unwrap(), printed);
+        assert!(reparsed.validate(&Strictness::default()).is_ok());
+
+        let cmd = |path| &reparsed.get_bundle(path).unwrap().cmd;
+        assert!(matches!(cmd("dev.front"), Cmd::Single(c) if c == "npm run dev"));
+        assert!(matches!(cmd("dev.back"), Cmd::Multiple(c) if c.len() == 2));
+        assert!(matches!(cmd("dev.edit"), Cmd::Edit { .. }));
+        assert!(matches!(cmd("dev.reset"), Cmd::Keys { .. }));
+        assert!(matches!(cmd("dev.clip"), Cmd::When { .. }));
+        assert!(matches!(cmd("dev.open"), Cmd::PerPlatform(t) if t.len() == 3));
+        assert_eq!(reparsed.version, Some(1));
+        assert_eq!(reparsed.resolve_alias("f").unwrap(), "dev.front");
+        assert_eq!(reparsed.workspaces["app"].windows[1].pane_count(), 2);
+    }
+
+    #[test]
+    fn test_with_resolved_layouts() {
+        let config = Config::from_str(
+            r#"
+            [defaults.layout_per_count]
+            2 = "vertical"
+
+            [dev.one]
+            cmd = "a"
+
+            [dev.two]
+            cmd = ["@dev.one", "@dev.other"]
+
+            [dev.other]
+            cmd = "b"
+            pane = 1
+
+            [dev.fixed]
+            cmd = "c"
+            layout = "horizontal"
+
+            [workspace.ws]
+            default_layout = "vertical"
+            windows = [{ panes = 2 }, { panes = 1, layout = "tiled" }]
+            "#,
+        )
+        .unwrap()
+        .with_resolved_layouts();
+
+        assert_eq!(
+            config.get_bundle("dev.one").unwrap().layout,
+            Some(Layout::Tiled)
+        );
+        assert_eq!(
+            config.get_bundle("dev.two").unwrap().layout,
+            Some(Layout::Vertical)
+        );
+        assert_eq!(
+            config.get_bundle("dev.fixed").unwrap().layout,
+            Some(Layout::Horizontal)
+        );
+        let ws = config.get_workspace("ws").unwrap();
+        assert_eq!(ws.windows[0].layout, Some(Layout::Vertical));
+        assert_eq!(ws.windows[1].layout, Some(Layout::Tiled));
+    }
+
+    #[test]
+    fn test_parse_edit_cmd() {
+        let config = Config::from_str(
+            r#"
+            [project.main]
+            cmd = { edit = "src/main.rs" }
+            dir = "~/src/app"
+            "#,
+        )
+        .unwrap();
+
+        let bundle = config.get_bundle("project.main").unwrap();
+        assert!(matches!(&bundle.cmd, Cmd::Edit { edit } if edit == "src/main.rs"));
+    }
+
+    #[test]
+    fn test_when_cmd_follows_platform() {
+        let config = Config::from_str(
+            r#"
+            [tools.copy]
+            cmd = [
+                "@tools.linux",
+                "@tools.macos",
+            ]
+
+            [tools.linux]
+            cmd = { cmd = ["xclip -o", "echo linux"], when = "os == linux" }
+
+            [tools.macos]
+            cmd = { cmd = "pbpaste", when = "os != linux" }
+            "#,
+        )
+        .unwrap();
+        let cmd = |name| &config.get_bundle(name).unwrap().cmd;
+
+        let linux = Platform {
+            os: "linux".into(),
+            hostname: None,
+        };
+        assert_eq!(
+            cmd("tools.linux").to_vec_on(&linux),
+            vec!["xclip -o", "echo linux"]
+        );
+        assert!(cmd("tools.macos").to_vec_on(&linux).is_empty());
+
+        let expected = match cfg!(target_os = "linux") {
+            true => vec!["xclip -o", "echo linux"],
+            false => vec!["pbpaste"],
+        };
+        assert_eq!(
+            resolver::resolve_bundle(&config, "tools.copy").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_per_platform_cmd() {
+        let config = Config::from_str(
+            r#"
+            [tools.open]
+            cmd = { linux = "xdg-open .", macos = ["brew update", "open ."], default = "true" }
+
+            [tools.serve]
+            cmd = { linux = { cmd = "systemctl --user start foo", when = "hostname == box" } }
+            "#,
+        )
+        .unwrap();
+        let cmd = |name| &config.get_bundle(name).unwrap().cmd;
+        let on = |os: &str| Platform {
+            os: os.into(),
+            hostname: Some("box".into()),
+        };
+
+        assert_eq!(
+            cmd("tools.open").to_vec_on(&on("linux")),
+            vec!["xdg-open ."]
+        );
+        assert_eq!(
+            cmd("tools.open").to_vec_on(&on("macos")),
+            vec!["brew update", "open ."]
+        );
+        assert_eq!(cmd("tools.open").to_vec_on(&on("freebsd")), vec!["true"]);
+        assert_eq!(
+            cmd("tools.serve").to_vec_on(&on("linux")),
+            vec!["systemctl --user start foo"]
+        );
+        assert!(cmd("tools.serve").to_vec_on(&on("macos")).is_empty());
+
+        assert!(
+            cmd("tools.open")
+                .check_platform("windows", "bundle tools.open")
+                .is_ok()
+        );
+        let err = cmd("tools.serve").check_platform("macos", "bundle tools.serve");
+        assert!(matches!(
+            err,
+            Err(PanoutError::InvalidConfig(msg)) if msg.starts_with("bundle tools.serve has no \
+                command for macos")
+        ));
+    }
+
+    #[test]
+    fn test_per_platform_cmd_rejects_unknown_platform() {
+        let config = Config::from_str(
+            r#"
+            [tools.open]
+            cmd = { linx = "xdg-open ." }
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(&Strictness::default()),
+            Err(PanoutError::InvalidConfig(msg)) if msg.contains("unknown platform `linx`")
+        ));
+    }
+
+    #[test]
+    fn test_validate_after_target_exists() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            after = ["dev.api"]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(&Strictness::default()),
+            Err(PanoutError::InvalidConfig(msg))
+                if msg == "bundle dev.web starts after missing bundle dev.api"
+        ));
+    }
+
+    #[test]
+    fn test_validate_bundle_when() {
+        let config = Config::from_str(
+            r#"
+            [dev.gpu]
+            cmd = "nvidia-smi"
+            when = { equals = "true" }
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(&Strictness::default()),
+            Err(PanoutError::InvalidConfig(msg)) if msg.starts_with("bundle dev.gpu: `equals`")
+        ));
+
+        let typo = Config::from_str(
+            r#"
+            [dev.gpu]
+            cmd = "nvidia-smi"
+            when = { host = "box" }
+            "#,
+        );
+        assert!(typo.is_err());
+    }
+
+    #[test]
+    fn test_editor_command_uses_editor() {
+        assert_eq!(
+            editor_command("src/main.rs", Some("nvim")),
+            "nvim src/main.rs"
+        );
+        assert_eq!(
+            editor_command("notes.md", Some("code --wait")),
+            "code --wait notes.md"
+        );
+    }
+
+    #[test]
+    fn test_editor_command_quotes_file() {
+        assert_eq!(
+            editor_command("my notes.md", Some("vim")),
+            "vim 'my notes.md'"
+        );
+        assert_eq!(
+            editor_command("~/my notes.md", Some("vim")),
+            "vim ~/'my notes.md'"
+        );
+    }
+
+    #[test]
+    fn test_editor_command_falls_back_to_vi() {
+        assert_eq!(editor_command("src/main.rs", None), "vi src/main.rs");
+        assert_eq!(editor_command("src/main.rs", Some("  ")), "vi src/main.rs");
+    }
+
+    #[test]
+    fn test_strict_rejects_lenient_config() {
+        let config = Config::from_str(
+            r#"
+            [dev.placeholder]
+            cmd = []
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.validate(&config.strictness(false)).is_ok());
+        assert!(matches!(
+            config.validate(&config.strictness(true)),
+            Err(PanoutError::InvalidConfig(msg)) if msg.contains("dev.placeholder")
+        ));
+    }
+
+    #[test]
+    fn test_strict_from_defaults() {
+        let config = Config::from_str(
+            r#"
+            [defaults]
+            strict = true
+
+            [dev.blank]
+            cmd = ["echo hi", "  "]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.strictness(false), Strictness::all());
+        assert!(config.validate(&config.strictness(false)).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_keys_outside_panes() {
+        let cases = [
+            (
+                "[defaults]\nbefore = { keys = [\"C-c\"] }\n",
+                "[defaults] can't use keys in before",
+            ),
+            (
+                "[dev.a]\ncmd = \"ls\"\nbefore = { keys = [\"C-c\"] }\n",
+                "bundle dev.a can't use keys in before",
+            ),
+            (
+                "[dev.a]\ncmd = \"ls\"\npost = { cmd = { keys = [\"C-c\"] }, when = \"os != x\" }\n",
+                "bundle dev.a can't use keys in post",
+            ),
+            (
+                "[dev.a]\ncmd = \"ls\"\nrun_shell = { keys = [\"C-c\"] }\n",
+                "bundle dev.a can't use keys in run_shell",
+            ),
+            (
+                "[workspace.w]\nrun_shell = { keys = [\"C-c\"] }\nwindows = [{ panes = 1 }]\n",
+                "workspace w can't use keys in run_shell",
+            ),
+            (
+                "[dev.reset]\ncmd = { keys = [\"C-c\"] }\n\
+                 [servers.box]\nhost = \"me@box\"\ndisconnect = true\ncmd = [\"@dev.reset\"]\n",
+                "server box can't use keys with disconnect = true",
+            ),
+            (
+                "[workspace.w]\nhost = \"me@box\"\ndisconnect = true\n\
+                 windows = [{ panes = 1, cmd = { keys = [\"C-c\"] } }]\n",
+                "workspace w window 0 can't use keys with disconnect = true",
+            ),
+        ];
+        for (toml, expected) in cases {
+            let config = Config::from_str(toml).unwrap();
+            match config.validate(&Strictness::default()) {
+                Err(PanoutError::InvalidConfig(msg)) => {
+                    assert!(msg.starts_with(expected), "{}", msg)
+                }
+                other => panic!("expected InvalidConfig for {}, got {:?}", toml, other),
+            }
+        }
+
+        // Keys sent to panes are fine, including without a host to disconnect from
+        let config = Config::from_str(
+            "[dev.reset]\ncmd = { keys = [\"C-c\"] }\n\
+             [servers.box]\nhost = \"me@box\"\ncmd = [\"@dev.reset\"]\n\
+             [workspace.w]\ndisconnect = true\nwindows = [{ panes = 1, cmd = \"@dev.reset\" }]\n",
+        )
+        .unwrap();
+        assert!(config.validate(&Strictness::default()).is_ok());
+    }
+
+    #[test]
+    fn test_strict_rejects_pane_conflicts() {
+        let config = Config::from_str(
+            r#"
+            [dev.api]
+            cmd = "cargo run"
+            pane = 1
+
+            [dev.db]
+            cmd = "psql"
+            pane = 1
+
+            [dev.all]
+            cmd = ["@dev.api", "@dev.db"]
+            "#,
+        )
+        .unwrap();
+
+        let only_panes = Strictness {
+            pane_conflicts: true,
+            ..Strictness::default()
+        };
+        assert!(config.validate(&Strictness::default()).is_ok());
+        match config.validate(&only_panes) {
+            Err(PanoutError::InvalidConfig(msg)) => assert_eq!(
+                msg,
+                "bundle dev.api and bundle dev.db both set pane 1 in dev.all"
+            ),
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_promotes_warnings() {
+        let config = Config::from_str(
+            r#"
+            [dev.quote]
+            cmd = "echo 'oops"
+            "#,
+        )
+        .unwrap();
+
+        let only_warnings = Strictness {
+            warnings: true,
+            ..Strictness::default()
+        };
+        assert!(config.validate(&Strictness::default()).is_ok());
+        assert!(config.validate(&only_warnings).is_err());
+    }
+
+    #[test]
+    fn test_workspace_default_layout() {
+        let config = Config::from_str(
+            r#"
+            [workspace.dev]
+            default_layout = "vertical"
+            windows = [
+                { panes = 2 },
+                { panes = 3, layout = "horizontal" },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let ws = config.get_workspace("dev").unwrap();
+        let defaults = &config.defaults;
+        assert_eq!(ws.window_layout(&ws.windows[0], defaults), Layout::Vertical);
+        assert_eq!(
+            ws.window_layout(&ws.windows[1], defaults),
+            Layout::Horizontal
+        );
+    }
+
+    fn six_windows() -> Workspace {
+        let config = Config::from_str(
+            r#"
+            [workspace.big]
+            windows = [
+                { panes = 1, name = "edit" },
+                { panes = 1, name = "build" },
+                { panes = 1, name = "test" },
+                { panes = 1, name = "logs" },
+                { panes = 1 },
+                { panes = 1, name = "db" },
+            ]
+            "#,
+        )
+        .unwrap();
+        config.get_workspace("big").unwrap().clone()
+    }
+
+    fn window_names(ws: &Workspace) -> Vec<String> {
+        ws.windows
+            .iter()
+            .map(|w| w.name.clone().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_windows_indices_and_ranges() {
+        let ws = six_windows();
+        assert_eq!(
+            window_names(&ws.filter_windows("0,1").unwrap()),
+            ["edit", "build"]
+        );
+        assert_eq!(
+            window_names(&ws.filter_windows("0-2").unwrap()),
+            ["edit", "build", "test"]
+        );
+        assert_eq!(
+            window_names(&ws.filter_windows("5,1-2").unwrap()),
+            ["build", "test", "db"]
+        );
+    }
+
+    #[test]
+    fn test_filter_windows_by_name() {
+        let ws = six_windows();
+        assert_eq!(
+            window_names(&ws.filter_windows("logs,build").unwrap()),
+            ["build", "logs"]
+        );
+        assert_eq!(
+            window_names(&ws.filter_windows("edit,4").unwrap()),
+            ["edit", ""]
+        );
+    }
+
+    #[test]
+    fn test_filter_windows_errors() {
+        let ws = six_windows();
+        for spec in ["6", "0-9", "3-1", "nope"] {
+            assert!(
+                matches!(ws.filter_windows(spec), Err(PanoutError::WindowNotFound(_))),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_window_layout_string_falls_back_to_defaults() {
+        let config = Config::from_str(
+            r#"
+            [defaults]
+            layout_string = "aaaa,80x24,0,0,1"
+
+            [workspace.dev]
+            windows = [{ panes = 1 }, { panes = 1, layout_string = "bbbb,80x24,0,0,1" }]
+            "#,
+        )
+        .unwrap();
+
+        let ws = config.get_workspace("dev").unwrap();
+        let defaults = &config.defaults;
+        assert_eq!(
+            ws.window_layout_string(&ws.windows[0], defaults),
+            Some("aaaa,80x24,0,0,1")
+        );
+        assert_eq!(
+            ws.window_layout_string(&ws.windows[1], defaults),
+            Some("bbbb,80x24,0,0,1")
+        );
+        assert_eq!(
+            ws.window_layout_string(&ws.windows[0], &Defaults::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_workspace_layout_falls_back_to_tiled() {
+        let config = Config::from_str(
+            r#"
+            [workspace.dev]
+            windows = [{ panes = 2 }]
+            "#,
+        )
+        .unwrap();
+
+        let ws = config.get_workspace("dev").unwrap();
+        assert_eq!(
+            ws.window_layout(&ws.windows[0], &config.defaults),
+            Layout::Tiled
+        );
+    }
+
+    #[test]
+    fn test_quoting_hazard_balanced() {
+        for cmd in [
+            "npm run dev",
+            "echo 'hello world'",
+            r#"echo "it's fine""#,
+            r#"echo 'say "hi"'"#,
+            r#"echo "escaped \" quote""#,
+            r"echo don\'t",
+        ] {
+            assert_eq!(quoting_hazard(cmd), None, "{}", cmd);
+        }
+    }
+
+    #[test]
+    fn test_quoting_hazard_unbalanced() {
+        assert_eq!(
+            quoting_hazard("echo 'oops"),
+            Some("unbalanced single quote")
+        );
+        assert_eq!(
+            quoting_hazard(r#"echo "oops"#),
+            Some("unbalanced double quote")
+        );
+        assert_eq!(
+            quoting_hazard(r#"echo 'a' "b"#),
+            Some("unbalanced double quote")
+        );
+        assert_eq!(quoting_hazard("make \\"), Some("trailing backslash"));
+    }
+
+    #[test]
+    fn test_warnings_name_bundle_and_command() {
+        let config = Config::from_str(
+            r#"
+            [dev.ok]
+            cmd = "echo 'fine'"
+
+            [dev.broken]
+            cmd = ["ls", "echo \"unterminated"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.warnings(),
+            vec![r#"bundle dev.broken: unbalanced double quote in command: echo "unterminated"#]
+        );
+    }
+
+    #[test]
+    fn test_layout_per_count() {
+        let config = Config::from_str(
+            r#"
+            [defaults]
+            layout = "horizontal"
+
+            [defaults.layout_per_count]
+            2 = "vertical"
+            4 = "tiled"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.defaults.layout_for(2), Some(Layout::Vertical));
+        assert_eq!(config.defaults.layout_for(4), Some(Layout::Tiled));
+        assert_eq!(config.defaults.layout_for(3), Some(Layout::Horizontal));
+    }
+
+    #[test]
+    fn test_layout_per_count_without_default() {
+        let config = Config::from_str(
+            r#"
+            [defaults.layout_per_count]
+            2 = "vertical"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.defaults.layout_for(2), Some(Layout::Vertical));
+        assert_eq!(config.defaults.layout_for(5), None);
+    }
+
+    #[test]
+    fn test_validate_window_sizes_must_match_panes() {
+        let config = Config::from_str(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = 3
+            sizes = [70, 30]
+            "#,
+        )
+        .unwrap();
+
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::InvalidConfig(msg)) => {
+                assert_eq!(msg, "workspace ws window 0 has 2 sizes for 3 panes");
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_window_focus() {
+        let config = Config::from_str(
+            r#"
+            [workspace.ws]
+            windows = [
+                { panes = 2, focus = true, focus_pane = 1 },
+                { panes = 2, focus = 1 },
+            ]
+            "#,
+        )
+        .unwrap();
+        let windows = &config.get_workspace("ws").unwrap().windows;
+
+        assert!(windows[0].is_focused());
+        assert_eq!(windows[0].focused_pane(), Some(1));
+        assert!(!windows[1].is_focused());
+        assert_eq!(windows[1].focused_pane(), Some(1));
+        assert!(config.validate(&Strictness::default()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_window_hosts_match_panes() {
+        let cases = [
+            (
+                "{ panes = 3, hosts = [\"a@1\", \"b@2\"] }",
+                Some("has 3 panes for 2 hosts"),
+            ),
+            ("{ hosts = [] }", Some("has no panes")),
+            ("{ cmd = \"ls\" }", Some("has no panes")),
+            ("{ panes = 2, hosts = [\"a@1\", \"b@2\"] }", None),
+            ("{ hosts = [\"a@1\", \"b@2\"] }", None),
+        ];
+        for (window, expected) in cases {
+            let config =
+                Config::from_str(&format!("[workspace.ws]\nwindows = [{}]\n", window)).unwrap();
+            match (config.validate(&Strictness::default()), expected) {
+                (Ok(()), None) => {}
+                (Err(PanoutError::InvalidConfig(msg)), Some(expected)) => {
+                    assert_eq!(msg, format!("workspace ws window 0 {}", expected));
+                }
+                (other, _) => panic!("{}: got {:?}", window, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_one_focused_window() {
+        let config = Config::from_str(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 1, focus = true }, { panes = 1 }, { panes = 1, focus = true }]
+            "#,
+        )
+        .unwrap();
+
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::InvalidConfig(msg)) => {
+                assert_eq!(msg, "workspace ws sets focus on both window 0 and window 2");
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_config_version() {
+        let current = Config::from_str("version = 1\n[dev.a]\ncmd = \"a\"").unwrap();
+        assert_eq!(current.version, Some(1));
+        assert!(current.validate(&Strictness::default()).is_ok());
+        assert!(current.warnings().is_empty());
+
+        let missing = Config::from_str("[dev.a]\ncmd = \"a\"").unwrap();
+        assert_eq!(missing.version, None);
+        assert!(missing.warnings().is_empty());
+
+        let old = Config::from_str("version = 0").unwrap();
+        assert!(old.warnings()[0].starts_with("config version 0 is outdated"));
+        assert!(old.validate(&Strictness::all()).is_err());
+    }
+
+    #[test]
+    fn test_config_version_too_new() {
+        // Later formats may add fields this build would fail to parse
+        let config = Config::from_str(
+            r#"
+            version = 99
+
+            [dev.a]
+            cmd = { future = "syntax" }
+            "#,
+        )
+        .unwrap();
+
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::UnsupportedConfigVersion { found, supported }) => {
+                assert_eq!((found, supported), (99, CONFIG_VERSION));
+            }
+            other => panic!("expected UnsupportedConfigVersion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_workspace_shell_precedence() {
+        let config = Config::from_str(
+            r#"
+            [defaults]
+            shell = "bash"
+
+            [workspace.a]
+            windows = [{ panes = 1 }]
+
+            [workspace.b]
+            shell = "none"
+            windows = [{ panes = 1 }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.workspace_shell(&config.workspaces["a"]),
+            Some("bash")
+        );
+        assert_eq!(
+            config.workspace_shell(&config.workspaces["b"]),
+            Some(NO_SHELL)
+        );
+    }
+
+    #[test]
+    fn test_pre_and_post_hooks() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            pre = "docker compose up -d"
+            post = ["notify-send up"]
+
+            [workspace.ws]
+            before = "true"
+            post = "notify-send ws"
+            windows = [{ panes = 1 }]
+            "#,
+        )
+        .unwrap();
+
+        let web = config.get_bundle("dev.web").unwrap();
+        assert_eq!(web.before, Some(Cmd::Single("docker compose up -d".into())));
+        assert_eq!(web.post, Some(Cmd::Multiple(vec!["notify-send up".into()])));
+        let ws = config.get_workspace("ws").unwrap();
+        assert_eq!(ws.before, Some(Cmd::Single("true".into())));
+        assert_eq!(ws.post, Some(Cmd::Single("notify-send ws".into())));
+    }
+
+    #[test]
+    fn test_unknown_field_rejected() {
+        let err = Config::from_str("[dev.web]\ncmd = \"ls\"\nlayuot = \"tiled\"\n").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field `layuot`"),
+            "{}",
+            err
+        );
+
+        let err = Config::from_str("[defaults]\nlayouts = \"tiled\"\n").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field `layouts`"),
+            "{}",
+            err
+        );
+
+        let err = Config::from_str(
+            "[workspace.w]\nwindows = [{ panes = [{ cmd = \"make\", dri = \"/srv\" }] }]\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `dri`"), "{}", err);
+    }
+
+    #[test]
+    fn test_misspelled_reserved_key() {
+        let err = Config::from_str("[workspaces.app]\nwindows = [{ panes = 2 }]\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unknown top-level key `workspaces` (did you mean `workspace`?)"),
+            "{}",
+            err
+        );
+
+        // Still a valid bundle group, so only a warning (an error with --strict)
+        let config = Config::from_str("[default.web]\ncmd = \"ls\"\n").unwrap();
+        assert_eq!(
+            config.warnings(),
+            vec!["top-level key `default` is read as a bundle group; did you mean `defaults`?"]
+        );
+        assert!(config.validate(&Strictness::all()).is_err());
+
+        let config = Config::from_str("[cats.web]\ncmd = \"ls\"\n").unwrap();
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let config = Config::from_str(
+            r#"
+            [aliases]
+            f = "dev.frontend"
+            ff = "f"
+
+            [dev.frontend]
+            cmd = "npm run dev"
+            "#,
+        )
+        .unwrap();
+
+        assert!(!config.bundles.contains_key("aliases"));
+        assert_eq!(config.resolve_alias("ff").unwrap(), "dev.frontend");
+        assert_eq!(
+            config.resolve_alias("dev.frontend").unwrap(),
+            "dev.frontend"
+        );
+        assert_eq!(config.aliases_for("dev.frontend"), vec!["f", "ff"]);
+        assert!(config.validate(&Strictness::default()).is_ok());
+    }
+
+    #[test]
+    fn test_alias_cycle_and_missing_target() {
+        let config = Config::from_str("[aliases]\na = \"b\"\nb = \"a\"\n").unwrap();
+        assert!(matches!(
+            config.validate(&Strictness::default()),
+            Err(PanoutError::CircularRef(ref msg)) if msg == "alias a -> b -> a"
+        ));
+
+        let config = Config::from_str("[aliases]\nf = \"dev.nope\"\n").unwrap();
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::InvalidConfig(msg)) => {
+                assert_eq!(msg, "alias f points to missing bundle dev.nope");
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vars_section() {
+        let config = Config::from_str(
+            r#"
+            [vars]
+            project_dir = "~/src/megacorp"
+
+            [workspace.ws]
+            dir = "{project_dir}/api"
+            [[workspace.ws.windows]]
+            panes = [{ cmd = "ls {project_dir}" }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.vars["project_dir"], "~/src/megacorp");
+        assert!(!config.bundles.contains_key("vars"));
+
+        let ws = config.workspaces["ws"]
+            .with_vars(&config.vars, "ws")
+            .unwrap();
+        assert_eq!(ws.dir.as_deref(), Some("~/src/megacorp/api"));
+        let pane = ws.windows[0].panes.get(0).unwrap();
+        assert_eq!(
+            pane.cmd.as_ref().unwrap().to_vec(),
+            vec!["ls ~/src/megacorp"]
+        );
+    }
+
+    #[test]
+    fn test_vars_shadowing_builtin_rejected() {
+        let config = Config::from_str("[vars]\nuser = \"me\"\n").unwrap();
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::InvalidConfig(msg)) => {
+                assert_eq!(msg, "vars.user shadows the built-in {user} placeholder");
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_env_keys() {
+        let config = Config::from_str(
+            r#"
+            [workspace.ws]
+            env = { "MY-VAR" = "1" }
+            windows = [{ panes = 1 }]
+            "#,
+        )
+        .unwrap();
+
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::InvalidConfig(msg)) => {
+                assert_eq!(msg, "workspace ws has an invalid env name: MY-VAR");
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_conditions() {
+        let config = Config::from_str(
+            r#"
+            [tools.copy]
+            cmd = { cmd = "xclip", when = "os = linux" }
+            "#,
+        )
+        .unwrap();
+
+        match config.validate(&Strictness::default()) {
+            Err(PanoutError::InvalidConfig(msg)) => {
+                assert!(msg.starts_with("bundle tools.copy has an invalid condition `os = linux`"));
+            }
+            other => panic!("expected InvalidConfig, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pane_size_forms() {
+        let config = Config::from_str(
+            r#"
+            [dev.a]
+            cmd = "a"
+            size = 70
+
+            [dev.b]
+            cmd = "b"
+            size = "30%"
+
+            [dev.c]
+            cmd = "c"
+            size = { cells = 10 }
+            "#,
+        )
+        .unwrap();
+
+        let size = |name: &str| config.get_bundle(name).unwrap().size;
+        assert_eq!(size("dev.a"), Some(PaneSize::Percent(70)));
+        assert_eq!(size("dev.b"), Some(PaneSize::Percent(30)));
+        assert_eq!(size("dev.c"), Some(PaneSize::Cells(10)));
+        assert!(Config::from_str("[dev.d]\ncmd = \"d\"\nsize = \"wide\"\n").is_err());
+    }
 }
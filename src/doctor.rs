@@ -0,0 +1,269 @@
+//! Environment diagnostics for `panout doctor`.
+//!
+//! Each check is a plain function over already-gathered data so it can be
+//! tested without tmux or a real config. [`run_all`] gathers the data from
+//! the live environment and runs every check.
+
+use crate::config::Config;
+use crate::error::PanoutError;
+use crate::{interpolate, loader, ssh, tmux};
+use std::fmt;
+use std::path::Path;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    /// Everything is fine.
+    Pass,
+    /// Panout will work, but possibly not as expected.
+    Warn,
+    /// Panout won't work until this is fixed.
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Result of a diagnostic check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    /// Short name of what was checked.
+    pub name: &'static str,
+    /// Outcome.
+    pub status: Status,
+    /// What was found.
+    pub message: String,
+    /// How to fix it, for warnings and failures.
+    pub remedy: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Pass,
+            message: message.into(),
+            remedy: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, remedy: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Warn,
+            message: message.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, remedy: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Fail,
+            message: message.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+}
+
+/// Check that tmux is installed, given the result of [`tmux::version`].
+pub fn check_tmux_version(version: Result<&str, &PanoutError>) -> Check {
+    match version {
+        Ok(v) => Check::pass("tmux", format!("tmux {} found", v)),
+        Err(e) => Check::fail(
+            "tmux",
+            format!("tmux not usable: {}", e),
+            "install tmux and make sure it is on your PATH",
+        ),
+    }
+}
+
+/// Check that panout is running inside a tmux session.
+pub fn check_in_tmux(in_tmux: bool) -> Check {
+    if in_tmux {
+        Check::pass("session", "running inside tmux")
+    } else {
+        Check::warn(
+            "session",
+            "not running inside tmux",
+            "start tmux first; bundles and workspaces need a session",
+        )
+    }
+}
+
+/// Check that the config file exists and parses.
+pub fn check_config(path: &Path, loaded: Result<&Config, &PanoutError>) -> Check {
+    match loaded {
+        Ok(_) => Check::pass("config", format!("{} parsed", path.display())),
+        Err(PanoutError::ConfigNotFound(_)) => Check::fail(
+            "config",
+            format!("{} does not exist", path.display()),
+            "run `panout init` to create a starter config",
+        ),
+        Err(e) => Check::fail(
+            "config",
+            e.to_string(),
+            format!("fix the syntax in {}", path.display()),
+        ),
+    }
+}
+
+/// Check that every workspace host is in `user@host` form.
+pub fn check_workspace_hosts(config: &Config) -> Check {
+    let bad: Vec<String> = config
+        .list_workspaces()
+        .into_iter()
+        .filter(|name| {
+            config.workspaces[name]
+                .host
+                .as_deref()
+                .and_then(|h| ssh::resolve_target(config, h).ok())
+                .is_some_and(|t| interpolate::parse_host(&t.host).is_none())
+        })
+        .collect();
+
+    if bad.is_empty() {
+        Check::pass("hosts", "all workspace hosts are user@host")
+    } else {
+        Check::warn(
+            "hosts",
+            format!("hosts without a user: {}", bad.join(", ")),
+            "use user@host so {user}/{ip} interpolation works",
+        )
+    }
+}
+
+/// Check whether `base-index`/`pane-base-index` differ from tmux's default of 0.
+///
+/// Values are the raw option strings; `None` means they couldn't be read.
+pub fn check_base_index(base_index: Option<&str>, pane_base_index: Option<&str>) -> Check {
+    let non_default: Vec<String> = [
+        ("base-index", base_index),
+        ("pane-base-index", pane_base_index),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| match value {
+        Some(v) if v != "0" => Some(format!("{} = {}", name, v)),
+        _ => None,
+    })
+    .collect();
+
+    if non_default.is_empty() {
+        Check::pass("indices", "base-index and pane-base-index are default")
+    } else {
+        Check::warn(
+            "indices",
+            format!("non-default {}", non_default.join(", ")),
+            "panout maps pane numbers through tmux, but check any hardcoded targets",
+        )
+    }
+}
+
+/// Gather data from the live environment and run every check.
+///
+/// The config checked is the one a run would load, given `config_override`
+/// (`--config`); see [`loader::resolve_config_path`].
+pub fn run_all(config_override: Option<&Path>) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let version = tmux::version();
+    checks.push(check_tmux_version(version.as_deref()));
+
+    let in_tmux = tmux::in_tmux();
+    checks.push(check_in_tmux(in_tmux));
+
+    match loader::resolve_config_path(config_override) {
+        Ok(path) => {
+            let loaded = loader::load_config(&path);
+            checks.push(check_config(&path, loaded.as_ref()));
+            if let Ok(config) = &loaded {
+                checks.push(check_workspace_hosts(config));
+            }
+        }
+        Err(e) => checks.push(Check::fail(
+            "config",
+            e.to_string(),
+            "set HOME or XDG_CONFIG_HOME",
+        )),
+    }
+
+    if version.is_ok() && in_tmux {
+        let base = tmux::global_option("base-index", false).ok();
+        let pane_base = tmux::global_option("pane-base-index", true).ok();
+        checks.push(check_base_index(base.as_deref(), pane_base.as_deref()));
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_tmux_version_check() {
+        assert_eq!(check_tmux_version(Ok("3.4")).status, Status::Pass);
+        let err = PanoutError::TmuxError("No such file or directory".into());
+        assert_eq!(check_tmux_version(Err(&err)).status, Status::Fail);
+    }
+
+    #[test]
+    fn test_in_tmux_check() {
+        assert_eq!(check_in_tmux(true).status, Status::Pass);
+        assert_eq!(check_in_tmux(false).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_config_check() {
+        let path = PathBuf::from("/tmp/panout/config.toml");
+        let config = Config::default();
+        assert_eq!(check_config(&path, Ok(&config)).status, Status::Pass);
+
+        let missing = PanoutError::ConfigNotFound(path.clone());
+        let check = check_config(&path, Err(&missing));
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.message.contains("does not exist"));
+        assert!(check.remedy.unwrap().contains("panout init"));
+    }
+
+    #[test]
+    fn test_workspace_hosts_check() {
+        let config = Config::from_str(
+            r#"
+            [workspace.good]
+            host = "me@10.0.0.1"
+            windows = [{ panes = 1 }]
+
+            [workspace.bad]
+            host = "justahost"
+            windows = [{ panes = 1 }]
+            "#,
+        )
+        .unwrap();
+
+        let check = check_workspace_hosts(&config);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.message.contains("bad"));
+        assert!(!check.message.contains("good"));
+    }
+
+    #[test]
+    fn test_base_index_check() {
+        assert_eq!(check_base_index(Some("0"), Some("0")).status, Status::Pass);
+        assert_eq!(check_base_index(None, None).status, Status::Pass);
+
+        let check = check_base_index(Some("1"), Some("1"));
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.message.contains("pane-base-index = 1"));
+    }
+}
@@ -10,9 +10,13 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum PanoutError {
     /// Config file does not exist at the expected path.
-    #[error("Config file not found: {0}")]
+    #[error("Config file not found: {0} (run `panout init` to create one)")]
     ConfigNotFound(PathBuf),
 
+    /// `panout init` found a config file already in place.
+    #[error("Config file already exists: {0}")]
+    ConfigExists(PathBuf),
+
     /// Could not determine the user's config directory.
     #[error("Could not determine config directory")]
     NoConfigDir,
@@ -25,6 +29,19 @@ pub enum PanoutError {
     #[error("Failed to parse config: {0}")]
     ParseError(#[from] toml::de::Error),
 
+    /// A config file's TOML failed to parse.
+    #[error("Failed to parse {path}: {source}")]
+    ConfigParse {
+        /// The file that failed to parse.
+        path: PathBuf,
+        /// The underlying TOML error.
+        source: toml::de::Error,
+    },
+
+    /// Config parsed but failed validation.
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
     /// Requested bundle does not exist in config.
     #[error("Bundle not found: {0}")]
     BundleNotFound(String),
@@ -37,6 +54,27 @@ pub enum PanoutError {
     #[error("Workspace not found: {0}")]
     WorkspaceNotFound(String),
 
+    /// A positional target names both a bundle and a workspace.
+    #[error("'{0}' is both a bundle and a workspace; use -b or -w to choose")]
+    AmbiguousTarget(String),
+
+    /// A pane working directory (`--dir` or a bundle's `dir`) doesn't exist.
+    #[error("Directory not found: {0}")]
+    DirNotFound(String),
+
+    /// A `--windows` filter names a window the workspace doesn't have.
+    #[error("Window not found: {0}")]
+    WindowNotFound(String),
+
+    /// The config's `version` is newer than this build understands.
+    #[error("Config requires newer panout: version {found} (supported: {supported})")]
+    UnsupportedConfigVersion {
+        /// `version` from the config.
+        found: u32,
+        /// Newest version this build supports.
+        supported: u32,
+    },
+
     /// Bundle reference (`@ref`) has invalid syntax.
     #[error("Invalid bundle reference: {0}")]
     InvalidRef(String),
@@ -45,14 +83,157 @@ pub enum PanoutError {
     #[error("Circular reference detected: {0}")]
     CircularRef(String),
 
+    /// A logical pane index doesn't exist in the window.
+    #[error("Pane {pane} is out of range (window has {count} panes)")]
+    PaneOutOfRange {
+        /// The requested logical pane index.
+        pane: u32,
+        /// Number of panes in the window.
+        count: usize,
+    },
+
+    /// The `tmux` executable couldn't be found.
+    #[error(
+        "tmux is not installed or not on PATH; install it with your package manager \
+         (e.g. `apt install tmux` or `brew install tmux`)"
+    )]
+    TmuxNotInstalled,
+
     /// A tmux command failed to execute.
     #[error("Tmux error: {0}")]
     TmuxError(String),
 
+    /// `{git_root}` was used outside a git repository (strict mode only).
+    #[error("{{git_root}} used outside a git repository: {0}")]
+    NotInGitRepo(String),
+
+    /// A `{name}` placeholder isn't a `[vars]` entry or a built-in token.
+    #[error("Unknown variable {{{name}}} in {target}")]
+    UnknownVariable {
+        /// The placeholder name, without braces or filter.
+        name: String,
+        /// Bundle or workspace the placeholder appeared in.
+        target: String,
+    },
+
+    /// A local hook command exited unsuccessfully.
+    #[error("Hook '{command}' failed ({status}): {stderr}")]
+    HookFailed {
+        /// The command that was run.
+        command: String,
+        /// How the command exited.
+        status: String,
+        /// Captured standard error.
+        stderr: String,
+    },
+
+    /// `panout doctor` found hard failures.
+    #[error("{0} check(s) failed")]
+    ChecksFailed(usize),
+
+    /// `panout last` was run before any run was recorded.
+    #[error("No previous run recorded")]
+    NoLastRun,
+
+    /// The recorded last run can't be replayed by this version of panout.
+    #[error("Cannot replay last run: {0}")]
+    InvalidLastRun(String),
+
+    /// The user declined (or couldn't be asked) to build a large layout.
+    #[error("Cancelled: the run would create {0} panes (pass --yes to skip the prompt)")]
+    NotConfirmed(u32),
+
     /// Command was run outside of a tmux session.
     #[error("Not running inside tmux")]
     NotInTmux,
 }
 
+/// Process exit codes as `(code, meaning)` pairs, for `--help` and the man page.
+///
+/// These are part of panout's interface: scripts rely on them, so existing
+/// codes must not change.
+pub const EXIT_CODES: &[(i32, &str)] = &[
+    (0, "success"),
+    (
+        1,
+        "other failure (hook failed, doctor checks failed, I/O error, ...)",
+    ),
+    (
+        2,
+        "config problem (missing, unparsable, or invalid config; also usage errors)",
+    ),
+    (3, "bundle, workspace, server, or window not found"),
+    (4, "a tmux command failed"),
+    (5, "not running inside tmux"),
+];
+
+impl PanoutError {
+    /// Process exit code for this error (see [`EXIT_CODES`]).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PanoutError::ConfigNotFound(_)
+            | PanoutError::ConfigExists(_)
+            | PanoutError::NoConfigDir
+            | PanoutError::ParseError(_)
+            | PanoutError::ConfigParse { .. }
+            | PanoutError::InvalidConfig(_)
+            | PanoutError::InvalidRef(_)
+            | PanoutError::CircularRef(_)
+            | PanoutError::NotInGitRepo(_)
+            | PanoutError::UnknownVariable { .. }
+            | PanoutError::DirNotFound(_)
+            | PanoutError::UnsupportedConfigVersion { .. }
+            | PanoutError::InvalidLastRun(_) => 2,
+            PanoutError::BundleNotFound(_)
+            | PanoutError::ServerNotFound(_)
+            | PanoutError::WorkspaceNotFound(_)
+            | PanoutError::WindowNotFound(_)
+            | PanoutError::AmbiguousTarget(_)
+            | PanoutError::NoLastRun => 3,
+            PanoutError::TmuxError(_)
+            | PanoutError::TmuxNotInstalled
+            | PanoutError::PaneOutOfRange { .. } => 4,
+            PanoutError::NotInTmux => 5,
+            PanoutError::IoError(_)
+            | PanoutError::HookFailed { .. }
+            | PanoutError::ChecksFailed(_)
+            | PanoutError::NotConfirmed(_) => 1,
+        }
+    }
+}
+
 /// Convenient Result type alias for panout operations.
 pub type Result<T> = std::result::Result<T, PanoutError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_stable() {
+        let parse_error = toml::from_str::<toml::Value>("=").unwrap_err();
+        let cases = [
+            (PanoutError::ConfigNotFound(PathBuf::from("x")), 2),
+            (PanoutError::ParseError(parse_error), 2),
+            (PanoutError::InvalidConfig("x".into()), 2),
+            (PanoutError::CircularRef("a".into()), 2),
+            (PanoutError::BundleNotFound("a".into()), 3),
+            (PanoutError::WorkspaceNotFound("a".into()), 3),
+            (PanoutError::ServerNotFound("a".into()), 3),
+            (PanoutError::TmuxError("x".into()), 4),
+            (PanoutError::TmuxNotInstalled, 4),
+            (PanoutError::NotInTmux, 5),
+            (PanoutError::ChecksFailed(1), 1),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.exit_code(), code, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_exit_codes_are_documented() {
+        for code in 0..=5 {
+            assert!(EXIT_CODES.iter().any(|&(c, _)| c == code), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Local setup and teardown hooks.
+//!
+//! Hooks run synchronously on the machine panout is invoked from, via
+//! `sh -c`, rather than being typed into a pane. This makes them suitable
+//! for one-time setup such as `docker compose up -d` that must finish
+//! before any pane starts (`before`), or notifications once everything is
+//! up (`post`).
+
+use crate::config::Cmd;
+use crate::error::{PanoutError, Result};
+use std::process::Command;
+
+/// Run a single hook command and wait for it to finish.
+///
+/// # Errors
+///
+/// - [`PanoutError::IoError`] if `sh` can't be spawned
+/// - [`PanoutError::HookFailed`] if the command exits non-zero, carrying its stderr
+pub fn run(command: &str) -> Result<()> {
+    let output = Command::new("sh").args(["-c", command]).output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(PanoutError::HookFailed {
+        command: command.to_string(),
+        status: output.status.code().map_or_else(
+            || "killed by signal".to_string(),
+            |c| format!("exit code {}", c),
+        ),
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    })
+}
+
+/// Run every command in a hook, stopping at the first failure.
+pub fn run_all(cmd: &Cmd) -> Result<()> {
+    for command in cmd.to_vec() {
+        run(&command)?;
+    }
+    Ok(())
+}
+
+/// Run every command in a hook, warning about failures instead of stopping.
+///
+/// Used for `post` hooks: the panes are already up, so a failed
+/// notification shouldn't fail the run. Returns how many commands failed.
+pub fn run_all_warn(cmd: &Cmd) -> usize {
+    let mut failed = 0;
+    for command in cmd.to_vec() {
+        if let Err(e) = run(&command) {
+            eprintln!("Warning: {}", e);
+            failed += 1;
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_success() {
+        assert!(run("true").is_ok());
+    }
+
+    #[test]
+    fn test_run_failure() {
+        assert!(matches!(
+            run("false"),
+            Err(PanoutError::HookFailed { command, .. }) if command == "false"
+        ));
+    }
+
+    #[test]
+    fn test_run_failure_carries_stderr() {
+        match run("echo 'compose not found' >&2; exit 3") {
+            Err(PanoutError::HookFailed { status, stderr, .. }) => {
+                assert_eq!(status, "exit code 3");
+                assert_eq!(stderr, "compose not found");
+            }
+            other => panic!("expected HookFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_run_all_warn_keeps_going() {
+        let cmd = Cmd::Multiple(vec!["false".into(), "true".into(), "exit 7".into()]);
+        assert_eq!(run_all_warn(&cmd), 2);
+    }
+
+    #[test]
+    fn test_run_all_stops_at_first_failure() {
+        let cmd = Cmd::Multiple(vec!["true".into(), "false".into(), "exit 7".into()]);
+        assert!(matches!(
+            run_all(&cmd),
+            Err(PanoutError::HookFailed { command, .. }) if command == "false"
+        ));
+    }
+}
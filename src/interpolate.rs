@@ -1,7 +1,28 @@
 //! Variable interpolation for commands.
 //!
-//! Provides `{user}` and `{ip}` placeholder expansion for commands
-//! that need to reference parts of an SSH host string.
+//! Provides `{user}` and `{host}` placeholder expansion for commands
+//! that need to reference parts of an SSH host string. `{ip}` is the older
+//! name for `{host}` and still works, although the value may be a DNS name.
+//!
+//! # Filters
+//!
+//! A token may carry a `:filter` suffix that transforms its value:
+//!
+//! - `{user:upper}` - uppercase the value
+//! - `{host:lower}` - lowercase the value
+//!
+//! Unknown tokens and unknown filters are left in the command untouched.
+//!
+//! # Git root
+//!
+//! `{git_root}` expands to the top of the current git repository. The root
+//! is looked up at most once per run through a [`GitRoot`].
+//!
+//! # User variables
+//!
+//! Entries of the config's `[vars]` table expand through [`expand_vars`].
+//! Unlike the built-in tokens, an unknown `{name}` there is an error rather
+//! than being passed to the shell.
 //!
 //! # Example
 //!
@@ -9,15 +30,23 @@
 //! use panout::interpolate::{parse_host, interpolate};
 //!
 //! let host = "admin@192.168.1.100";
-//! let (user, ip) = parse_host(host).unwrap();
+//! let (user, host) = parse_host(host).unwrap();
 //!
-//! let cmd = interpolate("cd /home/{user}/src", &user, &ip);
+//! let cmd = interpolate("cd /home/{user}/src", &user, &host);
 //! assert_eq!(cmd, "cd /home/admin/src");
 //! ```
 
-/// Parse a host string into (user, ip) components.
+use crate::error::{PanoutError, Result};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Parse a host string into (user, host) components.
 ///
-/// Expects format `user@ip` and returns `None` if the `@` is missing.
+/// Expects format `user@host` and returns `None` if the `@` is missing. The
+/// host may be an IPv4 address, a DNS name, or an IPv6 address; brackets
+/// (`[::1]`) and a trailing `:port` are dropped.
 ///
 /// # Examples
 ///
@@ -28,29 +57,293 @@
 ///     parse_host("admin@192.168.1.1"),
 ///     Some(("admin".to_string(), "192.168.1.1".to_string()))
 /// );
+/// assert_eq!(
+///     parse_host("admin@[::1]:22"),
+///     Some(("admin".to_string(), "::1".to_string()))
+/// );
 /// assert_eq!(parse_host("no-at-sign"), None);
 /// ```
 pub fn parse_host(host: &str) -> Option<(String, String)> {
-    let parts: Vec<&str> = host.splitn(2, '@').collect();
-    if parts.len() == 2 {
-        Some((parts[0].to_string(), parts[1].to_string()))
-    } else {
-        None
+    let (user, host) = host.split_once('@')?;
+    Some((user.to_string(), strip_port(host).to_string()))
+}
+
+/// The address part of `host`, without brackets or a `:port`.
+///
+/// A bare IPv6 address has several colons and is left alone; only a single
+/// colon followed by digits counts as a port.
+fn strip_port(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[')
+        && let Some((address, _)) = rest.split_once(']')
+    {
+        return address;
+    }
+    match host.split_once(':') {
+        Some((address, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => {
+            address
+        }
+        _ => host,
     }
 }
 
-/// Replace `{user}` and `{ip}` placeholders in a command string.
+/// Replace `{user}` and `{host}` placeholders in a command string.
+///
+/// `{ip}` is an alias for `{host}`. Tokens may use a `:upper` or `:lower`
+/// filter; anything unrecognized is passed through unchanged.
 ///
 /// # Examples
 ///
 /// ```
 /// use panout::interpolate::interpolate;
 ///
-/// let result = interpolate("ssh {user}@{ip}", "root", "10.0.0.1");
+/// let result = interpolate("ssh {user}@{host}", "root", "10.0.0.1");
 /// assert_eq!(result, "ssh root@10.0.0.1");
+///
+/// let result = interpolate("echo {user:upper}", "root", "10.0.0.1");
+/// assert_eq!(result, "echo ROOT");
+/// ```
+pub fn interpolate(command: &str, user: &str, host: &str) -> String {
+    substitute(command, |name| match name {
+        "user" => Some(user.to_string()),
+        "host" | "ip" => Some(host.to_string()),
+        _ => None,
+    })
+}
+
+/// Whether `command` has a `{user}`, `{host}`, or `{ip}` token for
+/// [`interpolate`] to fill in.
+pub fn has_host_tokens(command: &str) -> bool {
+    let found = std::cell::Cell::new(false);
+    substitute(command, |name| {
+        if matches!(name, "user" | "host" | "ip") {
+            found.set(true);
+        }
+        None
+    });
+    found.get()
+}
+
+/// Lazily resolved, cached git repository root for `{git_root}`.
+pub struct GitRoot {
+    resolver: Box<dyn Fn() -> Option<String>>,
+    cached: OnceCell<Option<String>>,
+}
+
+impl GitRoot {
+    /// Resolve with `git rev-parse --show-toplevel`, run from `dir` if given
+    /// or from the current directory otherwise.
+    pub fn new(dir: Option<&Path>) -> Self {
+        let dir: Option<PathBuf> = dir.map(Path::to_path_buf);
+        Self::with_resolver(move || {
+            let mut cmd = Command::new("git");
+            if let Some(ref d) = dir {
+                cmd.arg("-C").arg(d);
+            }
+            let output = cmd.args(["rev-parse", "--show-toplevel"]).output().ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            (!root.is_empty()).then_some(root)
+        })
+    }
+
+    /// Use a custom resolver, e.g. a fixed path in tests.
+    pub fn with_resolver(resolver: impl Fn() -> Option<String> + 'static) -> Self {
+        GitRoot {
+            resolver: Box::new(resolver),
+            cached: OnceCell::new(),
+        }
+    }
+
+    /// The repository root, or `None` outside a git repository.
+    ///
+    /// The resolver runs on first call only.
+    pub fn get(&self) -> Option<&str> {
+        self.cached.get_or_init(|| (self.resolver)()).as_deref()
+    }
+}
+
+/// Replace `{git_root}` tokens in a command.
+///
+/// The root is only looked up if the command uses the token. Outside a git
+/// repository the token is left as-is, or rejected when `strict` is set.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::NotInGitRepo`] in strict mode when the root can't
+/// be determined.
+pub fn expand_git_root(command: &str, git_root: &GitRoot, strict: bool) -> Result<String> {
+    if !command.contains("{git_root") {
+        return Ok(command.to_string());
+    }
+    match git_root.get() {
+        Some(root) => Ok(substitute(command, |name| {
+            (name == "git_root").then(|| root.to_string())
+        })),
+        None if strict => Err(PanoutError::NotInGitRepo(command.to_string())),
+        None => Ok(command.to_string()),
+    }
+}
+
+/// Tokens expanded later in a run, which [`expand_vars`] leaves alone.
+pub const BUILTIN_TOKENS: &[&str] = &["user", "ip", "host", "git_root"];
+
+/// Replace `[vars]` placeholders in a command or path.
+///
+/// Filters work as for the built-in tokens (`{project_dir:upper}`). Built-in
+/// tokens are left for later expansion. Braces that can't be a placeholder
+/// are ignored: `${HOME}`, `{a,b}`, and anything inside single quotes, such
+/// as `awk '{print}'`. `target` names the bundle or workspace for errors.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::UnknownVariable`] for a `{name}` that is neither a
+/// variable nor a built-in token.
+///
+/// # Examples
+///
 /// ```
-pub fn interpolate(command: &str, user: &str, ip: &str) -> String {
-    command.replace("{user}", user).replace("{ip}", ip)
+/// use panout::interpolate::expand_vars;
+/// use std::collections::HashMap;
+///
+/// let vars = HashMap::from([("port".to_string(), "8080".to_string())]);
+/// let cmd = expand_vars("serve -p {port}", &vars, "dev.web").unwrap();
+/// assert_eq!(cmd, "serve -p 8080");
+/// assert!(expand_vars("serve -p {prot}", &vars, "dev.web").is_err());
+/// ```
+pub fn expand_vars(command: &str, vars: &HashMap<String, String>, target: &str) -> Result<String> {
+    let known = |name: &str| vars.contains_key(name) || BUILTIN_TOKENS.contains(&name);
+    if let Some(name) = placeholders(command).into_iter().find(|name| !known(name)) {
+        return Err(PanoutError::UnknownVariable {
+            name,
+            target: target.to_string(),
+        });
+    }
+    // Replace only the placeholders found above, so braces the shell owns
+    // stay as written
+    let mut result = String::with_capacity(command.len());
+    let mut copied = 0;
+    for span in placeholder_spans(command) {
+        let token = &command[span.start + 1..span.end - 1];
+        if let Some(value) = expand_token(token, &|name: &str| vars.get(name).cloned()) {
+            result.push_str(&command[copied..span.start]);
+            result.push_str(&value);
+            copied = span.end;
+        }
+    }
+    result.push_str(&command[copied..]);
+    Ok(result)
+}
+
+/// Names of the `{name}` placeholders in a command.
+fn placeholders(command: &str) -> Vec<String> {
+    placeholder_spans(command)
+        .into_iter()
+        .map(|span| {
+            let token = &command[span.start + 1..span.end - 1];
+            token
+                .split_once(':')
+                .map_or(token, |(name, _)| name)
+                .to_string()
+        })
+        .collect()
+}
+
+/// Byte ranges of the `{name}` placeholders in a command, braces included.
+///
+/// Only identifier-like names count, and tokens after `$` or inside single
+/// quotes are skipped, since the shell owns those braces.
+fn placeholder_spans(command: &str) -> Vec<std::ops::Range<usize>> {
+    let bytes = command.as_bytes();
+    let mut spans = Vec::new();
+    let (mut single, mut double) = (false, false);
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' if !double => single = !single,
+            b'"' if !single => double = !double,
+            b'{' if !single && (i == 0 || bytes[i - 1] != b'$') => {
+                if let Some(len) = command[i + 1..].find('}') {
+                    let token = &command[i + 1..i + 1 + len];
+                    let name = token.split_once(':').map_or(token, |(name, _)| name);
+                    if is_identifier(name) {
+                        spans.push(i..i + len + 2);
+                        i += len + 2;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    spans
+}
+
+/// Whether `name` could be a variable: a letter or `_`, then word characters.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Expand `{name}` and `{name:filter}` tokens using `lookup`.
+///
+/// Tokens for which `lookup` returns `None`, or that use an unknown filter,
+/// are copied to the output verbatim.
+fn substitute<F>(command: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(command.len());
+    let mut rest = command;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let token = &after[..end];
+        match expand_token(token, &lookup) {
+            Some(value) => result.push_str(&value),
+            None => {
+                result.push('{');
+                result.push_str(token);
+                result.push('}');
+            }
+        }
+        rest = &after[end + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Expand a single token body (the text between the braces).
+fn expand_token<F>(token: &str, lookup: &F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let (name, filter) = match token.split_once(':') {
+        Some((name, filter)) => (name, Some(filter)),
+        None => (token, None),
+    };
+
+    let value = lookup(name)?;
+    match filter {
+        None => Some(value),
+        Some("upper") => Some(value.to_uppercase()),
+        Some("lower") => Some(value.to_lowercase()),
+        Some(_) => None,
+    }
 }
 
 #[cfg(test)]
@@ -66,6 +359,44 @@ mod tests {
         assert_eq!(parse_host("no-at-sign"), None);
     }
 
+    #[test]
+    fn test_parse_host_forms() {
+        let host = |h| parse_host(h).map(|(_, host)| host);
+        assert_eq!(host("admin@10.0.0.5:2222").as_deref(), Some("10.0.0.5"));
+        assert_eq!(host("admin@[::1]").as_deref(), Some("::1"));
+        assert_eq!(
+            host("admin@[fe80::1%eth0]:22").as_deref(),
+            Some("fe80::1%eth0")
+        );
+        assert_eq!(host("admin@2001:db8::7").as_deref(), Some("2001:db8::7"));
+        assert_eq!(
+            host("deploy@build.example.com").as_deref(),
+            Some("build.example.com")
+        );
+        assert_eq!(
+            host("deploy@build.example.com:22").as_deref(),
+            Some("build.example.com")
+        );
+        assert_eq!(host("-p 2222 admin@[::1]").as_deref(), Some("::1"));
+    }
+
+    #[test]
+    fn test_interpolate_host_and_ip() {
+        let (user, host) = parse_host("ops@[2001:db8::7]:22").unwrap();
+        assert_eq!(
+            interpolate("ping6 {host} && echo {user}@{ip}", &user, &host),
+            "ping6 2001:db8::7 && echo ops@2001:db8::7"
+        );
+    }
+
+    #[test]
+    fn test_has_host_tokens() {
+        assert!(has_host_tokens("ping {ip}"));
+        assert!(has_host_tokens("cd /home/{user:lower}"));
+        assert!(!has_host_tokens("echo {project} {hostname}"));
+        assert!(!has_host_tokens("awk '{print $1}'"));
+    }
+
     #[test]
     fn test_interpolate() {
         assert_eq!(
@@ -77,4 +408,126 @@ mod tests {
             "ssh root@10.0.0.1"
         );
     }
+
+    #[test]
+    fn test_interpolate_upper_filter() {
+        assert_eq!(
+            interpolate("echo {user:upper}", "admin", "192.168.1.1"),
+            "echo ADMIN"
+        );
+    }
+
+    #[test]
+    fn test_interpolate_lower_filter() {
+        assert_eq!(
+            interpolate("ping {host:lower}", "admin", "Build.Example.COM"),
+            "ping build.example.com"
+        );
+    }
+
+    #[test]
+    fn test_interpolate_unknown_filter_passthrough() {
+        assert_eq!(
+            interpolate("echo {user:reverse} {user}", "admin", "10.0.0.1"),
+            "echo {user:reverse} admin"
+        );
+    }
+
+    #[test]
+    fn test_interpolate_unknown_token_passthrough() {
+        assert_eq!(
+            interpolate("awk '{print $1}' {nope}", "admin", "10.0.0.1"),
+            "awk '{print $1}' {nope}"
+        );
+    }
+
+    #[test]
+    fn test_expand_git_root() {
+        let root = GitRoot::with_resolver(|| Some("/home/me/src/app".into()));
+        assert_eq!(
+            expand_git_root("cd {git_root}/web", &root, false).unwrap(),
+            "cd /home/me/src/app/web"
+        );
+    }
+
+    #[test]
+    fn test_git_root_resolved_once() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let root = GitRoot::with_resolver(move || {
+            counter.set(counter.get() + 1);
+            Some("/repo".into())
+        });
+
+        expand_git_root("cd {git_root}", &root, false).unwrap();
+        expand_git_root("ls {git_root}", &root, false).unwrap();
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_git_root_not_resolved_when_unused() {
+        let root = GitRoot::with_resolver(|| panic!("should not be called"));
+        assert_eq!(expand_git_root("make", &root, true).unwrap(), "make");
+    }
+
+    #[test]
+    fn test_git_root_outside_repo() {
+        let root = GitRoot::with_resolver(|| None);
+        assert_eq!(
+            expand_git_root("cd {git_root}", &root, false).unwrap(),
+            "cd {git_root}"
+        );
+        assert!(matches!(
+            expand_git_root("cd {git_root}", &root, true),
+            Err(PanoutError::NotInGitRepo(_))
+        ));
+    }
+
+    fn vars() -> HashMap<String, String> {
+        HashMap::from([
+            ("project_dir".to_string(), "~/src/megacorp".to_string()),
+            ("api_port".to_string(), "8080".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        assert_eq!(
+            expand_vars(
+                "cd {project_dir} && serve -p {api_port}",
+                &vars(),
+                "dev.api"
+            )
+            .unwrap(),
+            "cd ~/src/megacorp && serve -p 8080"
+        );
+        assert_eq!(
+            expand_vars("echo {project_dir:upper}", &vars(), "dev.api").unwrap(),
+            "echo ~/SRC/MEGACORP"
+        );
+    }
+
+    #[test]
+    fn test_expand_vars_leaves_builtins_and_shell_braces() {
+        let cmd = "ssh {user}@{ip} && cd {git_root} && echo ${HOME} {a,b} && awk '{print}'";
+        assert_eq!(expand_vars(cmd, &vars(), "dev.api").unwrap(), cmd);
+    }
+
+    #[test]
+    fn test_expand_vars_leaves_shell_variable_with_same_name() {
+        let vars = HashMap::from([("port".to_string(), "8080".to_string())]);
+        assert_eq!(
+            expand_vars("echo ${port} '{port}' {port} \"{port}\"", &vars, "dev.api").unwrap(),
+            "echo ${port} '{port}' 8080 \"8080\""
+        );
+    }
+
+    #[test]
+    fn test_expand_vars_unknown_variable() {
+        let err = expand_vars("cd {projct_dir}", &vars(), "dev.api").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown variable {projct_dir} in dev.api");
+    }
 }
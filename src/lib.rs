@@ -39,22 +39,45 @@
 //!
 //! - [`config`]: TOML configuration parsing and data structures
 //! - [`cli`]: Command-line argument parsing with clap
+//! - [`condition`]: Platform conditions for commands (`when = "os == linux"`)
+//! - [`completions`]: Shell completion scripts (`panout completions`)
 //! - [`loader`]: Config file discovery and loading
 //! - [`resolver`]: Bundle reference (`@ref`) expansion
 //! - [`tmux`]: Tmux pane and window operations
 //! - [`ssh`]: SSH session management
+//! - [`hook`]: Local setup hooks run before panes are created
+//! - [`doctor`]: Environment diagnostics (`panout doctor`)
+//! - [`man`]: Man page generation (`panout man`)
 //! - [`interpolate`]: Variable substitution (`{user}`, `{ip}`)
+//! - [`listing`]: Target listings (`--list`) as text or JSON
+//! - [`path`]: Local path helpers (`~` expansion)
+//! - [`report`]: JSON run summaries (`--report`)
+//! - [`run`]: Running bundles, workspaces, and servers ([`RunOptions`])
+//! - [`shell`]: Shell quoting and `export` generation
+//! - [`state`]: Persisted state such as the last run (`panout last`)
 //! - [`error`]: Error types
 
 pub mod cli;
+pub mod completions;
+pub mod condition;
 pub mod config;
+pub mod doctor;
 pub mod error;
+pub mod hook;
 pub mod interpolate;
+pub mod listing;
 pub mod loader;
+pub mod man;
+pub mod path;
+pub mod report;
 pub mod resolver;
+pub mod run;
 pub mod session;
+pub mod shell;
 pub mod ssh;
+pub mod state;
 pub mod tmux;
 
 pub use config::{BundleEntry, Cmd, Config, Layout, WindowDef, Workspace};
 pub use error::{PanoutError, Result};
+pub use run::{RunOptions, run_bundle, run_server, run_workspace};
@@ -0,0 +1,352 @@
+//! Target listings (`--list`).
+//!
+//! [`Listings`] collects the bundles, workspaces, and servers from a config,
+//! with their descriptions, and renders them as indented text for people,
+//! one name per line for scripts, or JSON for tooling such as editor plugins.
+
+use crate::condition::{LocalMachine, Platform};
+use crate::config::{Cmd, Config};
+use crate::error::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Output format for `--list`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum ListFormat {
+    /// Headed, indented sections.
+    #[default]
+    Text,
+    /// One target name per line, no headers.
+    Plain,
+    /// A JSON object with `bundles`, `workspaces`, and `servers` arrays.
+    Json,
+}
+
+/// One listed target.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListEntry {
+    /// Bundle path (`group.name`), workspace name, or server name.
+    pub name: String,
+    /// The target's `description`, if it has one.
+    pub description: Option<String>,
+    /// The first command the target runs, as written in the config.
+    pub command: Option<String>,
+    /// `[aliases]` that name this bundle.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Whether the bundle's `when` fails on this machine, so it won't run.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+}
+
+impl ListEntry {
+    /// The name as shown in text output, with any aliases in parentheses
+    /// and a `[skipped]` mark.
+    fn label(&self) -> String {
+        let mut label = self.name.clone();
+        if !self.aliases.is_empty() {
+            label = format!("{} ({})", label, self.aliases.join(", "));
+        }
+        if self.skipped {
+            label.push_str(" [skipped]");
+        }
+        label
+    }
+}
+
+/// Every runnable target in a config, sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Listings {
+    /// Bundles.
+    pub bundles: Vec<ListEntry>,
+    /// Workspaces.
+    pub workspaces: Vec<ListEntry>,
+    /// Servers.
+    pub servers: Vec<ListEntry>,
+}
+
+impl Listings {
+    /// Collect the targets defined in `config`.
+    ///
+    /// A workspace's command is the first one of its first window.
+    pub fn from_config(config: &Config) -> Self {
+        let bundles = config
+            .list_bundles()
+            .into_iter()
+            .filter_map(|name| {
+                let bundle = config.get_bundle(&name)?;
+                let command = first_command(Some(&bundle.cmd));
+                let aliases = config.aliases_for(&name);
+                let skipped = config.bundle_skipped(&name, &LocalMachine);
+                Some(ListEntry {
+                    aliases,
+                    skipped,
+                    ..entry(name, bundle.description.as_ref(), command)
+                })
+            })
+            .collect();
+        let workspaces = config
+            .list_workspaces()
+            .into_iter()
+            .map(|name| {
+                let ws = &config.workspaces[&name];
+                let command = first_command(ws.windows.first().and_then(|w| w.cmd.as_ref()));
+                entry(name, ws.description.as_ref(), command)
+            })
+            .collect();
+        let servers = config
+            .list_servers()
+            .into_iter()
+            .map(|name| {
+                let server = &config.servers[&name];
+                let command = first_command(server.cmd.as_ref());
+                entry(name, server.description.as_ref(), command)
+            })
+            .collect();
+
+        Listings {
+            bundles,
+            workspaces,
+            servers,
+        }
+    }
+
+    /// Keep only the bundles tagged `tag`, dropping workspaces and servers.
+    pub fn tagged(mut self, config: &Config, tag: &str) -> Self {
+        let names = config.bundles_with_tag(tag);
+        self.bundles.retain(|entry| names.contains(&entry.name));
+        self.workspaces.clear();
+        self.servers.clear();
+        self
+    }
+
+    /// Render the listings in `format`, ending with a newline unless empty.
+    ///
+    /// With `long`, text output also shows each target's first command.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::IoError`](crate::PanoutError::IoError) if JSON
+    /// serialization fails.
+    pub fn render(&self, format: ListFormat, long: bool) -> Result<String> {
+        match format {
+            ListFormat::Text => Ok(self.to_text(long)),
+            ListFormat::Plain => Ok(self.to_plain()),
+            ListFormat::Json => {
+                let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+                Ok(json + "\n")
+            }
+        }
+    }
+
+    /// Headed sections, skipping kinds with no targets.
+    ///
+    /// Bundle aliases follow the name in parentheses. Descriptions line up in
+    /// one column across every section.
+    fn to_text(&self, long: bool) -> String {
+        let sections = [
+            ("Bundles", &self.bundles),
+            ("Workspaces", &self.workspaces),
+            ("Servers", &self.servers),
+        ];
+        let width = self.entries().map(|e| e.label().len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for (heading, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(heading);
+            out.push_str(":\n");
+            for entry in entries {
+                let label = entry.label();
+                match entry.description {
+                    Some(ref desc) => {
+                        out.push_str(&format!("  {:<width$}  — {}\n", label, desc));
+                    }
+                    None => out.push_str(&format!("  {}\n", label)),
+                }
+                if long && let Some(ref command) = entry.command {
+                    out.push_str(&format!("      $ {}\n", command));
+                }
+            }
+        }
+        out
+    }
+
+    /// Bare names, bundles first, then workspaces, then servers.
+    fn to_plain(&self) -> String {
+        self.entries().map(|e| format!("{}\n", e.name)).collect()
+    }
+
+    /// Every entry: bundles, then workspaces, then servers.
+    fn entries(&self) -> impl Iterator<Item = &ListEntry> {
+        [&self.bundles, &self.workspaces, &self.servers]
+            .into_iter()
+            .flatten()
+    }
+}
+
+/// Build a [`ListEntry`], copying the description.
+fn entry(name: String, description: Option<&String>, command: Option<String>) -> ListEntry {
+    ListEntry {
+        name,
+        description: description.cloned(),
+        command,
+        aliases: Vec::new(),
+        skipped: false,
+    }
+}
+
+/// The first command in `cmd`, unexpanded (`@ref`s stay as written).
+///
+/// Key sequences are shown as `keys C-c ...`; conditional commands show
+/// their first command whatever the platform, and per-platform ones the
+/// current OS's.
+fn first_command(cmd: Option<&Cmd>) -> Option<String> {
+    match cmd? {
+        Cmd::Keys { keys } => Some(format!("keys {}", keys.join(" "))),
+        Cmd::When { cmd, .. } => first_command(Some(cmd)),
+        cmd @ Cmd::PerPlatform(_) => first_command(cmd.for_os(&Platform::current().os)),
+        cmd => cmd.to_vec().into_iter().next(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn listings() -> Listings {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            description = "vite dev server"
+
+            [dev.api]
+            cmd = "cargo run"
+
+            [aliases]
+            a = "dev.api"
+
+            [servers.prod]
+            host = "deploy@prod"
+
+            [workspace.app]
+            windows = [{ panes = 2 }]
+            "#,
+        )
+        .unwrap();
+        Listings::from_config(&config)
+    }
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            listings().render(ListFormat::Text, false).unwrap(),
+            "Bundles:\n  dev.api (a)\n  dev.web      — vite dev server\n\n\
+             Workspaces:\n  app\n\nServers:\n  prod\n"
+        );
+    }
+
+    #[test]
+    fn test_render_text_long() {
+        let text = listings().render(ListFormat::Text, true).unwrap();
+        assert!(text.starts_with("Bundles:\n  dev.api (a)\n      $ cargo run\n"));
+    }
+
+    #[test]
+    fn test_render_plain() {
+        assert_eq!(
+            listings().render(ListFormat::Plain, false).unwrap(),
+            "dev.api\ndev.web\napp\nprod\n"
+        );
+    }
+
+    #[test]
+    fn test_tagged_keeps_only_tagged_bundles() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+
+            [dev.api]
+            cmd = "cargo run"
+            tags = ["rust"]
+
+            [workspace.app]
+            windows = [{ panes = 2 }]
+            "#,
+        )
+        .unwrap();
+        let listings = Listings::from_config(&config).tagged(&config, "rust");
+
+        assert_eq!(
+            listings.render(ListFormat::Plain, false).unwrap(),
+            "dev.api\n"
+        );
+    }
+
+    #[test]
+    fn test_skipped_bundles_are_marked() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            when = { env = "PANOUT_TEST_NEVER_SET" }
+            "#,
+        )
+        .unwrap();
+        let listings = Listings::from_config(&config);
+
+        assert_eq!(
+            listings.render(ListFormat::Text, false).unwrap(),
+            "Bundles:\n  dev.web [skipped]\n"
+        );
+        let json = listings.render(ListFormat::Json, false).unwrap();
+        assert!(json.contains("\"skipped\": true"));
+    }
+
+    #[test]
+    fn test_desc_is_an_alias_for_description() {
+        let config = Config::from_str(
+            r#"
+            [dev.frontend]
+            cmd = "npm run dev"
+            desc = "runs the vite dev server"
+
+            [dev.plain]
+            cmd = "make"
+
+            [workspace.app]
+            desc = "the whole stack"
+            windows = [{ panes = 1 }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Listings::from_config(&config)
+                .render(ListFormat::Text, false)
+                .unwrap(),
+            "Bundles:\n  dev.frontend  — runs the vite dev server\n  dev.plain\n\n\
+             Workspaces:\n  app           — the whole stack\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = listings().render(ListFormat::Json, false).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["bundles"][0]["name"], "dev.api");
+        assert_eq!(value["bundles"][0]["command"], "cargo run");
+        assert_eq!(value["bundles"][0]["aliases"][0], "a");
+        assert_eq!(value["bundles"][1]["description"], "vite dev server");
+        assert_eq!(value["workspaces"][0]["name"], "app");
+        assert_eq!(value["servers"][0]["name"], "prod");
+    }
+}
@@ -1,80 +1,301 @@
 //! Config file discovery and loading.
 //!
 //! Handles finding the config file across different platforms and loading it.
-//! The search order is:
+//! An explicit `--config` path or `PANOUT_CONFIG` skips the search (see
+//! [`resolve_config_path`]). Otherwise the search order is:
 //!
-//! 1. `$XDG_CONFIG_HOME/panout/config.toml`
-//! 2. `~/.config/panout/config.toml`
-//! 3. Platform default (e.g., `~/Library/Application Support` on macOS)
+//! 1. `.panout.toml` in the current directory or a parent (project config)
+//! 2. `$XDG_CONFIG_HOME/panout/config.toml`
+//! 3. `~/.config/panout/config.toml`
+//! 4. Platform default (e.g., `~/Library/Application Support` on macOS)
+//!
+//! A project config takes precedence over the global one: when found, it is
+//! loaded instead.
+//!
+//! A config can `include` other files, whose bundles, workspaces, and
+//! servers are merged into it (see [`load_config`]).
 
 use crate::config::Config;
 use crate::error::{PanoutError, Result};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// File name of a per-project config.
+pub const PROJECT_CONFIG_NAME: &str = ".panout.toml";
+
+/// Environment variable naming the config file to use.
+pub const CONFIG_ENV: &str = "PANOUT_CONFIG";
+
+/// Commented example config written by `panout init`.
+pub const STARTER_CONFIG: &str = include_str!("starter_config.toml");
+
+/// Find a project config by walking up from `start`.
+///
+/// Each directory is checked for [`PROJECT_CONFIG_NAME`]. The walk stops
+/// after the first directory containing `.git` (the repository root) or at
+/// the filesystem root, so configs outside the current repository are never
+/// picked up.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(PROJECT_CONFIG_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
 
 /// Determine the config file path.
 ///
-/// Checks locations in order of preference:
+/// Checks locations in order of preference, returning the first that exists:
 /// 1. `$XDG_CONFIG_HOME/panout/config.toml` (if XDG_CONFIG_HOME is set)
 /// 2. `~/.config/panout/config.toml` (common on Linux, often used on macOS)
-/// 3. Platform default via `dirs::config_dir()`
+/// 3. Platform default via `dirs::config_dir()` (e.g.
+///    `~/Library/Application Support/panout/config.toml` on macOS)
 ///
-/// If no existing config is found, returns `~/.config/panout/config.toml`
-/// as the default location for new configs.
+/// If no existing config is found, returns
+/// `$XDG_CONFIG_HOME/panout/config.toml` (or `~/.config/panout/config.toml`
+/// when XDG_CONFIG_HOME isn't set) as the location for new configs.
 ///
 /// # Errors
 ///
 /// Returns [`PanoutError::NoConfigDir`] if the home directory cannot be determined.
 pub fn default_config_path() -> Result<PathBuf> {
-    // Check XDG_CONFIG_HOME first
-    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-        let path = PathBuf::from(xdg).join("panout").join("config.toml");
-        if path.exists() {
-            return Ok(path);
-        }
-    }
-
-    // Check ~/.config (common on Linux and often used on macOS)
-    if let Some(home) = dirs::home_dir() {
-        let path = home.join(".config").join("panout").join("config.toml");
-        if path.exists() {
-            return Ok(path);
-        }
-    }
+    let xdg = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    config_path_in(xdg, dirs::home_dir(), dirs::config_dir())
+}
 
-    // Fall back to platform default
-    let config_dir = dirs::config_dir().ok_or(PanoutError::NoConfigDir)?;
-    let _path = config_dir.join("panout").join("config.toml");
+/// [`default_config_path`] for the given XDG, home, and platform directories.
+fn config_path_in(
+    xdg: Option<PathBuf>,
+    home: Option<PathBuf>,
+    platform: Option<PathBuf>,
+) -> Result<PathBuf> {
+    let home_config = home.map(|home| home.join(".config"));
+    let file = |dir: PathBuf| dir.join("panout").join("config.toml");
 
-    // Return ~/.config path as default location for new configs
-    if let Some(home) = dirs::home_dir() {
-        Ok(home.join(".config").join("panout").join("config.toml"))
-    } else {
-        Err(PanoutError::NoConfigDir)
+    let new_config_dir = xdg.clone().or_else(|| home_config.clone());
+    let candidates = [xdg, home_config, platform];
+    if let Some(found) = candidates
+        .into_iter()
+        .flatten()
+        .map(file)
+        .find(|p| p.is_file())
+    {
+        return Ok(found);
     }
+
+    // Nothing exists yet: new configs go under XDG_CONFIG_HOME or ~/.config
+    new_config_dir.map(file).ok_or(PanoutError::NoConfigDir)
 }
 
 /// Load and parse a config file from the given path.
 ///
+/// Files listed in `include` are loaded too, relative to the including
+/// file's directory and with glob patterns (`homelab/*.toml`) expanded in
+/// sorted order. Their bundles, workspaces, and servers are merged in;
+/// everything else (`defaults`, `vars`, `aliases`, `version`) comes from the
+/// main file only. Included files may include others.
+///
 /// # Errors
 ///
-/// - [`PanoutError::ConfigNotFound`] if the file doesn't exist
+/// - [`PanoutError::ConfigNotFound`] if the file, or a non-glob include,
+///   doesn't exist
 /// - [`PanoutError::IoError`] if reading fails
-/// - [`PanoutError::ParseError`] if TOML parsing fails
-pub fn load_config(path: &PathBuf) -> Result<Config> {
+/// - [`PanoutError::ConfigParse`] naming the file if TOML parsing fails
+/// - [`PanoutError::InvalidConfig`] if two files define the same bundle,
+///   workspace, or server, or includes form a cycle
+pub fn load_config(path: &Path) -> Result<Config> {
     if !path.exists() {
-        return Err(PanoutError::ConfigNotFound(path.clone()));
+        return Err(PanoutError::ConfigNotFound(path.to_path_buf()));
     }
-    let contents = std::fs::read_to_string(path)?;
-    let config = Config::from_str(&contents)?;
+    let mut chain = Vec::new();
+    let mut loaded = HashSet::new();
+    let (mut config, _) = load_with_includes(path, &mut chain, &mut loaded)?;
+    config.include.clear();
     Ok(config)
 }
 
+/// Where each bundle path, workspace, and server came from, for errors.
+type Origins = HashMap<String, PathBuf>;
+
+/// Load `path` and, recursively, its includes.
+///
+/// `chain` holds the files currently being loaded, to catch cycles.
+/// `loaded` holds every file loaded so far: a file included twice, e.g.
+/// by two files that share it, only counts the first time.
+fn load_with_includes(
+    path: &Path,
+    chain: &mut Vec<PathBuf>,
+    loaded: &mut HashSet<PathBuf>,
+) -> Result<(Config, Origins)> {
+    let canonical = path.canonicalize()?;
+    if chain.contains(&canonical) {
+        let cycle: Vec<String> = chain
+            .iter()
+            .skip_while(|p| **p != canonical)
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(PanoutError::InvalidConfig(format!(
+            "include cycle: {}",
+            cycle.join(" -> ")
+        )));
+    }
+
+    if !loaded.insert(canonical.clone()) {
+        return Ok((Config::default(), Origins::new()));
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+    let mut config = Config::from_str(&contents).map_err(|source| PanoutError::ConfigParse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    for warning in &mut config.parse_warnings {
+        *warning = format!("{}: {}", path.display(), warning);
+    }
+    let mut origins = definitions(&config)
+        .into_iter()
+        .map(|key| (key, path.to_path_buf()))
+        .collect();
+
+    chain.push(canonical);
+    let base = path.parent().unwrap_or(Path::new("."));
+    for pattern in std::mem::take(&mut config.include) {
+        for file in include_paths(base, &pattern)? {
+            let (included, included_origins) = load_with_includes(&file, chain, loaded)?;
+            merge_included(&mut config, &mut origins, included, included_origins)?;
+        }
+    }
+    chain.pop();
+
+    Ok((config, origins))
+}
+
+/// Files matched by one `include` entry, relative to `base`.
+///
+/// A glob may match nothing; a plain path must exist.
+fn include_paths(base: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let full = base.join(crate::path::expand_tilde(pattern));
+    if !pattern.contains(['*', '?', '[']) {
+        if !full.is_file() {
+            return Err(PanoutError::ConfigNotFound(full));
+        }
+        return Ok(vec![full]);
+    }
+
+    let full = full.to_string_lossy();
+    let paths = glob::glob(&full).map_err(|e| {
+        PanoutError::InvalidConfig(format!("bad include pattern {}: {}", pattern, e))
+    })?;
+    let mut files: Vec<PathBuf> = paths
+        .filter_map(|p| p.ok())
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Keys naming everything a config defines that an include could clash with.
+fn definitions(config: &Config) -> Vec<String> {
+    let bundles = config
+        .list_bundles()
+        .into_iter()
+        .map(|b| format!("bundle {}", b));
+    let workspaces = config.workspaces.keys().map(|w| format!("workspace {}", w));
+    let servers = config.servers.keys().map(|s| format!("server {}", s));
+    bundles.chain(workspaces).chain(servers).collect()
+}
+
+/// Merge an included config's bundles, workspaces, and servers into `config`.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::InvalidConfig`] naming both files if anything is
+/// defined twice.
+fn merge_included(
+    config: &mut Config,
+    origins: &mut Origins,
+    included: Config,
+    included_origins: Origins,
+) -> Result<()> {
+    let mut keys: Vec<_> = included_origins.keys().collect();
+    keys.sort();
+    for key in keys {
+        if let Some(first) = origins.get(key) {
+            return Err(PanoutError::InvalidConfig(format!(
+                "{} is defined in both {} and {}",
+                key,
+                first.display(),
+                included_origins[key].display()
+            )));
+        }
+    }
+
+    for (group, entries) in included.bundles {
+        config.bundles.entry(group).or_default().extend(entries);
+    }
+    config.workspaces.extend(included.workspaces);
+    config.servers.extend(included.servers);
+    config.parse_warnings.extend(included.parse_warnings);
+    origins.extend(included_origins);
+    Ok(())
+}
+
 /// Load config from the default path.
 ///
-/// Convenience wrapper that combines [`default_config_path`] and [`load_config`].
+/// Uses a project config found from the current directory (see
+/// [`find_project_config`]) if there is one, otherwise the global config
+/// from [`default_config_path`].
 pub fn load_default_config() -> Result<Config> {
-    let path = default_config_path()?;
-    load_config(&path)
+    load_config(&discover_config_path()?)
+}
+
+/// The config file to load.
+///
+/// Precedence: `cli_override` (`--config`) > [`CONFIG_ENV`] > discovery (a
+/// project config, then [`default_config_path`]). An explicit path is used
+/// as given, so [`load_config`] reports it as missing rather than falling
+/// back to another file.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::NoConfigDir`] if discovery can't find a home
+/// directory.
+pub fn resolve_config_path(cli_override: Option<&Path>) -> Result<PathBuf> {
+    let env = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty());
+    choose_config_path(cli_override, env.map(PathBuf::from), discover_config_path)
+}
+
+/// [`resolve_config_path`] with the environment value and discovery supplied.
+fn choose_config_path(
+    cli_override: Option<&Path>,
+    env: Option<PathBuf>,
+    discover: impl FnOnce() -> Result<PathBuf>,
+) -> Result<PathBuf> {
+    match (cli_override, env) {
+        (Some(path), _) => Ok(path.to_path_buf()),
+        (None, Some(path)) => Ok(path),
+        (None, None) => discover(),
+    }
+}
+
+/// A project config found from the current directory, or the global one.
+fn discover_config_path() -> Result<PathBuf> {
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| find_project_config(&cwd));
+    match project {
+        Some(path) => Ok(path),
+        None => default_config_path(),
+    }
 }
 
 /// Ensure the config directory exists, creating it if necessary.
@@ -87,3 +308,333 @@ pub fn ensure_config_dir() -> Result<PathBuf> {
     }
     Ok(path)
 }
+
+/// Write the starter config to the default location (`panout init`).
+///
+/// Returns the path written.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::ConfigExists`] if a config is already there.
+pub fn init_config() -> Result<PathBuf> {
+    let path = ensure_config_dir()?;
+    write_starter_config(&path)?;
+    Ok(path)
+}
+
+/// Write [`STARTER_CONFIG`] to `path`, creating parent directories.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::ConfigExists`] instead of overwriting a file.
+pub fn write_starter_config(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Err(PanoutError::ConfigExists(path.to_path_buf()));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, STARTER_CONFIG)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_starter_config_is_valid() {
+        let config = Config::from_str(STARTER_CONFIG).unwrap();
+        assert!(config.validate(&config.strictness(true)).is_ok());
+        assert!(config.get_bundle("dev.all").is_some());
+    }
+
+    /// Held by tests that change the process environment.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_init_config_into_xdg_dir() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let xdg = tempfile::tempdir().unwrap();
+        let previous = std::env::var_os("XDG_CONFIG_HOME");
+        // SAFETY: tests touching the environment hold ENV_LOCK
+        unsafe { std::env::set_var("XDG_CONFIG_HOME", xdg.path()) };
+
+        let written = init_config();
+        let again = init_config();
+        match previous {
+            // SAFETY: as above
+            Some(value) => unsafe { std::env::set_var("XDG_CONFIG_HOME", value) },
+            None => unsafe { std::env::remove_var("XDG_CONFIG_HOME") },
+        }
+
+        let path = xdg.path().join("panout").join("config.toml");
+        assert_eq!(written.unwrap(), path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), STARTER_CONFIG);
+        // A second run refuses instead of clobbering the file
+        assert!(matches!(again, Err(PanoutError::ConfigExists(p)) if p == path));
+    }
+
+    #[test]
+    fn test_write_starter_config_keeps_existing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("panout").join("config.toml");
+
+        write_starter_config(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), STARTER_CONFIG);
+
+        // A second run must not clobber the user's edits
+        fs::write(&path, "[mine.x]\ncmd = \"ls\"\n").unwrap();
+        assert!(matches!(
+            write_starter_config(&path),
+            Err(PanoutError::ConfigExists(p)) if p == path
+        ));
+        assert!(fs::read_to_string(&path).unwrap().contains("mine"));
+    }
+
+    /// XDG, home, and platform config dirs under a temp dir, with a config
+    /// written into the ones named in `existing`.
+    fn config_dirs(existing: &[&str]) -> (tempfile::TempDir, [PathBuf; 3]) {
+        let tmp = tempfile::tempdir().unwrap();
+        let xdg = tmp.path().join("xdg");
+        let home = tmp.path().join("home");
+        let platform = tmp.path().join("home/Library/Application Support");
+        let home_config = home.join(".config");
+        for (name, dir) in [
+            ("xdg", &xdg),
+            ("home", &home_config),
+            ("platform", &platform),
+        ] {
+            if existing.contains(&name) {
+                fs::create_dir_all(dir.join("panout")).unwrap();
+                fs::write(dir.join("panout").join("config.toml"), "").unwrap();
+            }
+        }
+        (tmp, [xdg, home, platform])
+    }
+
+    fn config_path(dirs: &[PathBuf; 3]) -> PathBuf {
+        let [xdg, home, platform] = dirs.clone();
+        config_path_in(Some(xdg), Some(home), Some(platform)).unwrap()
+    }
+
+    #[test]
+    fn test_config_path_prefers_xdg() {
+        let (_tmp, dirs) = config_dirs(&["xdg", "home", "platform"]);
+        assert_eq!(config_path(&dirs), dirs[0].join("panout/config.toml"));
+    }
+
+    #[test]
+    fn test_config_path_home_config() {
+        let (_tmp, dirs) = config_dirs(&["home", "platform"]);
+        assert_eq!(
+            config_path(&dirs),
+            dirs[1].join(".config/panout/config.toml")
+        );
+    }
+
+    #[test]
+    fn test_config_path_platform_dir() {
+        let (_tmp, dirs) = config_dirs(&["platform"]);
+        assert_eq!(config_path(&dirs), dirs[2].join("panout/config.toml"));
+    }
+
+    #[test]
+    fn test_config_path_defaults_to_xdg_then_home_config() {
+        let (_tmp, dirs) = config_dirs(&[]);
+        assert_eq!(config_path(&dirs), dirs[0].join("panout/config.toml"));
+        let [_, home, platform] = dirs.clone();
+        assert_eq!(
+            config_path_in(None, Some(home), Some(platform)).unwrap(),
+            dirs[1].join(".config/panout/config.toml")
+        );
+        assert!(matches!(
+            config_path_in(None, None, None),
+            Err(PanoutError::NoConfigDir)
+        ));
+    }
+
+    #[test]
+    fn test_config_path_precedence() {
+        let flag = Path::new("/flag.toml");
+        let env = || Some(PathBuf::from("/env.toml"));
+        let discover = || Ok(PathBuf::from("/found.toml"));
+
+        assert_eq!(
+            choose_config_path(Some(flag), env(), discover).unwrap(),
+            flag
+        );
+        assert_eq!(
+            choose_config_path(None, env(), discover).unwrap(),
+            env().unwrap()
+        );
+        assert_eq!(
+            choose_config_path(None, None, discover).unwrap(),
+            PathBuf::from("/found.toml")
+        );
+    }
+
+    #[test]
+    fn test_explicit_config_path_must_exist() {
+        let tmp = tempfile::tempdir().unwrap();
+        let missing = tmp.path().join("work.toml");
+        let path = choose_config_path(Some(&missing), None, || panic!("no discovery")).unwrap();
+
+        assert!(matches!(
+            load_config(&path),
+            Err(PanoutError::ConfigNotFound(p)) if p == missing
+        ));
+    }
+
+    #[test]
+    fn test_find_project_config_in_parent() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nested = tmp.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(tmp.path().join("a").join(PROJECT_CONFIG_NAME), "").unwrap();
+
+        assert_eq!(
+            find_project_config(&nested),
+            Some(tmp.path().join("a").join(PROJECT_CONFIG_NAME))
+        );
+    }
+
+    #[test]
+    fn test_find_project_config_prefers_nearest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nested = tmp.path().join("a");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(tmp.path().join(PROJECT_CONFIG_NAME), "").unwrap();
+        fs::write(nested.join(PROJECT_CONFIG_NAME), "").unwrap();
+
+        assert_eq!(
+            find_project_config(&nested),
+            Some(nested.join(PROJECT_CONFIG_NAME))
+        );
+    }
+
+    #[test]
+    fn test_find_project_config_stops_at_git_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path().join("repo");
+        let nested = repo.join("src");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(&nested).unwrap();
+        // Outside the repository, so it must not be found
+        fs::write(tmp.path().join(PROJECT_CONFIG_NAME), "").unwrap();
+
+        assert_eq!(find_project_config(&nested), None);
+
+        fs::write(repo.join(PROJECT_CONFIG_NAME), "").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(repo.join(PROJECT_CONFIG_NAME))
+        );
+    }
+
+    #[test]
+    fn test_load_config_merges_includes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("config.toml");
+        fs::create_dir_all(tmp.path().join("homelab")).unwrap();
+        fs::write(
+            &main,
+            "include = [\"work.toml\", \"homelab/*.toml\"]\n[dev.web]\ncmd = \"a\"\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("work.toml"), "[dev.api]\ncmd = \"b\"\n").unwrap();
+        fs::write(
+            tmp.path().join("homelab").join("nas.toml"),
+            "[servers.nas]\nhost = \"me@nas\"\n",
+        )
+        .unwrap();
+
+        let config = load_config(&main).unwrap();
+        assert!(config.get_bundle("dev.web").is_some());
+        assert!(config.get_bundle("dev.api").is_some());
+        assert!(config.servers.contains_key("nas"));
+        assert!(config.include.is_empty());
+    }
+
+    #[test]
+    fn test_load_config_duplicate_names_both_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("config.toml");
+        fs::write(&main, "include = [\"work.toml\"]\n[dev.web]\ncmd = \"a\"\n").unwrap();
+        fs::write(tmp.path().join("work.toml"), "[dev.web]\ncmd = \"b\"\n").unwrap();
+
+        let err = load_config(&main).unwrap_err().to_string();
+        assert!(err.contains("bundle dev.web is defined in both"), "{}", err);
+        assert!(
+            err.contains("config.toml") && err.contains("work.toml"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_load_config_include_cycle() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("a.toml");
+        fs::write(&main, "include = [\"b.toml\"]\n").unwrap();
+        fs::write(tmp.path().join("b.toml"), "include = [\"a.toml\"]\n").unwrap();
+
+        let err = load_config(&main).unwrap_err().to_string();
+        assert!(err.contains("include cycle"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_config_shared_include_loads_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("a.toml");
+        fs::write(&main, "include = [\"b.toml\", \"c.toml\"]\n").unwrap();
+        fs::write(tmp.path().join("b.toml"), "include = [\"d.toml\"]\n").unwrap();
+        fs::write(tmp.path().join("c.toml"), "include = [\"./d.toml\"]\n").unwrap();
+        fs::write(tmp.path().join("d.toml"), "[dev.x]\ncmd = \"ls\"\n").unwrap();
+
+        let config = load_config(&main).unwrap();
+        assert_eq!(config.list_bundles(), vec!["dev.x"]);
+    }
+
+    #[test]
+    fn test_load_config_errors_name_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("config.toml");
+        fs::write(
+            &main,
+            "include = [\"work.toml\"]\n[default.web]\ncmd = \"a\"\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.path().join("work.toml"),
+            "[servers.x]\nhots = \"me@x\"\n",
+        )
+        .unwrap();
+
+        let err = load_config(&main).unwrap_err().to_string();
+        assert!(err.starts_with("Failed to parse "), "{}", err);
+        assert!(
+            err.contains("work.toml") && err.contains("unknown field `hots`"),
+            "{}",
+            err
+        );
+
+        fs::write(tmp.path().join("work.toml"), "").unwrap();
+        let config = load_config(&main).unwrap();
+        assert!(config.warnings()[0].starts_with(&main.display().to_string()));
+    }
+
+    #[test]
+    fn test_load_config_missing_include() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("config.toml");
+        fs::write(&main, "include = [\"nope.toml\", \"none/*.toml\"]\n").unwrap();
+
+        assert!(matches!(
+            load_config(&main),
+            Err(PanoutError::ConfigNotFound(p)) if p.ends_with("nope.toml")
+        ));
+    }
+}
@@ -4,213 +4,561 @@
 //! windows from TOML configuration.
 
 use clap::Parser;
-use panout::cli::Cli;
-use panout::config::{Config, Layout, Workspace};
+use panout::cli::{Cli, Command, Target};
+use panout::config::{BundleEntry, Config};
+use panout::doctor::{self, Status};
 use panout::error::Result;
-use panout::{loader, resolver, session, tmux, PanoutError};
+use panout::listing::Listings;
+use panout::report::RunReport;
+use panout::run::{
+    self, RunOptions, RunSize, run_bundle_with, run_server_with, run_tag_with, run_workspace_with,
+};
+use panout::state::{self, LastRun};
+use panout::tmux::{ProcessTmux, RecordingTmux, TmuxBackend};
+use panout::{PanoutError, completions, loader, man};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::time::Duration;
 
 fn main() {
     if let Err(e) = run() {
         eprintln!("Error: {}", e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
 
 /// Main application logic.
 fn run() -> Result<()> {
     let cli = Cli::parse();
-    let config = loader::load_default_config()?;
 
-    if cli.list {
-        print_listings(&config);
-        return Ok(());
+    match cli.command {
+        Some(Command::Doctor) => run_doctor(cli.config.as_deref()),
+        Some(Command::Init) => {
+            let path = loader::init_config()?;
+            println!("Created {}", path.display());
+            Ok(())
+        }
+        Some(Command::Last { show }) => run_last(show),
+        Some(Command::Completions { shell }) => Ok(completions::render(shell, &mut io::stdout())?),
+        Some(Command::Man) => Ok(man::render(&mut io::stdout())?),
+        None => run_cli(cli),
     }
+}
 
-    if let Some(ref ws_name) = cli.workspace {
-        return run_workspace(&config, ws_name);
+/// Run the bundle, workspace, or server selected on the command line.
+///
+/// Successful runs are recorded for `panout last`. With `--report`, a JSON
+/// summary is written whether or not the run succeeds.
+fn run_cli(cli: Cli) -> Result<()> {
+    let timeout = Duration::from_secs(cli.tmux_timeout);
+    let tmux = RecordingTmux::new(ProcessTmux::new().with_timeout(timeout));
+    let mut target = None;
+    let result = loader::resolve_config_path(cli.config.as_deref())
+        .and_then(|path| loader::load_config(&path))
+        .and_then(|config| run_config(&cli, &config, &tmux, &mut target));
+
+    if let Some(ref path) = cli.report {
+        write_report(path, target.clone(), &tmux, result.as_ref().err());
     }
+    result?;
 
-    run_bundle(&cli, &config)
+    // Best effort: failing to record the run must not fail it
+    if let (Some(target), Some(path)) = (target, state::last_run_path()) {
+        let _ = state::save_last_run(&path, &LastRun::from_cli(&cli, &target));
+    }
+    Ok(())
 }
 
-/// Print all available bundles, workspaces, and servers.
-fn print_listings(config: &Config) {
-    if !config.bundles.is_empty() {
-        println!("Bundles:");
-        for bundle in config.list_bundles() {
-            println!("  {}", bundle);
+/// Validate `config` and run the selected target.
+///
+/// `target` is filled in as soon as it's known, so a failed run can still
+/// be reported against it. It stays `None` for `--list`, `--print-config`,
+/// and a cancelled menu.
+fn run_config(
+    cli: &Cli,
+    config: &Config,
+    tmux: &dyn TmuxBackend,
+    target: &mut Option<Target>,
+) -> Result<()> {
+    let strict = config.strictness(cli.strict);
+    config.validate(&strict)?;
+
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if cli.print_config {
+        print!("{}", config.with_resolved_layouts().to_toml_string()?);
+        return Ok(());
+    }
+
+    if cli.list {
+        let mut listings = Listings::from_config(config);
+        if let Some(ref tag) = cli.tag {
+            listings = listings.tagged(config, tag);
         }
+        print!("{}", listings.render(cli.format, cli.long)?);
+        return Ok(());
     }
-    if !config.workspaces.is_empty() {
-        println!("\nWorkspaces:");
-        for ws in config.list_workspaces() {
-            println!("  {}", ws);
+
+    // Bare `panout` at a terminal offers a menu instead of an error;
+    // `--select` asks for a bundle even when stdin is piped
+    let selected = match cli.target(config)? {
+        Some(selected) => selected,
+        None if cli.select || io::stdin().is_terminal() => {
+            let mut menu = LineMenu {
+                input: io::stdin().lock(),
+                output: io::stdout(),
+            };
+            match pick_target(config, !cli.select, &mut menu)? {
+                Some(selected) => selected,
+                None => return Ok(()),
+            }
         }
+        None => return Err(PanoutError::BundleNotFound("no bundle specified".into())),
+    };
+
+    let opts = cli.run_options();
+    if cli.count_only {
+        println!("{}", count_summary(config, &selected, &opts)?);
+        return Ok(());
     }
-    if !config.servers.is_empty() {
-        println!("\nServers:");
-        for server in config.list_servers() {
-            println!("  {}", server);
+
+    // Large layouts are confirmed before any tmux call; without a terminal
+    // to ask, they're declined
+    let size = match selected {
+        Target::Workspace(ref name) => Some(run::workspace_size(config, name, &opts)?),
+        Target::Bundle(ref name) => Some(run::bundle_size(config, name, &opts)?),
+        Target::Tag(ref tag) => Some(run::tag_size(config, tag, &opts)?),
+        Target::Server(_) => None,
+    };
+    if let Some(size) = size
+        && !cli.yes
+        && size.needs_confirmation(config.defaults.confirm_threshold)
+    {
+        let confirmed = io::stdin().is_terminal()
+            && confirm(&size, &mut io::stdin().lock(), &mut io::stderr())?;
+        if !confirmed {
+            return Err(PanoutError::NotConfirmed(size.panes));
         }
     }
+
+    // Fail with install hints before anything else touches tmux
+    tmux.ensure_available()?;
+
+    match target.insert(selected) {
+        Target::Workspace(name) => run_workspace_with(tmux, config, name, &opts),
+        Target::Bundle(name) => run_bundle_with(tmux, config, name, &opts),
+        Target::Tag(tag) => run_tag_with(tmux, config, tag, &opts),
+        Target::Server(name) => run_server_with(tmux, config, name),
+    }
 }
 
-/// Execute a bundle configuration.
-///
-/// Bundles targeting SSH servers (detected via resolved commands matching
-/// known server hosts) route through the session module to create
-/// persistent remote tmux sessions. Other bundles send commands to
-/// local panes as before.
-fn run_bundle(cli: &Cli, config: &Config) -> Result<()> {
-    let bundle_name = cli
-        .bundle
-        .as_ref()
-        .ok_or_else(|| PanoutError::BundleNotFound("no bundle specified".into()))?;
-
-    let num_panes = cli.num.unwrap_or(1);
-    let pane_commands = resolver::resolve_with_panes(config, bundle_name)?;
-
-    // Layout precedence: CLI flag > bundle config > defaults > tiled
-    let bundle = config.get_bundle(bundle_name);
-    let layout = cli
-        .layout()
-        .or_else(|| bundle.and_then(|b| b.layout))
-        .or(config.defaults.layout)
-        .unwrap_or(Layout::Tiled);
-
-    let pane_indices = tmux::create_panes(num_panes, layout)?;
-
-    // Check if pane 0 commands include an SSH connection to a known server
-    let server_host = find_server_host(&pane_commands, config);
-
-    if let Some(host) = server_host {
-        // Remote bundle: create named tmux session on remote host
-        let cmd = session::build_remote_session_cmd(
-            &host,
-            bundle_name,
-            None,
+/// Write the `--report` file. Failing to write it only warns.
+fn write_report<B: TmuxBackend>(
+    path: &std::path::Path,
+    target: Option<Target>,
+    tmux: &RecordingTmux<B>,
+    error: Option<&PanoutError>,
+) {
+    let report = RunReport::new(target, &tmux.calls(), error);
+    if let Err(e) = report.write(path) {
+        eprintln!(
+            "Warning: could not write report to {}: {}",
+            path.display(),
+            e
         );
-        if let Some(&pane) = pane_indices.first() {
-            tmux::send_keys(pane, &cmd)?;
-        }
-    } else {
-        // Local bundle: send commands to panes as before
-        for (i, commands) in pane_commands {
-            if let Some(&actual_pane) = pane_indices.get(i as usize) {
-                for cmd in commands {
-                    tmux::send_keys(actual_pane, &cmd)?;
-                }
-            }
-        }
     }
+}
 
-    Ok(())
+/// Repeat the last recorded run, or print it with `--show`.
+fn run_last(show: bool) -> Result<()> {
+    let path = state::last_run_path().ok_or(PanoutError::NoLastRun)?;
+    let last = state::load_last_run(&path)?;
+    if show {
+        println!("{}", last.command_line());
+        return Ok(());
+    }
+    run_cli(last.to_cli()?)
+}
+
+/// Where the target menu is shown and answered.
+trait Menu {
+    /// Show one line of the menu.
+    fn show(&mut self, line: &str) -> Result<()>;
+    /// Show `prompt` and read the answer, without its line ending; `None` once
+    /// input ends.
+    fn ask(&mut self, prompt: &str) -> Result<Option<String>>;
 }
 
-/// Check if resolved pane commands contain an SSH command targeting a known server.
+/// A [`Menu`] on a line reader and a writer, such as stdin and stdout.
+struct LineMenu<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Menu for LineMenu<R, W> {
+    fn show(&mut self, line: &str) -> Result<()> {
+        writeln!(self.output, "{}", line)?;
+        Ok(())
+    }
+
+    fn ask(&mut self, prompt: &str) -> Result<Option<String>> {
+        write!(self.output, "{}", prompt)?;
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+}
+
+/// Ask the user to choose a bundle, or also a workspace with
+/// `include_workspaces`, from a numbered list.
 ///
-/// Scans all resolved pane commands for `ssh <host>` patterns where `<host>`
-/// matches a server host from the config. Returns the first matching host.
-fn find_server_host(
-    pane_commands: &[(u32, Vec<String>)],
+/// Re-prompts on invalid input. Returns `None` if the user enters nothing
+/// or input ends.
+fn pick_target(
     config: &Config,
-) -> Option<String> {
-    let known_hosts: Vec<&str> = config
-        .servers
-        .values()
-        .map(|s| s.host.as_str())
-        .collect();
-
-    for (_, commands) in pane_commands {
-        for cmd in commands {
-            if let Some(rest) = cmd.strip_prefix("ssh ") {
-                let target = rest.trim();
-                if known_hosts
-                    .iter()
-                    .any(|h| target == *h || target.ends_with(h))
-                {
-                    return Some(target.to_string());
-                }
+    include_workspaces: bool,
+    menu: &mut dyn Menu,
+) -> Result<Option<Target>> {
+    let bundles = config.list_bundles();
+    let workspaces = match include_workspaces {
+        true => config.list_workspaces(),
+        false => Vec::new(),
+    };
+    if bundles.is_empty() && workspaces.is_empty() {
+        return Err(PanoutError::BundleNotFound("no bundle specified".into()));
+    }
+
+    let mut choices = Vec::new();
+    if !bundles.is_empty() {
+        menu.show("Bundles:")?;
+        for name in bundles {
+            menu.show(&format!("  {}) {}", choices.len() + 1, name))?;
+            choices.push(Target::Bundle(name));
+        }
+    }
+    if !workspaces.is_empty() {
+        menu.show("Workspaces:")?;
+        for name in workspaces {
+            menu.show(&format!("  {}) {}", choices.len() + 1, name))?;
+            choices.push(Target::Workspace(name));
+        }
+    }
+
+    loop {
+        let Some(answer) = menu.ask(&format!("Select [1-{}]: ", choices.len()))? else {
+            return Ok(None);
+        };
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Ok(None);
+        }
+
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=choices.len()).contains(&n) => {
+                return Ok(Some(choices.swap_remove(n - 1)));
             }
+            _ => menu.show(&format!("Invalid choice: {}", answer))?,
         }
     }
-    None
 }
 
-/// Execute a workspace configuration (multiple windows with optional SSH).
+/// Ask whether to go ahead with a run of `size`. Only `y` or `yes` agrees.
+fn confirm(size: &RunSize, input: &mut impl BufRead, output: &mut impl Write) -> Result<bool> {
+    write!(output, "{}", size.prompt())?;
+    output.flush()?;
+
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Name of the layout a bundle (or a tag run, without one) ends up with.
+fn layout_name(
+    config: &Config,
+    bundle: Option<&BundleEntry>,
+    panes: u32,
+    opts: &RunOptions,
+) -> &'static str {
+    match run::bundle_layout_string(config, bundle, opts) {
+        Some(_) => "custom",
+        None => run::bundle_layout(config, bundle, panes, opts).name(),
+    }
+}
+
+/// Describe what running `target` would create, for `--count-only`.
 ///
-/// Remote workspaces (with `host` set) create a persistent named tmux
-/// session on the remote host via SSH. Local workspaces create windows
-/// and panes as before.
-fn run_workspace(config: &Config, name: &str) -> Result<()> {
-    let workspace = config
-        .get_workspace(name)
-        .ok_or_else(|| PanoutError::WorkspaceNotFound(name.into()))?;
-
-    match &workspace.host {
-        Some(host) => {
-            // Remote session: SSH into host with named tmux session
-            let cmd = session::build_remote_session_cmd(
-                host,
+/// e.g. `bundle dev.all -> 4 panes (tiled)` or
+/// `workspace x -> 3 windows, 9 panes total`.
+fn count_summary(config: &Config, target: &Target, opts: &RunOptions) -> Result<String> {
+    let plural = |n: u32, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    Ok(match target {
+        Target::Bundle(name) => {
+            let name = config.resolve_alias(name)?;
+            let panes = run::bundle_size(config, &name, opts)?.panes;
+            let bundle = config.get_bundle(&name);
+            let layout = layout_name(config, bundle, panes, opts);
+            format!("bundle {} -> {} ({})", name, plural(panes, "pane"), layout)
+        }
+        Target::Tag(tag) => {
+            let panes = run::tag_size(config, tag, opts)?.panes;
+            let layout = layout_name(config, None, panes, opts);
+            format!("tag {} -> {} ({})", tag, plural(panes, "pane"), layout)
+        }
+        Target::Workspace(name) => {
+            let size = run::workspace_size(config, name, opts)?;
+            format!(
+                "workspace {} -> {}, {} total",
                 name,
-                workspace.dir.as_deref(),
-            );
-            let panes = tmux::pane_indices()?;
-            tmux::send_keys(panes[0], &cmd)?;
+                plural(size.windows, "window"),
+                plural(size.panes, "pane")
+            )
         }
-        None => {
-            // Local workspace: create windows/panes as before
-            let start_window = tmux::current_window()?;
-            run_workspace_windows(workspace)?;
-            tmux::select_window(start_window)?;
+        Target::Server(name) => format!("server {} -> 1 pane (the current one)", name),
+    })
+}
+
+/// Run environment diagnostics and print one line per check.
+fn run_doctor(config: Option<&std::path::Path>) -> Result<()> {
+    let checks = doctor::run_all(config);
+    for check in &checks {
+        println!("[{}] {}: {}", check.status, check.name, check.message);
+        if let Some(ref remedy) = check.remedy {
+            println!("       {}", remedy);
         }
     }
 
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failures > 0 {
+        return Err(PanoutError::ChecksFailed(failures));
+    }
     Ok(())
 }
 
-/// Create all windows defined in a workspace.
-fn run_workspace_windows(workspace: &Workspace) -> Result<()> {
-    for (i, win) in workspace.windows.iter().enumerate() {
-        if i > 0 {
-            tmux::create_window(win.name.as_deref())?;
-        }
-
-        let layout = win.layout.unwrap_or(Layout::Tiled);
-        let pane_indices = tmux::create_panes(win.panes, layout)?;
-
-        for pane in pane_indices {
-            match (&workspace.host, &workspace.dir) {
-                // SSH + cd: single command that connects and changes directory
-                (Some(host), Some(dir)) => {
-                    let cmd = format!(
-                        "ssh -t {} \"cd {} && exec \\$SHELL -l\"",
-                        host, dir
-                    );
-                    tmux::send_keys(pane, &cmd)?;
-                }
-                // SSH only
-                (Some(host), None) => {
-                    let cmd = format!("ssh {}", host);
-                    tmux::send_keys(pane, &cmd)?;
-                }
-                // Local cd only
-                (None, Some(dir)) => {
-                    let cmd = format!("cd {}", dir);
-                    tmux::send_keys(pane, &cmd)?;
-                }
-                // No host or dir
-                (None, None) => {}
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use panout::tmux::MockTmux;
+    use std::str::FromStr;
 
-            // Window-specific commands
-            if let Some(ref cmd) = win.cmd {
-                for c in cmd.to_vec() {
-                    tmux::send_keys(pane, &c)?;
-                }
-            }
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("panout").chain(args.iter().copied())).unwrap()
+    }
+
+    fn config(toml: &str) -> Config {
+        Config::from_str(toml).unwrap()
+    }
+
+    const DEV: &str = r#"
+        [dev]
+        all = { cmd = ["@dev.web", "@dev.api"] }
+        web = { cmd = "npm run dev", pane = 0 }
+        api = { cmd = "cargo run", pane = 1 }
+    "#;
+
+    #[test]
+    fn test_pick_target_selects_by_number() {
+        let config = config(&format!("{}\n[workspace.ws]\nwindows = []\n", DEV));
+        let mut menu = LineMenu {
+            input: io::Cursor::new("9\nx\n4\n"),
+            output: Vec::new(),
+        };
+
+        let target = pick_target(&config, true, &mut menu).unwrap();
+        assert!(matches!(target, Some(Target::Workspace(ref w)) if w == "ws"));
+
+        let output = String::from_utf8(menu.output).unwrap();
+        assert!(output.contains("  1) dev.all\n"));
+        assert!(output.contains("  4) ws\n"));
+        assert!(output.contains("Invalid choice: 9"));
+        assert!(output.contains("Invalid choice: x"));
+    }
+
+    /// A [`Menu`] answering from a script and recording what it was shown.
+    #[derive(Default)]
+    struct ScriptedMenu {
+        answers: Vec<&'static str>,
+        shown: Vec<String>,
+    }
+
+    impl Menu for ScriptedMenu {
+        fn show(&mut self, line: &str) -> Result<()> {
+            self.shown.push(line.to_string());
+            Ok(())
+        }
+
+        fn ask(&mut self, _prompt: &str) -> Result<Option<String>> {
+            Ok((!self.answers.is_empty()).then(|| self.answers.remove(0).to_string()))
         }
     }
 
-    Ok(())
+    #[test]
+    fn test_pick_target_empty_input_cancels() {
+        let mut menu = ScriptedMenu {
+            answers: vec![""],
+            ..ScriptedMenu::default()
+        };
+        assert!(
+            pick_target(&config(DEV), true, &mut menu)
+                .unwrap()
+                .is_none()
+        );
+
+        let mut menu = ScriptedMenu::default();
+        assert!(
+            pick_target(&config(DEV), true, &mut menu)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_pick_target_bundles_only() {
+        let config = config(&format!("{}\n[workspace.ws]\nwindows = []\n", DEV));
+        let mut menu = ScriptedMenu {
+            answers: vec!["4", "2"],
+            ..ScriptedMenu::default()
+        };
+
+        let target = pick_target(&config, false, &mut menu).unwrap();
+        assert!(matches!(target, Some(Target::Bundle(ref b)) if b == "dev.api"));
+        assert_eq!(menu.shown[0], "Bundles:");
+        assert!(!menu.shown.iter().any(|line| line == "Workspaces:"));
+        assert_eq!(menu.shown.last().unwrap(), "Invalid choice: 4");
+    }
+
+    fn report_for(cli: &Cli, config: &Config) -> serde_json::Value {
+        let tmux = RecordingTmux::new(MockTmux::new());
+        let mut target = None;
+        let result = run_config(cli, config, &tmux, &mut target);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("report.json");
+        write_report(&path, target, &tmux, result.as_ref().err());
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_report_for_successful_run() {
+        let report = report_for(&cli(&["dev.all"]), &config(DEV));
+
+        assert_eq!(report["target"]["kind"], "bundle");
+        assert_eq!(report["target"]["name"], "dev.all");
+        assert_eq!(report["success"], true);
+        assert_eq!(report["panes_created"], 1);
+        assert_eq!(report["commands"][1]["pane"], 1);
+        assert_eq!(report["commands"][1]["command"], "cargo run");
+        assert!(report["error"].is_null());
+    }
+
+    #[test]
+    fn test_report_for_failed_run() {
+        let config = config(&format!(
+            "{}\n[dev.fail]\ncmd = \"ls\"\nbefore = \"exit 3\"\n",
+            DEV
+        ));
+        let report = report_for(&cli(&["-b", "dev.fail"]), &config);
+
+        assert_eq!(report["target"]["name"], "dev.fail");
+        assert_eq!(report["success"], false);
+        assert_eq!(report["commands"].as_array().unwrap().len(), 0);
+        assert!(report["error"].as_str().unwrap().contains("exit code 3"));
+    }
+
+    #[test]
+    fn test_confirm_needs_yes() {
+        let size = RunSize {
+            panes: 12,
+            windows: 3,
+        };
+        for (answer, expected) in [
+            ("y\n", true),
+            ("YES\n", true),
+            ("\n", false),
+            ("no\n", false),
+        ] {
+            let mut output = Vec::new();
+            let confirmed = confirm(&size, &mut io::Cursor::new(answer), &mut output).unwrap();
+            assert_eq!(confirmed, expected, "{:?}", answer);
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "This will create 12 panes across 3 windows. Continue? [y/N] "
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_config_yes_skips_confirmation() {
+        let config = config(&format!("[defaults]\nconfirm_threshold = 1\n{}", DEV));
+        let tmux = MockTmux::new().not_installed();
+        let mut target = None;
+        let result = run_config(&cli(&["dev.all", "-y"]), &config, &tmux, &mut target);
+
+        // Past the prompt, on to the tmux check
+        assert!(matches!(result, Err(PanoutError::TmuxNotInstalled)));
+    }
+
+    #[test]
+    fn test_count_summary() {
+        let config = config(
+            r#"
+            [svc]
+            web = { cmd = "npm run dev", pane = 0 }
+            api = { cmd = "cargo run", pane = 1 }
+            db = { cmd = "psql", pane = 2 }
+
+            [dev]
+            all = { cmd = "@svc.*", layout = "main-vertical" }
+
+            [workspace.x]
+            windows = [{ panes = 2 }, { panes = 4 }, { panes = 3 }]
+            "#,
+        );
+        let summary = |args: &[&str], target| {
+            count_summary(&config, &target, &cli(args).run_options()).unwrap()
+        };
+
+        assert_eq!(
+            summary(&["dev.all"], Target::Bundle("dev.all".into())),
+            "bundle dev.all -> 3 panes (main-vertical)"
+        );
+        assert_eq!(
+            summary(&["svc.web", "-n", "1"], Target::Bundle("svc.web".into())),
+            "bundle svc.web -> 1 pane (tiled)"
+        );
+        assert_eq!(
+            summary(&["x"], Target::Workspace("x".into())),
+            "workspace x -> 3 windows, 9 panes total"
+        );
+        assert_eq!(
+            summary(&["x", "--windows", "1"], Target::Workspace("x".into())),
+            "workspace x -> 1 window, 4 panes total"
+        );
+    }
+
+    #[test]
+    fn test_run_config_count_only_skips_tmux() {
+        let tmux = MockTmux::new().not_installed();
+        let mut target = None;
+        let cli = cli(&["dev.all", "--count-only"]);
+        let result = run_config(&cli, &config(DEV), &tmux, &mut target);
+
+        assert!(result.is_ok());
+        assert!(target.is_none());
+        assert!(tmux.calls().is_empty());
+    }
+
+    #[test]
+    fn test_run_config_checks_tmux_first() {
+        let tmux = MockTmux::new().not_installed();
+        let mut target = None;
+        let result = run_config(&cli(&["dev.all"]), &config(DEV), &tmux, &mut target);
+
+        assert!(matches!(result, Err(PanoutError::TmuxNotInstalled)));
+        assert!(tmux.calls().is_empty());
+    }
 }
@@ -0,0 +1,99 @@
+//! Man page generation (`panout man`).
+//!
+//! Renders the clap definition of [`Cli`] as a roff `panout(1)` page, with
+//! an EXAMPLES section built from [`EXAMPLES`] and an EXIT STATUS section
+//! built from [`EXIT_CODES`].
+
+use crate::cli::{Cli, EXAMPLES};
+use crate::error::EXIT_CODES;
+use clap::CommandFactory;
+use clap_mangen::Man;
+use std::io::{self, Write};
+
+/// Write the man page to `out`.
+pub fn render(out: &mut dyn Write) -> io::Result<()> {
+    let man = Man::new(Cli::command());
+    man.render_title(out)?;
+    man.render_name_section(out)?;
+    man.render_synopsis_section(out)?;
+    man.render_description_section(out)?;
+    man.render_options_section(out)?;
+    man.render_subcommands_section(out)?;
+    render_examples_section(out)?;
+    render_exit_status_section(out)?;
+    man.render_version_section(out)?;
+    Ok(())
+}
+
+/// Write the EXAMPLES section, one tagged paragraph per example.
+fn render_examples_section(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, ".SH EXAMPLES")?;
+    for (description, command) in EXAMPLES {
+        writeln!(out, ".TP")?;
+        writeln!(out, "\\fB{}\\fR", escape(command))?;
+        writeln!(out, "{}", escape(description))?;
+    }
+    Ok(())
+}
+
+/// Write the EXIT STATUS section, one tagged paragraph per code.
+fn render_exit_status_section(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, ".SH \"EXIT STATUS\"")?;
+    for (code, meaning) in EXIT_CODES {
+        writeln!(out, ".TP")?;
+        writeln!(out, "\\fB{}\\fR", code)?;
+        writeln!(out, "{}", escape(meaning))?;
+    }
+    Ok(())
+}
+
+/// Escape text for roff: backslashes, hyphens, and a leading control character.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page() -> String {
+        let mut out = Vec::new();
+        render(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_man_page_lists_every_long_flag() {
+        let page = page();
+        let cmd = Cli::command();
+        for arg in cmd.get_arguments().filter(|a| !a.is_hide_set()) {
+            if let Some(long) = arg.get_long() {
+                let flag = format!("\\-\\-{}", long.replace('-', "\\-"));
+                assert!(page.contains(&flag), "man page is missing --{}", long);
+            }
+        }
+    }
+
+    #[test]
+    fn test_man_page_has_description_and_examples() {
+        let page = page();
+        assert!(page.contains(".SH DESCRIPTION"));
+        assert!(page.contains("Define bundles for local commands"));
+        assert!(page.contains(".SH EXAMPLES"));
+        for (description, _) in EXAMPLES {
+            assert!(page.contains(&escape(description)));
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("panout -b x"), "panout \\-b x");
+        assert_eq!(escape(".hidden"), "\\&.hidden");
+        assert_eq!(escape("a\\b"), "a\\eb");
+    }
+}
@@ -0,0 +1,103 @@
+//! Local path helpers.
+//!
+//! Shells expand `~` on their own, but tmux does not (for example in
+//! `split-window -c`), so paths handed to tmux are expanded here first.
+
+use crate::error::{PanoutError, Result};
+use std::path::Path;
+
+/// Expand a leading `~` to the current user's home directory.
+///
+/// Only `~` on its own or followed by `/` is expanded; `~user` forms and
+/// paths without a leading tilde are returned unchanged. If the home
+/// directory can't be determined the path is returned as-is.
+pub fn expand_tilde(path: &str) -> String {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => return path.to_string(),
+    };
+
+    match dirs::home_dir() {
+        Some(home) => format!("{}{}", home.display(), rest),
+        None => path.to_string(),
+    }
+}
+
+/// Resolve a working directory for panes: expand `~`, make it absolute, and
+/// check that it exists.
+///
+/// Relative paths are taken from the current directory, since tmux would
+/// otherwise resolve them against its server's directory.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::DirNotFound`] if the path isn't an existing
+/// directory.
+pub fn working_dir(path: &str) -> Result<String> {
+    let expanded = expand_tilde(path);
+    let absolute = if Path::new(&expanded).is_absolute() {
+        Path::new(&expanded).to_path_buf()
+    } else {
+        std::env::current_dir()?.join(&expanded)
+    };
+
+    if !absolute.is_dir() {
+        return Err(PanoutError::DirNotFound(path.to_string()));
+    }
+    Ok(absolute.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        dirs::home_dir().unwrap().display().to_string()
+    }
+
+    #[test]
+    fn test_expand_bare_tilde() {
+        assert_eq!(expand_tilde("~"), home());
+    }
+
+    #[test]
+    fn test_expand_tilde_slash() {
+        assert_eq!(expand_tilde("~/"), format!("{}/", home()));
+        assert_eq!(
+            expand_tilde("~/src/project"),
+            format!("{}/src/project", home())
+        );
+    }
+
+    #[test]
+    fn test_absolute_and_relative_unchanged() {
+        assert_eq!(expand_tilde("/var/log"), "/var/log");
+        assert_eq!(expand_tilde("src/~"), "src/~");
+    }
+
+    #[test]
+    fn test_other_user_unchanged() {
+        assert_eq!(expand_tilde("~bob/src"), "~bob/src");
+    }
+
+    #[test]
+    fn test_working_dir_expands_tilde() {
+        assert_eq!(working_dir("~").unwrap(), home());
+    }
+
+    #[test]
+    fn test_working_dir_makes_relative_absolute() {
+        let cwd = std::env::current_dir().unwrap().join(".");
+        assert_eq!(working_dir(".").unwrap(), cwd.display().to_string());
+    }
+
+    #[test]
+    fn test_working_dir_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let missing = tmp.path().join("nope").display().to_string();
+        match working_dir(&missing) {
+            Err(PanoutError::DirNotFound(dir)) => assert_eq!(dir, missing),
+            other => panic!("expected DirNotFound, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Machine-readable run summaries (`--report`).
+//!
+//! A [`RunReport`] describes what a run did to tmux: the target, how many
+//! windows and panes were created, every command, text, and key sequence
+//! sent to a pane, and the error if the run failed. It's written as JSON for
+//! CI pipelines.
+
+use crate::cli::Target;
+use crate::error::{PanoutError, Result};
+use crate::tmux::TmuxCall;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Summary of a single panout run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunReport {
+    /// What was run, if the target was determined.
+    pub target: Option<Target>,
+    /// Whether the run finished without error.
+    pub success: bool,
+    /// Windows created with `new-window`.
+    pub windows_created: usize,
+    /// Panes created, by splitting or as the first pane of a new window.
+    pub panes_created: usize,
+    /// Commands, text, and keys sent to panes, in order.
+    pub commands: Vec<SentCommand>,
+    /// The error message, if the run failed.
+    pub error: Option<String>,
+}
+
+/// Something sent to a pane: a command, raw text, or keys.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SentCommand {
+    /// Actual tmux pane index.
+    pub pane: u32,
+    /// How it was sent.
+    pub kind: SendKind,
+    /// The command or text, or the key names separated by spaces.
+    pub command: String,
+}
+
+/// How a [`SentCommand`] reached its pane.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SendKind {
+    /// Typed, then Enter pressed.
+    Command,
+    /// Typed without pressing Enter.
+    Text,
+    /// tmux keys pressed, such as `C-c`.
+    Keys,
+}
+
+impl RunReport {
+    /// Build a report from the tmux calls a run made and its outcome.
+    pub fn new(target: Option<Target>, calls: &[TmuxCall], error: Option<&PanoutError>) -> Self {
+        let windows_created = calls
+            .iter()
+            .filter(|c| matches!(c, TmuxCall::NewWindow { .. } | TmuxCall::NewSession(_)))
+            .count();
+        let splits = calls
+            .iter()
+            .filter(|c| matches!(c, TmuxCall::SplitWindow { .. }))
+            .count();
+        let commands = calls
+            .iter()
+            .filter_map(|c| {
+                let (pane, kind, command) = match c {
+                    TmuxCall::SendKeys { pane, command } => {
+                        (pane, SendKind::Command, command.clone())
+                    }
+                    TmuxCall::SendKeysRaw { pane, text } => (pane, SendKind::Text, text.clone()),
+                    TmuxCall::SendKeySequence { pane, keys } => {
+                        (pane, SendKind::Keys, keys.join(" "))
+                    }
+                    _ => return None,
+                };
+                Some(SentCommand {
+                    pane: *pane,
+                    kind,
+                    command,
+                })
+            })
+            .collect();
+
+        RunReport {
+            target,
+            success: error.is_none(),
+            windows_created,
+            panes_created: windows_created + splits,
+            commands,
+            error: error.map(|e| e.to_string()),
+        }
+    }
+
+    /// Write the report to `path` as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_counts_calls() {
+        let calls = vec![
+            TmuxCall::SplitWindow {
+                window: None,
+                start_dir: None,
+                size: None,
+                direction: None,
+            },
+            TmuxCall::SendKeys {
+                pane: 1,
+                command: "make".into(),
+            },
+            TmuxCall::SendKeySequence {
+                pane: 1,
+                keys: vec!["C-c".into(), "C-l".into()],
+            },
+            TmuxCall::SendKeysRaw {
+                pane: 0,
+                text: "y".into(),
+            },
+            TmuxCall::NewWindow { name: None },
+            TmuxCall::SplitWindow {
+                window: None,
+                start_dir: None,
+                size: None,
+                direction: None,
+            },
+        ];
+        let report = RunReport::new(Some(Target::Bundle("dev.all".into())), &calls, None);
+
+        assert!(report.success);
+        assert_eq!(report.windows_created, 1);
+        assert_eq!(report.panes_created, 3);
+        assert_eq!(
+            report.commands,
+            vec![
+                SentCommand {
+                    pane: 1,
+                    kind: SendKind::Command,
+                    command: "make".into()
+                },
+                SentCommand {
+                    pane: 1,
+                    kind: SendKind::Keys,
+                    command: "C-c C-l".into()
+                },
+                SentCommand {
+                    pane: 0,
+                    kind: SendKind::Text,
+                    command: "y".into()
+                },
+            ]
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["commands"][1]["kind"], "keys");
+    }
+
+    #[test]
+    fn test_report_json_shape() {
+        let error = PanoutError::NotInTmux;
+        let report = RunReport::new(Some(Target::Workspace("ws".into())), &[], Some(&error));
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["target"]["kind"], "workspace");
+        assert_eq!(json["target"]["name"], "ws");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"], "Not running inside tmux");
+    }
+}
@@ -6,7 +6,10 @@
 //! # Reference Syntax
 //!
 //! - `@group.name` - Reference a specific bundle
-//! - `@group.*` - Reference all bundles in a group
+//! - `@group.*` - Reference all bundles in a group, merged into one pane
+//! - `@group.each` - Reference all bundles in a group, one pane each
+//! - `@group` - Reference the group's default entry (`default` or `main`,
+//!   or `[defaults] default_entry`)
 //!
 //! # Example
 //!
@@ -21,9 +24,12 @@
 //! cmd = ["@dev.frontend", "@dev.backend"]  # Expands to both bundles
 //! ```
 
-use crate::config::Config;
+use crate::condition::{LocalMachine, Machine, Platform};
+use crate::config::{BundleEntry, Cmd, Config, PaneDef, PaneSize, Panes, WindowDef};
 use crate::error::{PanoutError, Result};
-use std::collections::HashSet;
+use crate::interpolate;
+use crate::tmux;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 /// A parsed reference from a command string.
 #[derive(Debug, Clone, PartialEq)]
@@ -37,11 +43,41 @@ pub enum ResolvedRef {
         /// The bundle entry name within the group.
         name: String,
     },
-    /// Reference to all bundles in a group: `@group.*`
+    /// Reference to all bundles in a group, in declared order (adjusted for
+    /// `after`): `@group.*`
     GroupAll {
         /// The bundle group name.
         group: String,
     },
+    /// Reference to all bundles in a group, each in its own pane: `@group.each`
+    GroupEach {
+        /// The bundle group name.
+        group: String,
+    },
+    /// Reference to a group's default entry: `@group`
+    GroupDefault {
+        /// The bundle group name.
+        group: String,
+    },
+}
+
+/// Commands resolved for a single logical pane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneCommands {
+    /// Logical (0-based) pane index.
+    pub pane: u32,
+    /// Commands to send, in execution order.
+    pub commands: Vec<String>,
+    /// Delay between successive commands, from the contributing bundles' `delay_ms`.
+    pub delay_ms: Option<u64>,
+    /// Pane title, from the first contributing bundle that sets `title`.
+    pub title: Option<String>,
+    /// Pane size, from the first contributing bundle that sets `size`.
+    pub size: Option<PaneSize>,
+    /// Working directory, from the first contributing bundle that sets `dir`.
+    ///
+    /// `[vars]` are expanded; `~` and relative paths are left to the caller.
+    pub dir: Option<String>,
 }
 
 /// Parse a string into a [`ResolvedRef`].
@@ -49,7 +85,11 @@ pub enum ResolvedRef {
 /// Strings starting with `@` are treated as references:
 /// - `@group.name` -> `BundleRef`
 /// - `@group.*` -> `GroupAll`
+/// - `@group.each` -> `GroupEach`
+/// - `@group` -> `GroupDefault`
 /// - Everything else -> `Command`
+///
+/// Use [`parse_ref_in`] to keep `@word` a command when no such group exists.
 pub fn parse_ref(s: &str) -> ResolvedRef {
     if let Some(rest) = s.strip_prefix('@') {
         let parts: Vec<&str> = rest.splitn(2, '.').collect();
@@ -58,9 +98,15 @@ pub fn parse_ref(s: &str) -> ResolvedRef {
             let name = parts[1].to_string();
             if name == "*" {
                 ResolvedRef::GroupAll { group }
+            } else if name == "each" {
+                ResolvedRef::GroupEach { group }
             } else {
                 ResolvedRef::BundleRef { group, name }
             }
+        } else if is_group_name(rest) {
+            ResolvedRef::GroupDefault {
+                group: rest.to_string(),
+            }
         } else {
             ResolvedRef::Command(s.to_string())
         }
@@ -69,24 +115,200 @@ pub fn parse_ref(s: &str) -> ResolvedRef {
     }
 }
 
+/// [`parse_ref`] against `config`: `@word` is only a group reference when
+/// `config` has a group or alias named `word`.
+///
+/// Otherwise it's a command that happens to start with `@`, such as
+/// `@reboot-notify`, and is sent as written.
+pub fn parse_ref_in(config: &Config, s: &str) -> ResolvedRef {
+    match parse_ref(s) {
+        ResolvedRef::GroupDefault { ref group }
+            if config.get_group(group).is_none() && !config.aliases.contains_key(group) =>
+        {
+            ResolvedRef::Command(s.to_string())
+        }
+        reference => reference,
+    }
+}
+
+/// Whether `s` can be a bare group name, so `@s` is a reference rather
+/// than a command that happens to start with `@`.
+fn is_group_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Bundle paths a reference expands to, in resolution order.
+///
+/// Returns an empty list for plain commands.
+fn ref_targets(config: &Config, reference: &ResolvedRef) -> Result<Vec<String>> {
+    match reference {
+        ResolvedRef::Command(_) => Ok(Vec::new()),
+        ResolvedRef::BundleRef { group, name } => Ok(vec![format!("{}.{}", group, name)]),
+        ResolvedRef::GroupAll { group } | ResolvedRef::GroupEach { group } => {
+            let group_entries = config
+                .get_group(group)
+                .ok_or_else(|| PanoutError::BundleNotFound(format!("group '{}'", group)))?;
+            let paths: Vec<_> = group_entries
+                .keys()
+                .map(|name| format!("{}.{}", group, name))
+                .collect();
+            start_order(config, &paths)
+        }
+        // `@f` for an alias `f` names its bundle, not a group's default entry
+        ResolvedRef::GroupDefault { group } if config.aliases.contains_key(group) => {
+            Ok(vec![config.resolve_alias(group)?])
+        }
+        ResolvedRef::GroupDefault { group } => Ok(vec![config.default_bundle_path(group)?]),
+    }
+}
+
+/// Order bundle `paths` so each comes after the bundles in its `after`.
+///
+/// Only dependencies among `paths` count; otherwise the given order is kept.
+///
+/// # Errors
+///
+/// [`PanoutError::CircularRef`] with the chain if `after` loops.
+pub fn start_order(config: &Config, paths: &[String]) -> Result<Vec<String>> {
+    fn visit(
+        config: &Config,
+        paths: &[String],
+        path: &str,
+        stack: &mut Vec<String>,
+        ordered: &mut Vec<String>,
+    ) -> Result<()> {
+        if ordered.iter().any(|p| p == path) {
+            return Ok(());
+        }
+        if let Some(start) = stack.iter().position(|p| p == path) {
+            let mut chain = stack[start..].to_vec();
+            chain.push(path.to_string());
+            return Err(PanoutError::CircularRef(format!(
+                "after {}",
+                chain.join(" -> ")
+            )));
+        }
+        stack.push(path.to_string());
+        let deps = config
+            .get_bundle(path)
+            .map(|b| b.after.as_slice())
+            .unwrap_or(&[]);
+        for dep in deps {
+            let dep = config.resolve_alias(dep)?;
+            if paths.contains(&dep) {
+                visit(config, paths, &dep, stack, ordered)?;
+            }
+        }
+        stack.pop();
+        ordered.push(path.to_string());
+        Ok(())
+    }
+
+    let mut ordered = Vec::with_capacity(paths.len());
+    for path in paths {
+        visit(config, paths, path, &mut Vec::new(), &mut ordered)?;
+    }
+    Ok(ordered)
+}
+
 /// Resolve all commands for a bundle, recursively expanding `@ref`s.
 ///
-/// Returns a flat list of commands in execution order.
+/// Returns a flat list of commands in execution order, with `[vars]`
+/// placeholders expanded.
 ///
 /// # Errors
 ///
 /// - [`PanoutError::BundleNotFound`] if a referenced bundle doesn't exist
 /// - [`PanoutError::CircularRef`] if references form a cycle
+/// - [`PanoutError::UnknownVariable`] for a placeholder not in `[vars]`
+/// - [`PanoutError::InvalidConfig`] if a per-platform `cmd` has nothing for
+///   this OS and no `default`
 pub fn resolve_bundle(config: &Config, bundle_path: &str) -> Result<Vec<String>> {
+    resolve_bundle_on(config, bundle_path, &LocalMachine)
+}
+
+/// Like [`resolve_bundle`], checking bundles' `when` against `machine`.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_bundle_on(
+    config: &Config,
+    bundle_path: &str,
+    machine: &dyn Machine,
+) -> Result<Vec<String>> {
     let mut visited = HashSet::new();
-    resolve_bundle_inner(config, bundle_path, &mut visited)
+    Ok(
+        resolve_bundle_inner(config, machine, bundle_path, &mut visited)?
+            .into_iter()
+            .map(|(_, cmd)| cmd)
+            .collect(),
+    )
+}
+
+/// Like [`resolve_bundle`], but tags each command with the bundle it came from.
+///
+/// Returns `(source bundle path, command)` pairs in execution order. The
+/// source is the bundle whose `cmd` holds the command text, after every
+/// `@ref` has been expanded, which helps track down where a line comes from.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_bundle_traced(config: &Config, bundle_path: &str) -> Result<Vec<(String, String)>> {
+    let mut visited = HashSet::new();
+    resolve_bundle_inner(config, &LocalMachine, bundle_path, &mut visited)
+}
+
+/// Resolve a list of commands that may contain `@ref`s.
+///
+/// Plain commands pass through unchanged; references are expanded with
+/// [`resolve_bundle`]. Used for command lists that live outside a bundle,
+/// such as `[servers.*]` entries.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_commands(config: &Config, commands: &[String]) -> Result<Vec<String>> {
+    resolve_commands_on(config, commands, &LocalMachine)
+}
+
+/// Like [`resolve_commands`], checking bundles' `when` against `machine`.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_commands_on(
+    config: &Config,
+    commands: &[String],
+    machine: &dyn Machine,
+) -> Result<Vec<String>> {
+    let mut result = Vec::new();
+    for cmd_str in commands {
+        match parse_ref_in(config, cmd_str) {
+            ResolvedRef::Command(cmd) => result.push(cmd),
+            reference => {
+                for ref_path in ref_targets(config, &reference)? {
+                    result.extend(resolve_bundle_on(config, &ref_path, machine)?);
+                }
+            }
+        }
+    }
+    Ok(result)
 }
 
 fn resolve_bundle_inner(
     config: &Config,
+    machine: &dyn Machine,
     bundle_path: &str,
     visited: &mut HashSet<String>,
-) -> Result<Vec<String>> {
+) -> Result<Vec<(String, String)>> {
+    let bundle_path = &config.resolve_alias(bundle_path)?;
+    if config.bundle_skipped(bundle_path, machine) {
+        return Ok(Vec::new());
+    }
     if visited.contains(bundle_path) {
         return Err(PanoutError::CircularRef(bundle_path.to_string()));
     }
@@ -98,25 +320,15 @@ fn resolve_bundle_inner(
 
     let mut result = Vec::new();
 
-    for cmd_str in bundle.cmd.to_vec() {
-        match parse_ref(&cmd_str) {
+    for cmd_str in bundle_commands(bundle_path, bundle)? {
+        match parse_ref_in(config, &cmd_str) {
             ResolvedRef::Command(cmd) => {
-                result.push(cmd);
-            }
-            ResolvedRef::BundleRef { group, name } => {
-                let ref_path = format!("{}.{}", group, name);
-                let sub_cmds = resolve_bundle_inner(config, &ref_path, visited)?;
-                result.extend(sub_cmds);
-            }
-            ResolvedRef::GroupAll { group } => {
-                let group_entries = config.get_group(&group).ok_or_else(|| {
-                    PanoutError::BundleNotFound(format!("group '{}'", group))
-                })?;
-                let mut names: Vec<_> = group_entries.keys().collect();
-                names.sort();
-                for name in names {
-                    let ref_path = format!("{}.{}", group, name);
-                    let sub_cmds = resolve_bundle_inner(config, &ref_path, visited)?;
+                let cmd = own_command(config, bundle_path, bundle, &cmd)?;
+                result.push((bundle_path.to_string(), cmd));
+            }
+            reference => {
+                for ref_path in ref_targets(config, &reference)? {
+                    let sub_cmds = resolve_bundle_inner(config, machine, &ref_path, visited)?;
                     result.extend(sub_cmds);
                 }
             }
@@ -127,26 +339,374 @@ fn resolve_bundle_inner(
     Ok(result)
 }
 
+/// The commands in `bundle`'s `cmd` on this machine, `@ref`s unexpanded.
+///
+/// # Errors
+///
+/// [`PanoutError::InvalidConfig`] if a per-platform `cmd` has nothing for
+/// this OS and no `default`.
+fn bundle_commands(bundle_path: &str, bundle: &BundleEntry) -> Result<Vec<String>> {
+    let platform = Platform::current();
+    bundle
+        .cmd
+        .check_platform(&platform.os, &format!("bundle {}", bundle_path))?;
+    Ok(bundle.cmd.to_vec_on(platform))
+}
+
+/// Prepare one of `bundle`'s own (non-`@ref`) commands.
+///
+/// Wraps it in the bundle's `prefix`/`suffix` (or the `[defaults]` ones),
+/// separated by spaces, then expands `[vars]`. Key sequences aren't typed
+/// as commands, so they're left unwrapped.
+fn own_command(
+    config: &Config,
+    bundle_path: &str,
+    bundle: &BundleEntry,
+    cmd: &str,
+) -> Result<String> {
+    let mut cmd = cmd.to_string();
+    if tmux::key_sequence(&cmd).is_none() {
+        let prefix = bundle.prefix.as_ref().or(config.defaults.prefix.as_ref());
+        let suffix = bundle.suffix.as_ref().or(config.defaults.suffix.as_ref());
+        if let Some(prefix) = prefix.filter(|p| !p.is_empty()) {
+            cmd = format!("{} {}", prefix, cmd);
+        }
+        if let Some(suffix) = suffix.filter(|s| !s.is_empty()) {
+            cmd = format!("{} {}", cmd, suffix);
+        }
+    }
+    interpolate::expand_vars(&cmd, &config.vars, &format!("bundle {}", bundle_path))
+}
+
 /// Resolve commands grouped by target pane.
 ///
 /// Similar to [`resolve_bundle`] but preserves pane assignments from bundle configs.
-/// Returns tuples of `(pane_index, commands)`.
-pub fn resolve_with_panes(config: &Config, bundle_path: &str) -> Result<Vec<(u32, Vec<String>)>> {
+/// Returns one [`PaneCommands`] per target pane. When several bundles feed the
+/// same pane, the largest `delay_ms` among them is used.
+///
+/// `@group.each` gives the group's bundles consecutive panes starting at the
+/// referencing bundle's pane, unless they set their own `pane`.
+///
+/// Bundles sharing a `role` share a pane: the first one resolved picks it
+/// (from its `pane` or the default), and the role then takes precedence
+/// over the `pane` of the rest. A pane left empty because its bundle joined
+/// its role's pane is closed up: the panes after it move down by one.
+///
+/// A bundle with an empty `cmd` (`cmd = []`) still reserves its pane: it
+/// gets an entry with no commands, so the pane is created (and titled or
+/// sized) but sent nothing.
+///
+/// A bundle whose `when` fails here is skipped, along with its references;
+/// `@group.each` leaves no gap for it.
+pub fn resolve_with_panes(config: &Config, bundle_path: &str) -> Result<Vec<PaneCommands>> {
+    resolve_with_panes_on(config, bundle_path, &LocalMachine)
+}
+
+/// Like [`resolve_with_panes`], checking bundles' `when` against `machine`.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_with_panes_on(
+    config: &Config,
+    bundle_path: &str,
+    machine: &dyn Machine,
+) -> Result<Vec<PaneCommands>> {
+    Ok(plan_with_panes(config, bundle_path, machine)?.0)
+}
+
+/// Two bundles in `bundle_path` that set the same `pane` without sharing a
+/// `role`, e.g. `"bundle dev.api and bundle dev.db both set pane 1"`.
+///
+/// Their commands would be merged into one pane; `--strict` rejects that.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn pane_conflict(config: &Config, bundle_path: &str) -> Result<Option<String>> {
+    Ok(plan_with_panes(config, bundle_path, &LocalMachine)?
+        .1
+        .conflict)
+}
+
+/// Resolve `bundle_path` into panes, keeping what was decided about them.
+fn plan_with_panes(
+    config: &Config,
+    bundle_path: &str,
+    machine: &dyn Machine,
+) -> Result<(Vec<PaneCommands>, PaneClaims)> {
     let mut visited = HashSet::new();
-    let mut pane_cmds: Vec<(u32, Vec<String>)> = Vec::new();
+    let mut pane_cmds: Vec<PaneCommands> = Vec::new();
+    let mut claims = PaneClaims::default();
+
+    resolve_with_panes_inner(
+        config,
+        machine,
+        bundle_path,
+        &mut visited,
+        &mut pane_cmds,
+        &mut claims,
+        0,
+    )?;
+    claims.close_gaps(&mut pane_cmds);
 
-    resolve_with_panes_inner(config, bundle_path, &mut visited, &mut pane_cmds, 0)?;
+    Ok((pane_cmds, claims))
+}
+
+/// Resolve every bundle tagged `tag`, in sorted order, into panes.
+///
+/// Works like `@group.each`: the bundles take consecutive panes from 0,
+/// unless they set their own `pane`.
+///
+/// # Errors
+///
+/// - [`PanoutError::BundleNotFound`] naming the tag if no bundle carries it
+/// - Otherwise the same as [`resolve_with_panes`]
+pub fn resolve_tag(config: &Config, tag: &str) -> Result<Vec<PaneCommands>> {
+    resolve_tag_on(config, tag, &LocalMachine)
+}
+
+/// Like [`resolve_tag`], checking bundles' `when` against `machine`.
+///
+/// # Errors
+///
+/// Same as [`resolve_tag`].
+pub fn resolve_tag_on(
+    config: &Config,
+    tag: &str,
+    machine: &dyn Machine,
+) -> Result<Vec<PaneCommands>> {
+    let paths = config.bundles_with_tag(tag);
+    if paths.is_empty() {
+        return Err(PanoutError::BundleNotFound(format!("tag '{}'", tag)));
+    }
+    let paths = paths
+        .into_iter()
+        .filter(|p| !config.bundle_skipped(p, machine));
+
+    let mut visited = HashSet::new();
+    let mut pane_cmds: Vec<PaneCommands> = Vec::new();
+    let mut claims = PaneClaims::default();
+    for (i, path) in paths.enumerate() {
+        resolve_with_panes_inner(
+            config,
+            machine,
+            &path,
+            &mut visited,
+            &mut pane_cmds,
+            &mut claims,
+            i as u32,
+        )?;
+    }
+    claims.close_gaps(&mut pane_cmds);
 
     Ok(pane_cmds)
 }
 
+/// Number of panes needed to hold every entry in a resolved plan.
+///
+/// This is one past the highest logical pane index, and at least 1.
+pub fn required_panes(pane_cmds: &[PaneCommands]) -> u32 {
+    pane_cmds.iter().map(|p| p.pane + 1).max().unwrap_or(1)
+}
+
+/// Per-pane sizes for a plan spread over `num` panes.
+///
+/// Returns `None` when no entry sets a `size`; panes without one are `None`
+/// in the result. See [`split_sizes`](crate::tmux::split_sizes) for how the
+/// gaps are filled.
+pub fn pane_sizes(pane_cmds: &[PaneCommands], num: u32) -> Option<Vec<Option<PaneSize>>> {
+    if pane_cmds.iter().all(|p| p.size.is_none()) {
+        return None;
+    }
+
+    Some(
+        (0..num)
+            .map(|pane| {
+                pane_cmds
+                    .iter()
+                    .find(|p| p.pane == pane)
+                    .and_then(|p| p.size)
+            })
+            .collect(),
+    )
+}
+
+/// Fold entries targeting panes at or past `num` into the last pane.
+///
+/// Commands keep their relative order: overflow commands are appended to the
+/// last pane's own commands in pane order.
+pub fn clamp_panes(pane_cmds: Vec<PaneCommands>, num: u32) -> Vec<PaneCommands> {
+    let last = num.max(1) - 1;
+    let (mut kept, mut overflow): (Vec<_>, Vec<_>) =
+        pane_cmds.into_iter().partition(|p| p.pane <= last);
+    overflow.sort_by_key(|p| p.pane);
+
+    for entry in overflow {
+        if let Some(target) = kept.iter_mut().find(|p| p.pane == last) {
+            target.commands.extend(entry.commands);
+            target.delay_ms = target.delay_ms.max(entry.delay_ms);
+            if target.title.is_none() {
+                target.title = entry.title;
+            }
+            if target.size.is_none() {
+                target.size = entry.size;
+            }
+            if target.dir.is_none() {
+                target.dir = entry.dir;
+            }
+        } else {
+            kept.push(PaneCommands {
+                pane: last,
+                ..entry
+            });
+        }
+    }
+    kept
+}
+
+/// Expand the `@ref`s in a workspace window's `cmd` into its panes.
+///
+/// References are resolved as if they were entries of a bundle sitting in
+/// the window's first pane, so a referenced bundle with `pane = 1` lands in
+/// the window's second pane. Entries aimed past the window's last pane are
+/// folded into it (see [`clamp_panes`]). The expanded commands are prepended
+/// to each pane's own `cmd`, so they run after the window's plain commands.
+/// Windows without references are returned unchanged.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_window(config: &Config, win: &WindowDef) -> Result<WindowDef> {
+    resolve_window_on(config, win, &LocalMachine)
+}
+
+/// Like [`resolve_window`], checking bundles' `when` against `machine`.
+///
+/// # Errors
+///
+/// Same as [`resolve_bundle`].
+pub fn resolve_window_on(
+    config: &Config,
+    win: &WindowDef,
+    machine: &dyn Machine,
+) -> Result<WindowDef> {
+    let cmds = match win.cmd {
+        Some(Cmd::Single(ref cmd)) => vec![cmd.clone()],
+        Some(Cmd::Multiple(ref cmds)) => cmds.clone(),
+        _ => return Ok(win.clone()),
+    };
+    let (refs, plain): (Vec<_>, Vec<_>) = cmds
+        .into_iter()
+        .partition(|cmd| !matches!(parse_ref_in(config, cmd), ResolvedRef::Command(_)));
+    if refs.is_empty() {
+        return Ok(win.clone());
+    }
+
+    let mut visited = HashSet::new();
+    let mut pane_cmds = Vec::new();
+    let mut claims = PaneClaims::default();
+    for cmd in &refs {
+        let reference = parse_ref_in(config, cmd);
+        resolve_ref_with_panes(
+            config,
+            machine,
+            &reference,
+            &mut visited,
+            &mut pane_cmds,
+            &mut claims,
+            0,
+        )?;
+    }
+    claims.close_gaps(&mut pane_cmds);
+
+    let mut panes = match win.panes {
+        Panes::Count(_) => vec![PaneDef::default(); win.pane_count() as usize],
+        Panes::List(ref panes) => panes.clone(),
+    };
+    for entry in clamp_panes(pane_cmds, win.pane_count()) {
+        if let Some(pane) = panes.get_mut(entry.pane as usize) {
+            let mut cmds = entry.commands;
+            cmds.extend(pane.cmd.as_ref().map(Cmd::to_vec).unwrap_or_default());
+            pane.cmd = Some(Cmd::Multiple(cmds));
+        }
+    }
+
+    Ok(WindowDef {
+        panes: Panes::List(panes),
+        cmd: (!plain.is_empty()).then_some(Cmd::Multiple(plain)),
+        ..win.clone()
+    })
+}
+
+/// What one resolution decided about panes: the pane each `role` resolved
+/// to, the panes given up to a role, and which bundle set each `pane`.
+#[derive(Default)]
+struct PaneClaims {
+    by_role: HashMap<String, u32>,
+    vacated: BTreeSet<u32>,
+    explicit: HashMap<u32, String>,
+    /// The first pair of bundles found setting the same `pane`.
+    conflict: Option<String>,
+}
+
+impl PaneClaims {
+    /// Record that `bundle_path` set `pane` explicitly, noting a conflict if
+    /// another bundle already did.
+    fn claim(&mut self, pane: u32, bundle_path: &str) {
+        match self.explicit.get(&pane) {
+            Some(other) if other != bundle_path => {
+                if self.conflict.is_none() {
+                    self.conflict = Some(format!(
+                        "bundle {} and bundle {} both set pane {}",
+                        other, bundle_path, pane
+                    ));
+                }
+            }
+            Some(_) => {}
+            None => {
+                self.explicit.insert(pane, bundle_path.to_string());
+            }
+        }
+    }
+
+    /// The pane for a bundle with `role` that would otherwise use
+    /// `own_pane`: the role's pane, claimed by the first bundle with it.
+    fn pane_for(&mut self, role: &str, own_pane: u32) -> u32 {
+        let pane = *self.by_role.entry(role.to_string()).or_insert(own_pane);
+        if pane != own_pane {
+            self.vacated.insert(own_pane);
+        }
+        pane
+    }
+
+    /// Close up the panes given up to a role that nothing else used, moving
+    /// every later pane down by one per gap.
+    fn close_gaps(&self, pane_cmds: &mut [PaneCommands]) {
+        for &gap in self.vacated.iter().rev() {
+            if pane_cmds.iter().any(|p| p.pane == gap) {
+                continue;
+            }
+            for entry in pane_cmds.iter_mut().filter(|p| p.pane > gap) {
+                entry.pane -= 1;
+            }
+        }
+    }
+}
+
 fn resolve_with_panes_inner(
     config: &Config,
+    machine: &dyn Machine,
     bundle_path: &str,
     visited: &mut HashSet<String>,
-    pane_cmds: &mut Vec<(u32, Vec<String>)>,
+    pane_cmds: &mut Vec<PaneCommands>,
+    claims: &mut PaneClaims,
     default_pane: u32,
 ) -> Result<()> {
+    let bundle_path = &config.resolve_alias(bundle_path)?;
+    if config.bundle_skipped(bundle_path, machine) {
+        return Ok(());
+    }
     if visited.contains(bundle_path) {
         return Err(PanoutError::CircularRef(bundle_path.to_string()));
     }
@@ -156,37 +716,66 @@ fn resolve_with_panes_inner(
         .get_bundle(bundle_path)
         .ok_or_else(|| PanoutError::BundleNotFound(bundle_path.to_string()))?;
 
-    let target_pane = bundle.pane.unwrap_or(default_pane);
+    let own_pane = bundle.pane.unwrap_or(default_pane);
+    let target_pane = match bundle.role {
+        Some(ref role) => claims.pane_for(role, own_pane),
+        None => own_pane,
+    };
     let mut direct_cmds = Vec::new();
 
-    for cmd_str in bundle.cmd.to_vec() {
-        match parse_ref(&cmd_str) {
+    for cmd_str in bundle_commands(bundle_path, bundle)? {
+        match parse_ref_in(config, &cmd_str) {
             ResolvedRef::Command(cmd) => {
-                direct_cmds.push(cmd);
-            }
-            ResolvedRef::BundleRef { group, name } => {
-                let ref_path = format!("{}.{}", group, name);
-                resolve_with_panes_inner(config, &ref_path, visited, pane_cmds, target_pane)?;
-            }
-            ResolvedRef::GroupAll { group } => {
-                let group_entries = config.get_group(&group).ok_or_else(|| {
-                    PanoutError::BundleNotFound(format!("group '{}'", group))
-                })?;
-                let mut names: Vec<_> = group_entries.keys().collect();
-                names.sort();
-                for name in names {
-                    let ref_path = format!("{}.{}", group, name);
-                    resolve_with_panes_inner(config, &ref_path, visited, pane_cmds, target_pane)?;
-                }
+                direct_cmds.push(own_command(config, bundle_path, bundle, &cmd)?);
+            }
+            reference => {
+                resolve_ref_with_panes(
+                    config,
+                    machine,
+                    &reference,
+                    visited,
+                    pane_cmds,
+                    claims,
+                    target_pane,
+                )?;
             }
         }
     }
 
-    if !direct_cmds.is_empty() {
-        if let Some(entry) = pane_cmds.iter_mut().find(|(p, _)| *p == target_pane) {
-            entry.1.extend(direct_cmds);
+    // An empty `cmd` still claims its pane; a bundle made only of `@ref`s
+    // leaves the panes to the bundles it references
+    if !direct_cmds.is_empty() || bundle.cmd.is_empty() {
+        // Bundles sharing a role share its pane on purpose
+        if let (Some(pane), None) = (bundle.pane, &bundle.role) {
+            claims.claim(pane, bundle_path);
+        }
+        let context = format!("bundle {}", bundle_path);
+        let dir = bundle
+            .dir
+            .as_deref()
+            .map(|d| interpolate::expand_vars(d, &config.vars, &context))
+            .transpose()?;
+        if let Some(entry) = pane_cmds.iter_mut().find(|p| p.pane == target_pane) {
+            entry.commands.extend(direct_cmds);
+            entry.delay_ms = entry.delay_ms.max(bundle.delay_ms);
+            if entry.title.is_none() {
+                entry.title = bundle.title.clone();
+            }
+            if entry.size.is_none() {
+                entry.size = bundle.size;
+            }
+            if entry.dir.is_none() {
+                entry.dir = dir;
+            }
         } else {
-            pane_cmds.push((target_pane, direct_cmds));
+            pane_cmds.push(PaneCommands {
+                pane: target_pane,
+                commands: direct_cmds,
+                delay_ms: bundle.delay_ms,
+                title: bundle.title.clone(),
+                size: bundle.size,
+                dir,
+            });
         }
     }
 
@@ -194,9 +783,39 @@ fn resolve_with_panes_inner(
     Ok(())
 }
 
+/// Resolve one `@ref` entry whose referencing bundle sits in `target_pane`.
+///
+/// `@group.each` gives the group's bundles consecutive panes starting at
+/// `target_pane`; every other reference starts them all there.
+fn resolve_ref_with_panes(
+    config: &Config,
+    machine: &dyn Machine,
+    reference: &ResolvedRef,
+    visited: &mut HashSet<String>,
+    pane_cmds: &mut Vec<PaneCommands>,
+    claims: &mut PaneClaims,
+    target_pane: u32,
+) -> Result<()> {
+    let each = matches!(reference, ResolvedRef::GroupEach { .. });
+    let targets = ref_targets(config, reference)?;
+    let active = targets
+        .iter()
+        .filter(|path| !config.bundle_skipped(path, machine));
+    for (i, ref_path) in active.enumerate() {
+        let pane = if each {
+            target_pane + i as u32
+        } else {
+            target_pane
+        };
+        resolve_with_panes_inner(config, machine, ref_path, visited, pane_cmds, claims, pane)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
 
     #[test]
     fn test_parse_ref_command() {
@@ -226,4 +845,962 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_parse_ref_group_each() {
+        assert_eq!(
+            parse_ref("@dev.each"),
+            ResolvedRef::GroupEach {
+                group: "dev".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_group_each_vs_group_all() {
+        let config = Config::from_str(
+            r#"
+            [dev.api]
+            cmd = "cargo run"
+
+            [dev.logs]
+            cmd = "tail -f log"
+            pane = 3
+
+            [dev.web]
+            cmd = "npm run dev"
+
+            [run.merged]
+            cmd = ["@dev.*"]
+
+            [run.split]
+            cmd = ["@dev.each"]
+            "#,
+        )
+        .unwrap();
+
+        // `.*` piles every bundle into the referencing pane
+        let merged = resolve_with_panes(&config, "run.merged").unwrap();
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].pane, 0);
+        assert_eq!(merged[0].commands, vec!["cargo run", "npm run dev"]);
+        assert_eq!(merged[1].pane, 3);
+
+        // `.each` gives each bundle its own pane, keeping explicit ones
+        let split = resolve_with_panes(&config, "run.split").unwrap();
+        let panes: Vec<_> = split.iter().map(|p| (p.pane, p.commands.clone())).collect();
+        assert_eq!(
+            panes,
+            vec![
+                (0, vec!["cargo run".to_string()]),
+                (3, vec!["tail -f log".to_string()]),
+                (2, vec!["npm run dev".to_string()]),
+            ]
+        );
+
+        // Flat resolution is the same for both
+        assert_eq!(
+            resolve_bundle(&config, "run.split").unwrap(),
+            resolve_bundle(&config, "run.merged").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_ref_group_default() {
+        assert_eq!(
+            parse_ref("@dev"),
+            ResolvedRef::GroupDefault {
+                group: "dev".to_string()
+            }
+        );
+        assert_eq!(parse_ref("@"), ResolvedRef::Command("@".to_string()));
+        assert_eq!(
+            parse_ref("@echo hi"),
+            ResolvedRef::Command("@echo hi".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_group_default_entry() {
+        let config = Config::from_str(
+            r#"
+            [dev.main]
+            cmd = "npm run dev"
+
+            [dev.default]
+            cmd = "make"
+
+            [run.all]
+            cmd = ["@dev"]
+            "#,
+        )
+        .unwrap();
+
+        // `default` wins over `main` when both exist
+        assert_eq!(resolve_bundle(&config, "run.all").unwrap(), vec!["make"]);
+    }
+
+    #[test]
+    fn test_literal_at_command_is_sent_verbatim() {
+        let config = Config::from_str(
+            r#"
+            [dev.main]
+            cmd = "npm run dev"
+
+            [ops.notify]
+            cmd = ["@reboot-notify", "@dev"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parse_ref_in(&config, "@reboot-notify"),
+            ResolvedRef::Command("@reboot-notify".to_string())
+        );
+        assert_eq!(
+            resolve_bundle(&config, "ops.notify").unwrap(),
+            vec!["@reboot-notify", "npm run dev"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_configured_default_entry() {
+        let config = Config::from_str(
+            r#"
+            [defaults]
+            default_entry = "primary"
+
+            [dev.primary]
+            cmd = "cargo run"
+
+            [dev.default]
+            cmd = "make"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_commands(&config, &["@dev".to_string()]).unwrap(),
+            vec!["cargo run"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_group_without_default_lists_entries() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+
+            [dev.api]
+            cmd = "cargo run"
+
+            [run.all]
+            cmd = ["@dev"]
+            "#,
+        )
+        .unwrap();
+
+        match resolve_with_panes(&config, "run.all") {
+            Err(PanoutError::BundleNotFound(msg)) => {
+                assert!(msg.contains("default or main"), "{}", msg);
+                assert!(msg.ends_with("available: api, web"), "{}", msg);
+            }
+            other => panic!("expected BundleNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resolve_bundle_traced_nested_refs() {
+        let config = Config::from_str(
+            r#"
+            [base.env]
+            cmd = "source .env"
+
+            [dev.web]
+            cmd = ["@base.env", "npm run dev"]
+
+            [dev.all]
+            cmd = ["echo start", "@dev.web"]
+            "#,
+        )
+        .unwrap();
+
+        let traced = resolve_bundle_traced(&config, "dev.all").unwrap();
+        assert_eq!(
+            traced,
+            vec![
+                ("dev.all".to_string(), "echo start".to_string()),
+                ("base.env".to_string(), "source .env".to_string()),
+                ("dev.web".to_string(), "npm run dev".to_string()),
+            ]
+        );
+        let plain: Vec<String> = traced.into_iter().map(|(_, cmd)| cmd).collect();
+        assert_eq!(resolve_bundle(&config, "dev.all").unwrap(), plain);
+    }
+
+    #[test]
+    fn test_resolve_bundle_traced_group_all() {
+        let config = Config::from_str(
+            r#"
+            [svc.db]
+            cmd = "postgres"
+
+            [svc.api]
+            cmd = ["cargo build", "cargo run"]
+
+            [run.all]
+            cmd = "@svc.*"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_bundle_traced(&config, "run.all").unwrap(),
+            vec![
+                ("svc.db".to_string(), "postgres".to_string()),
+                ("svc.api".to_string(), "cargo build".to_string()),
+                ("svc.api".to_string(), "cargo run".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_group_honours_after() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            after = "dev.api"
+
+            [dev.api]
+            cmd = "cargo run"
+            after = ["dev.db", "tools.lint"]
+
+            [dev.docs]
+            cmd = "mdbook serve"
+
+            [dev.db]
+            cmd = "postgres"
+
+            [tools.lint]
+            cmd = "cargo clippy"
+
+            [run.all]
+            cmd = "@dev.*"
+            "#,
+        )
+        .unwrap();
+
+        // `tools.lint` is outside the group, so it isn't pulled in
+        assert_eq!(
+            resolve_bundle(&config, "run.all").unwrap(),
+            vec!["postgres", "cargo run", "npm run dev", "mdbook serve"]
+        );
+    }
+
+    #[test]
+    fn test_after_cycle_is_circular_ref() {
+        let config = Config::from_str(
+            r#"
+            [dev.a]
+            cmd = "a"
+            after = ["dev.b"]
+
+            [dev.b]
+            cmd = "b"
+            after = ["dev.c"]
+
+            [dev.c]
+            cmd = "c"
+            after = ["dev.a"]
+
+            [run.all]
+            cmd = "@dev.each"
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            resolve_with_panes(&config, "run.all"),
+            Err(PanoutError::CircularRef(chain))
+                if chain == "after dev.a -> dev.b -> dev.c -> dev.a"
+        ));
+        assert!(matches!(
+            config.validate(&crate::config::Strictness::default()),
+            Err(PanoutError::CircularRef(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_group_in_declared_order() {
+        let config = Config::from_str(
+            r#"
+            [start.zookeeper]
+            cmd = "zkServer start"
+
+            [start.kafka]
+            cmd = "kafka-server-start"
+
+            [start.app]
+            cmd = "./app"
+
+            [run.all]
+            cmd = "@start.each"
+            "#,
+        )
+        .unwrap();
+
+        let plan = resolve_with_panes(&config, "run.all").unwrap();
+        let panes: Vec<_> = plan
+            .iter()
+            .map(|p| (p.pane, p.commands[0].as_str()))
+            .collect();
+        assert_eq!(
+            panes,
+            vec![
+                (0, "zkServer start"),
+                (1, "kafka-server-start"),
+                (2, "./app")
+            ]
+        );
+        assert_eq!(
+            config.list_bundles(),
+            vec!["run.all", "start.app", "start.kafka", "start.zookeeper"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_commands_expands_refs() {
+        let config = Config::from_str(
+            r#"
+            [project.init]
+            cmd = ["cd ~/.config/nvim", "nvim init.vim"]
+            "#,
+        )
+        .unwrap();
+
+        let cmds = resolve_commands(
+            &config,
+            &["cd /srv".to_string(), "@project.init".to_string()],
+        )
+        .unwrap();
+        assert_eq!(cmds, vec!["cd /srv", "cd ~/.config/nvim", "nvim init.vim"]);
+    }
+
+    #[test]
+    fn test_resolve_with_panes_carries_delay() {
+        let config = Config::from_str(
+            r#"
+            [dev.db]
+            cmd = ["docker compose up -d", "sleep 1"]
+            delay_ms = 500
+
+            [dev.api]
+            cmd = "cargo run"
+            pane = 1
+
+            [dev.all]
+            cmd = ["@dev.db", "@dev.api"]
+            "#,
+        )
+        .unwrap();
+
+        let panes = resolve_with_panes(&config, "dev.all").unwrap();
+        assert_eq!(
+            panes,
+            vec![
+                PaneCommands {
+                    pane: 0,
+                    commands: vec!["docker compose up -d".into(), "sleep 1".into()],
+                    delay_ms: Some(500),
+                    title: None,
+                    size: None,
+                    dir: None,
+                },
+                PaneCommands {
+                    pane: 1,
+                    commands: vec!["cargo run".into()],
+                    delay_ms: None,
+                    title: None,
+                    size: None,
+                    dir: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_with_panes_titles() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            title = "frontend"
+
+            [dev.api]
+            cmd = "cargo run"
+            pane = 1
+
+            [dev.all]
+            cmd = ["@dev.web", "@dev.api"]
+            "#,
+        )
+        .unwrap();
+
+        let panes = resolve_with_panes(&config, "dev.all").unwrap();
+        assert_eq!(panes[0].title.as_deref(), Some("frontend"));
+        assert_eq!(panes[1].title, None);
+    }
+
+    #[test]
+    fn test_resolve_with_panes_roles_share_a_pane() {
+        let config = Config::from_str(
+            r#"
+            [dev.app-logs]
+            cmd = "tail -f app.log"
+            pane = 1
+            role = "logs"
+
+            [dev.db-logs]
+            cmd = "tail -f db.log"
+            pane = 3
+            role = "logs"
+
+            [dev.web]
+            cmd = "npm run dev"
+
+            [dev.all]
+            cmd = ["@dev.web", "@dev.app-logs", "@dev.db-logs"]
+            "#,
+        )
+        .unwrap();
+
+        let panes = resolve_with_panes(&config, "dev.all").unwrap();
+        assert_eq!(panes.len(), 2);
+        assert_eq!(panes[0].pane, 0);
+        assert_eq!(panes[1].pane, 1);
+        assert_eq!(panes[1].commands, vec!["tail -f app.log", "tail -f db.log"]);
+        assert_eq!(required_panes(&panes), 2);
+    }
+
+    #[test]
+    fn test_resolve_with_panes_role_uses_default_pane() {
+        let config = Config::from_str(
+            r#"
+            [svc.a]
+            cmd = "a"
+            role = "main"
+
+            [svc.b]
+            cmd = "b"
+            role = "main"
+
+            [svc.c]
+            cmd = "c"
+
+            [run.all]
+            cmd = "@svc.each"
+            "#,
+        )
+        .unwrap();
+
+        // `each` would give a, b, c panes 0, 1, 2; the role folds b into a's,
+        // and c moves into the pane b left empty
+        let panes = resolve_with_panes(&config, "run.all").unwrap();
+        let layout: Vec<_> = panes.iter().map(|p| (p.pane, p.commands.clone())).collect();
+        assert_eq!(
+            layout,
+            vec![
+                (0, vec!["a".to_string(), "b".to_string()]),
+                (1, vec!["c".to_string()])
+            ]
+        );
+        assert_eq!(required_panes(&panes), 2);
+    }
+
+    #[test]
+    fn test_resolve_expands_vars() {
+        let config = Config::from_str(
+            r#"
+            [vars]
+            api_port = "8080"
+
+            [dev.api]
+            cmd = "serve -p {api_port}"
+
+            [dev.bad]
+            cmd = "serve -p {api_prot}"
+
+            [dev.all]
+            cmd = ["@dev.api", "@dev.bad"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_bundle(&config, "dev.api").unwrap(),
+            vec!["serve -p 8080"]
+        );
+        let err = resolve_with_panes(&config, "dev.all").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown variable {api_prot} in bundle dev.bad"
+        );
+    }
+
+    fn plan(panes: &[(u32, &str)]) -> Vec<PaneCommands> {
+        panes
+            .iter()
+            .map(|&(pane, cmd)| PaneCommands {
+                pane,
+                commands: vec![cmd.to_string()],
+                delay_ms: None,
+                title: None,
+                size: None,
+                dir: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pane_sizes_per_pane() {
+        let config = Config::from_str(
+            r#"
+            [dev.editor]
+            cmd = "nvim ."
+            pane = 0
+            size = 60
+
+            [dev.logs]
+            cmd = "tail -f log"
+            pane = 1
+
+            [dev.all]
+            cmd = ["@dev.editor", "@dev.logs"]
+            "#,
+        )
+        .unwrap();
+
+        let panes = resolve_with_panes(&config, "dev.all").unwrap();
+        assert_eq!(
+            pane_sizes(&panes, 3),
+            Some(vec![Some(PaneSize::Percent(60)), None, None])
+        );
+    }
+
+    #[test]
+    fn test_pane_sizes_none_without_sizes() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            "#,
+        )
+        .unwrap();
+
+        let panes = resolve_with_panes(&config, "dev.web").unwrap();
+        assert_eq!(pane_sizes(&panes, 2), None);
+    }
+
+    #[test]
+    fn test_required_panes() {
+        assert_eq!(required_panes(&[]), 1);
+        assert_eq!(required_panes(&plan(&[(0, "a")])), 1);
+        assert_eq!(required_panes(&plan(&[(0, "a"), (3, "d"), (1, "b")])), 4);
+    }
+
+    #[test]
+    fn test_clamp_panes_folds_overflow_into_last() {
+        let clamped = clamp_panes(plan(&[(0, "a"), (1, "b"), (3, "d"), (2, "c")]), 2);
+        assert_eq!(
+            clamped,
+            vec![
+                PaneCommands {
+                    pane: 0,
+                    commands: vec!["a".into()],
+                    delay_ms: None,
+                    title: None,
+                    size: None,
+                    dir: None,
+                },
+                PaneCommands {
+                    pane: 1,
+                    commands: vec!["b".into(), "c".into(), "d".into()],
+                    delay_ms: None,
+                    title: None,
+                    size: None,
+                    dir: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clamp_panes_when_last_pane_empty() {
+        let clamped = clamp_panes(plan(&[(0, "a"), (2, "c")]), 2);
+        assert_eq!(clamped[1].pane, 1);
+        assert_eq!(clamped[1].commands, vec!["c"]);
+    }
+
+    #[test]
+    fn test_resolve_prefix_and_suffix() {
+        let config = Config::from_str(
+            r#"
+            [defaults]
+            suffix = "; read"
+
+            [dev.prefixed]
+            cmd = ["cargo build", "cargo test"]
+            prefix = "nix develop -c"
+            suffix = ""
+
+            [dev.suffixed]
+            cmd = "make"
+
+            [dev.both]
+            cmd = ["@dev.suffixed", "./run.sh"]
+            prefix = "time"
+            suffix = "|| true"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_bundle(&config, "dev.prefixed").unwrap(),
+            vec!["nix develop -c cargo build", "nix develop -c cargo test"]
+        );
+        assert_eq!(
+            resolve_bundle(&config, "dev.suffixed").unwrap(),
+            vec!["make ; read"]
+        );
+        // A referenced bundle keeps its own wrapping, not the referrer's
+        assert_eq!(
+            resolve_bundle(&config, "dev.both").unwrap(),
+            vec!["make ; read", "time ./run.sh || true"]
+        );
+        let plan = resolve_with_panes(&config, "dev.both").unwrap();
+        assert_eq!(
+            plan[0].commands,
+            vec!["make ; read", "time ./run.sh || true"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_tag() {
+        let config = Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            tags = ["work"]
+
+            [dev.api]
+            cmd = ["cargo build", "cargo run"]
+            tags = ["work", "rust"]
+
+            [dev.docs]
+            cmd = "mdbook serve"
+            "#,
+        )
+        .unwrap();
+
+        let plan = resolve_tag(&config, "work").unwrap();
+        assert_eq!(plan.len(), 2);
+        assert_eq!(
+            (plan[0].pane, plan[0].commands.clone()),
+            (0, vec!["cargo build".to_string(), "cargo run".to_string()])
+        );
+        assert_eq!(
+            (plan[1].pane, plan[1].commands.clone()),
+            (1, vec!["npm run dev".to_string()])
+        );
+
+        assert!(matches!(
+            resolve_tag(&config, "docs"),
+            Err(PanoutError::BundleNotFound(name)) if name == "tag 'docs'"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_per_platform_without_default() {
+        let other = if cfg!(target_os = "windows") {
+            "linux"
+        } else {
+            "windows"
+        };
+        let config = Config::from_str(&format!(
+            r#"
+            [dev.open]
+            cmd = {{ {} = "start ." }}
+            "#,
+            other
+        ))
+        .unwrap();
+
+        let os = std::env::consts::OS;
+        let expected = format!("bundle dev.open has no command for {}", os);
+        for result in [
+            resolve_bundle(&config, "dev.open").map(drop),
+            resolve_with_panes(&config, "dev.open").map(drop),
+        ] {
+            assert!(matches!(
+                result,
+                Err(PanoutError::InvalidConfig(msg)) if msg.starts_with(&expected)
+            ));
+        }
+    }
+
+    struct Host(&'static str);
+
+    impl Machine for Host {
+        fn hostname(&self) -> Option<&str> {
+            Some(self.0)
+        }
+
+        fn var(&self, _key: &str) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_resolve_skips_bundles_whose_when_fails() {
+        let config = Config::from_str(
+            r#"
+            [run.all]
+            cmd = ["@dev.each", "echo done"]
+
+            [dev.gpu]
+            cmd = "nvidia-smi -l"
+            when = { hostname = "workstation-*" }
+
+            [dev.web]
+            cmd = "npm run dev"
+
+            [dev.ci]
+            cmd = "./ci.sh"
+            when = { env = "CI", equals = "true" }
+            "#,
+        )
+        .unwrap();
+
+        let work = Host("Workstation-2");
+        let laptop = Host("laptop");
+        assert_eq!(
+            resolve_bundle_on(&config, "run.all", &work).unwrap(),
+            vec!["nvidia-smi -l", "npm run dev", "echo done"]
+        );
+        assert_eq!(
+            resolve_bundle_on(&config, "run.all", &laptop).unwrap(),
+            vec!["npm run dev", "echo done"]
+        );
+        assert!(
+            resolve_bundle_on(&config, "dev.gpu", &laptop)
+                .unwrap()
+                .is_empty()
+        );
+
+        // `@group.each` closes up the skipped bundle's pane
+        let plan = resolve_with_panes_on(&config, "run.all", &laptop).unwrap();
+        assert_eq!(
+            plan.iter()
+                .map(|p| (p.pane, p.commands.clone()))
+                .collect::<Vec<_>>(),
+            vec![(0, vec!["npm run dev".to_string(), "echo done".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_resolve_tag_and_window_on_machine() {
+        let config = Config::from_str(
+            r#"
+            [dev.gpu]
+            cmd = "nvidia-smi -l"
+            tags = ["watch"]
+            when = { hostname = "workstation-*" }
+
+            [dev.logs]
+            cmd = "tail -f app.log"
+            tags = ["watch"]
+
+            [workspace.ws]
+            windows = [{ panes = 1, cmd = ["@dev.gpu", "@dev.logs"] }]
+            "#,
+        )
+        .unwrap();
+        let work = Host("workstation-1");
+        let laptop = Host("laptop");
+        let commands = |plan: Vec<PaneCommands>| {
+            plan.into_iter()
+                .map(|p| (p.pane, p.commands))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            commands(resolve_tag_on(&config, "watch", &work).unwrap()),
+            vec![
+                (0, vec!["nvidia-smi -l".to_string()]),
+                (1, vec!["tail -f app.log".to_string()])
+            ]
+        );
+        assert_eq!(
+            commands(resolve_tag_on(&config, "watch", &laptop).unwrap()),
+            vec![(0, vec!["tail -f app.log".to_string()])]
+        );
+
+        let win = &config.get_workspace("ws").unwrap().windows[0];
+        let cmds = |machine: &dyn Machine| {
+            let win = resolve_window_on(&config, win, machine).unwrap();
+            win.commands_for_pane(0)
+        };
+        assert_eq!(cmds(&work), vec!["nvidia-smi -l", "tail -f app.log"]);
+        assert_eq!(cmds(&laptop), vec!["tail -f app.log"]);
+        assert_eq!(
+            resolve_commands_on(&config, &["@dev.gpu".to_string()], &laptop).unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_resolve_empty_cmd_reserves_pane() {
+        let config = Config::from_str(
+            r#"
+            [dev.spare]
+            cmd = []
+            pane = 2
+
+            [dev.web]
+            cmd = "npm run dev"
+
+            [dev.all]
+            cmd = ["@dev.web", "@dev.spare"]
+            "#,
+        )
+        .unwrap();
+
+        let plan = resolve_with_panes(&config, "dev.all").unwrap();
+        assert_eq!(required_panes(&plan), 3);
+        let spare = plan.iter().find(|p| p.pane == 2).unwrap();
+        assert!(spare.commands.is_empty());
+        assert!(resolve_bundle(&config, "dev.spare").unwrap().is_empty());
+    }
+
+    fn window_config() -> Config {
+        Config::from_str(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+
+            [dev.api]
+            cmd = "cargo run"
+            pane = 1
+
+            [dev.all]
+            cmd = ["@dev.web", "@dev.api"]
+
+            [loop.a]
+            cmd = "@loop.b"
+
+            [loop.b]
+            cmd = "@loop.a"
+
+            [workspace.ws]
+            windows = [
+                { panes = 2, cmd = ["clear", "@dev.all"] },
+                { panes = 2, cmd = "ls" },
+                { panes = 1, cmd = "@loop.a" },
+                { panes = 1, cmd = "@dev.nope" },
+            ]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_window_maps_bundle_panes() {
+        let config = window_config();
+        let ws = config.get_workspace("ws").unwrap();
+        let win = resolve_window(&config, &ws.windows[0]).unwrap();
+
+        assert_eq!(win.commands_for_pane(0), vec!["clear", "npm run dev"]);
+        assert_eq!(win.commands_for_pane(1), vec!["clear", "cargo run"]);
+    }
+
+    #[test]
+    fn test_resolve_window_folds_extra_panes() {
+        let config = window_config();
+        let mut win = config.get_workspace("ws").unwrap().windows[0].clone();
+        win.panes = Panes::Count(1);
+        let win = resolve_window(&config, &win).unwrap();
+
+        assert_eq!(
+            win.commands_for_pane(0),
+            vec!["clear", "npm run dev", "cargo run"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_window_plain_commands_unchanged() {
+        let config = window_config();
+        let win = resolve_window(&config, &config.get_workspace("ws").unwrap().windows[1]).unwrap();
+
+        assert!(matches!(win.panes, Panes::Count(2)));
+        assert_eq!(win.commands_for_pane(1), vec!["ls"]);
+    }
+
+    #[test]
+    fn test_resolve_window_errors_like_bundles() {
+        let config = window_config();
+        let ws = config.get_workspace("ws").unwrap();
+
+        assert!(matches!(
+            resolve_window(&config, &ws.windows[2]),
+            Err(PanoutError::CircularRef(_))
+        ));
+        assert!(matches!(
+            resolve_window(&config, &ws.windows[3]),
+            Err(PanoutError::BundleNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_pane_conflict() {
+        let config = Config::from_str(
+            r#"
+            [dev.api]
+            cmd = "cargo run"
+            pane = 1
+
+            [dev.db]
+            cmd = "psql"
+            pane = 1
+
+            [dev.logs]
+            cmd = "tail -f log"
+            pane = 1
+            role = "watch"
+
+            [dev.metrics]
+            cmd = "htop"
+            pane = 1
+            role = "watch"
+
+            [dev.clash]
+            cmd = ["@dev.api", "@dev.db"]
+
+            [dev.shared]
+            cmd = ["@dev.logs", "@dev.metrics"]
+
+            [dev.twice]
+            cmd = ["@dev.api", "@dev.api"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            pane_conflict(&config, "dev.clash").unwrap().as_deref(),
+            Some("bundle dev.api and bundle dev.db both set pane 1")
+        );
+        assert_eq!(pane_conflict(&config, "dev.shared").unwrap(), None);
+        assert_eq!(pane_conflict(&config, "dev.twice").unwrap(), None);
+    }
 }
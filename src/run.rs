@@ -0,0 +1,1970 @@
+//! Running bundles, workspaces, and servers.
+//!
+//! These are the library entry points behind the `panout` binary. Each
+//! comes in two forms: [`run_bundle`] and friends drive the real tmux, while
+//! the `_with` variants take any [`TmuxBackend`], e.g. a
+//! [`MockTmux`](crate::tmux::MockTmux) to preview a run without touching tmux.
+//!
+//! The config is expected to be validated already (see
+//! [`Config::validate`]).
+
+use crate::condition::Platform;
+use crate::config::{BundleEntry, Config, Defaults, Layout, PaneSize, Panes, WindowDef, Workspace};
+use crate::error::{PanoutError, Result};
+use crate::interpolate::{self, GitRoot};
+use crate::resolver::{self, PaneCommands};
+use crate::tmux::{self, ProcessTmux, TmuxBackend};
+use crate::{hook, path, session, shell, ssh};
+use std::thread;
+use std::time::Duration;
+
+/// Per-run settings that override what the config says.
+///
+/// The default runs a target exactly as configured. The `panout` binary
+/// builds this from its command line with [`Cli::run_options`](crate::cli::Cli::run_options).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunOptions {
+    /// Minimum number of panes for a bundle; defaults to as many as it assigns.
+    ///
+    /// More panes are created if the bundle assigns commands past `num`.
+    pub num: Option<u32>,
+    /// Cap the panes at `num`, merging commands for later panes into the last.
+    pub clamp: bool,
+    /// Layout override for bundles.
+    pub layout: Option<Layout>,
+    /// Milliseconds between commands sent to the same pane; overrides `delay_ms`.
+    pub delay_ms: Option<u64>,
+    /// Build a bundle in a new window instead of splitting the current one.
+    pub new_window: bool,
+    /// Name for the new window; defaults to the bundle name.
+    pub window_name: Option<String>,
+    /// Stay in the new window instead of switching back.
+    pub stay: bool,
+    /// Working directory for every pane; overrides the bundle's `dir`.
+    pub dir: Option<String>,
+    /// Extra environment variables; override the bundle's `env`.
+    pub env: Vec<(String, String)>,
+    /// Pane to select (0-based) after setup; overrides the bundle's `focus`.
+    pub focus: Option<u32>,
+    /// Commands sent to every pane of a bundle after its own commands.
+    pub broadcast: Vec<String>,
+    /// Mirror keystrokes across panes once setup is done.
+    pub sync: bool,
+    /// Enable every strict check (see [`Config::strictness`]).
+    pub strict: bool,
+    /// Only create some of a workspace's windows (see [`Workspace::filter_windows`]).
+    pub windows: Option<String>,
+}
+
+/// How many panes and windows a run would create.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSize {
+    /// Panes across every window.
+    pub panes: u32,
+    /// Windows holding those panes, including the current one.
+    pub windows: u32,
+}
+
+impl RunSize {
+    /// Whether the run should be confirmed first: it makes more than
+    /// `threshold` panes, the `[defaults] confirm_threshold`.
+    pub fn needs_confirmation(&self, threshold: Option<u32>) -> bool {
+        threshold.is_some_and(|threshold| self.panes > threshold)
+    }
+
+    /// The question asked before the run.
+    pub fn prompt(&self) -> String {
+        format!(
+            "This will create {} panes across {} window{}. Continue? [y/N] ",
+            self.panes,
+            self.windows,
+            if self.windows == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Size of a bundle run, with `-n`/`--clamp` applied.
+pub fn bundle_size(config: &Config, bundle_name: &str, opts: &RunOptions) -> Result<RunSize> {
+    let (_, panes) = plan_bundle(config, bundle_name, opts)?;
+    Ok(RunSize { panes, windows: 1 })
+}
+
+/// Size of a `--tag` run, with `-n`/`--clamp` applied.
+pub fn tag_size(config: &Config, tag: &str, opts: &RunOptions) -> Result<RunSize> {
+    let (_, panes) = plan_panes(resolver::resolve_tag(config, tag)?, opts);
+    Ok(RunSize { panes, windows: 1 })
+}
+
+/// Size of a workspace run, with the `--windows` filter applied.
+pub fn workspace_size(config: &Config, name: &str, opts: &RunOptions) -> Result<RunSize> {
+    let workspace = config
+        .get_workspace(name)
+        .ok_or_else(|| PanoutError::WorkspaceNotFound(name.into()))?;
+    let workspace = match opts.windows {
+        Some(ref spec) => workspace.filter_windows(spec)?,
+        None => workspace.clone(),
+    };
+    Ok(RunSize {
+        panes: workspace.windows.iter().map(WindowDef::pane_count).sum(),
+        windows: workspace.windows.len() as u32,
+    })
+}
+
+/// The layout a run of `bundle` (`None` for a `--tag` run) would use for
+/// `panes` panes.
+///
+/// Precedence: options > bundle config > defaults (per-count, then plain) >
+/// tiled.
+pub fn bundle_layout(
+    config: &Config,
+    bundle: Option<&BundleEntry>,
+    panes: u32,
+    opts: &RunOptions,
+) -> Layout {
+    opts.layout
+        .or_else(|| bundle.and_then(|b| b.layout))
+        .or_else(|| config.defaults.layout_for(panes))
+        .unwrap_or(Layout::Tiled)
+}
+
+/// Raw tmux layout applied once a bundle's panes exist, if any.
+///
+/// Precedence: a layout flag (which turns it off) > the bundle's
+/// `layout_string` > `[defaults]` `layout_string`.
+pub fn bundle_layout_string<'a>(
+    config: &'a Config,
+    bundle: Option<&'a BundleEntry>,
+    opts: &RunOptions,
+) -> Option<&'a str> {
+    if opts.layout.is_some() {
+        return None;
+    }
+    bundle
+        .and_then(|b| b.layout_string.as_deref())
+        .or(config.defaults.layout_string.as_deref())
+}
+
+/// Resolve a bundle's pane commands and the number of panes to create.
+fn plan_bundle(
+    config: &Config,
+    bundle_name: &str,
+    opts: &RunOptions,
+) -> Result<(Vec<PaneCommands>, u32)> {
+    Ok(plan_panes(
+        resolver::resolve_with_panes(config, bundle_name)?,
+        opts,
+    ))
+}
+
+/// Decide how many panes a resolved plan gets.
+///
+/// `num` is a floor on the count, and only caps it when `clamp` folds
+/// the overflow into the last pane.
+fn plan_panes(pane_commands: Vec<PaneCommands>, opts: &RunOptions) -> (Vec<PaneCommands>, u32) {
+    let required = resolver::required_panes(&pane_commands);
+    match opts.num {
+        None => (pane_commands, required),
+        Some(n) if n >= required => (pane_commands, n),
+        Some(n) if opts.clamp => (resolver::clamp_panes(pane_commands, n), n),
+        Some(_) => (pane_commands, required),
+    }
+}
+
+/// Run a bundle in the current tmux session.
+///
+/// See [`run_bundle_with`].
+pub fn run_bundle(config: &Config, name: &str, opts: &RunOptions) -> Result<()> {
+    run_bundle_with(&ProcessTmux::new(), config, name, opts)
+}
+
+/// Run a workspace in the current tmux session.
+///
+/// See [`run_workspace_with`].
+pub fn run_workspace(config: &Config, name: &str, opts: &RunOptions) -> Result<()> {
+    run_workspace_with(&ProcessTmux::new(), config, name, opts)
+}
+
+/// Run every bundle tagged `tag` in the current tmux session.
+///
+/// See [`run_tag_with`].
+pub fn run_tag(config: &Config, tag: &str, opts: &RunOptions) -> Result<()> {
+    run_tag_with(&ProcessTmux::new(), config, tag, opts)
+}
+
+/// Connect to a server in the current pane.
+///
+/// See [`run_server_with`].
+pub fn run_server(config: &Config, name: &str) -> Result<()> {
+    run_server_with(&ProcessTmux::new(), config, name)
+}
+
+/// Execute a bundle configuration using `tmux`.
+///
+/// Bundles targeting SSH servers (detected via resolved commands matching
+/// known server hosts) route through the session module to create
+/// persistent remote tmux sessions. Other bundles send commands to
+/// local panes as before. `bundle_name` may be an [alias](Config::resolve_alias).
+pub fn run_bundle_with(
+    tmux: &dyn TmuxBackend,
+    config: &Config,
+    bundle_name: &str,
+    opts: &RunOptions,
+) -> Result<()> {
+    let bundle_name = &config.resolve_alias(bundle_name)?;
+    let (pane_commands, num_panes) = plan_bundle(config, bundle_name, opts)?;
+    let bundle = config.get_bundle(bundle_name);
+    run_panes_with(
+        tmux,
+        config,
+        bundle_name,
+        bundle,
+        pane_commands,
+        num_panes,
+        opts,
+    )
+}
+
+/// Run every bundle tagged `tag` side by side, like a bundle made of them.
+///
+/// The bundles take consecutive panes in sorted order (see
+/// [`resolver::resolve_tag`]). Options apply as for [`run_bundle_with`];
+/// `--new-window` names the window after the tag.
+///
+/// # Errors
+///
+/// [`PanoutError::BundleNotFound`] naming the tag if no bundle carries it,
+/// otherwise as for [`run_bundle_with`].
+pub fn run_tag_with(
+    tmux: &dyn TmuxBackend,
+    config: &Config,
+    tag: &str,
+    opts: &RunOptions,
+) -> Result<()> {
+    let (pane_commands, num_panes) = plan_panes(resolver::resolve_tag(config, tag)?, opts);
+    run_panes_with(tmux, config, tag, None, pane_commands, num_panes, opts)
+}
+
+/// Lay out a resolved plan in panes and send each its commands.
+///
+/// `bundle` is the bundle being run, for its own settings (layout, `dir`,
+/// `env`, hooks, ...); `bundle_name` names the new window and remote session.
+fn run_panes_with(
+    tmux: &dyn TmuxBackend,
+    config: &Config,
+    bundle_name: &str,
+    bundle: Option<&BundleEntry>,
+    mut pane_commands: Vec<PaneCommands>,
+    num_panes: u32,
+    opts: &RunOptions,
+) -> Result<()> {
+    let layout = bundle_layout(config, bundle, num_panes, opts);
+
+    // Working directory precedence: options > bundle config
+    let bundle_dir = bundle
+        .and_then(|b| b.dir.as_deref())
+        .map(|d| interpolate::expand_vars(d, &config.vars, &format!("bundle {}", bundle_name)))
+        .transpose()?;
+    let dir = opts
+        .dir
+        .as_deref()
+        .or(bundle_dir.as_deref())
+        .map(path::working_dir)
+        .transpose()?;
+
+    // Panes start in the `dir` of the bundle that feeds them, falling back to
+    // the one above; `--dir` overrides them all
+    let mut pane_dirs = vec![dir.clone(); num_panes as usize];
+    if opts.dir.is_none() {
+        for entry in &pane_commands {
+            if let (Some(d), Some(slot)) = (&entry.dir, pane_dirs.get_mut(entry.pane as usize)) {
+                *slot = Some(path::working_dir(d)?);
+            }
+        }
+    }
+    let start_dirs: Vec<Option<&str>> = pane_dirs.iter().map(Option::as_deref).collect();
+
+    // `{git_root}` is looked up from the bundle's directory, at most once
+    let strict = config.strictness(opts.strict);
+    let git_root = GitRoot::new(dir.as_deref().map(std::path::Path::new));
+    for entry in &mut pane_commands {
+        for cmd in &mut entry.commands {
+            *cmd = interpolate::expand_git_root(cmd, &git_root, strict.git_root)?;
+        }
+    }
+
+    // One-time setup: defaults first, then the bundle's own
+    if let Some(ref before) = config.defaults.before {
+        hook::run_all(before)?;
+    }
+    if let Some(before) = bundle.and_then(|b| b.before.as_ref()) {
+        hook::run_all(before)?;
+    }
+
+    // `new_window` builds the bundle in a fresh window instead of this one
+    let (window, start_window) = if opts.new_window {
+        let start = tmux.current_window()?;
+        let name = opts.window_name.as_deref().unwrap_or(bundle_name);
+        (Some(tmux.new_window(Some(name))?), Some(start))
+    } else {
+        (None, None)
+    };
+
+    // Sized panes are carved out by the splits; a layout would undo that.
+    // An explicit layout flag asks for even panes, so sizes give way to it.
+    let sizes = match opts.layout {
+        Some(_) => None,
+        None => resolver::pane_sizes(&pane_commands, num_panes),
+    };
+    let direction = bundle.and_then(|b| b.split_direction);
+    let pane_indices = match sizes {
+        Some(sizes) => {
+            let splits = tmux::split_sizes(&sizes)?;
+            tmux.create_sized_panes(window, &splits, &start_dirs, direction)?
+        }
+        None => {
+            set_main_pane_size(tmux, window, layout, &config.defaults)?;
+            tmux.create_panes(window, num_panes, layout, &start_dirs, direction)?
+        }
+    };
+    // A raw layout string describes the finished window, so it goes last
+    if let Some(custom) = bundle_layout_string(config, bundle, opts) {
+        tmux.select_custom_layout(window, custom)?;
+    }
+
+    for entry in &pane_commands {
+        if let Some(ref title) = entry.title {
+            tmux.set_pane_title(tmux::logical_pane(&pane_indices, entry.pane)?, title)?;
+        }
+    }
+
+    settle(
+        tmux,
+        &config.defaults,
+        window.is_some() || pane_indices.len() > 1,
+    )?;
+
+    // Split panes start in their directory; the original pane has to be moved
+    if let (Some(Some(dir)), Some(&first)) = (pane_dirs.first(), pane_indices.first()) {
+        tmux.send_keys(first, &format!("cd {}", shell::quote(dir)))?;
+    }
+
+    // Check if pane 0 commands include an SSH connection to a known server
+    let server_host = find_server_host(&pane_commands, config);
+
+    if let Some(host) = server_host {
+        // Remote bundle: create named tmux session on remote host
+        let cmd = session::build_remote_session_cmd(&host, bundle_name, None, &[]);
+        if let Some(&pane) = pane_indices.first() {
+            tmux.send_keys(pane, &cmd)?;
+        }
+    } else {
+        // Environment: bundle `env` with the options' `env` taking precedence
+        let mut env = bundle.map(|b| b.env.clone()).unwrap_or_default();
+        env.extend(opts.env.iter().cloned());
+        let exports = shell::export_commands(&env);
+        for &pane in &pane_indices {
+            for cmd in &exports {
+                tmux.send_keys(pane, cmd)?;
+            }
+        }
+
+        // Local bundle: send commands to panes as before. Configured `pane`
+        // values are logical (0-based), so translate them through the actual
+        // indices, which start at 1 with `pane-base-index 1`
+        for entry in pane_commands {
+            let actual_pane = tmux::logical_pane(&pane_indices, entry.pane)?;
+            let delay = opts.delay_ms.or(entry.delay_ms);
+            send_commands(tmux, actual_pane, &entry.commands, delay)?;
+        }
+    }
+
+    for &pane in &pane_indices {
+        for cmd in &opts.broadcast {
+            tmux.send_keys(pane, cmd)?;
+        }
+    }
+
+    // Only mirror input once every pane has its own commands, or each pane
+    // would receive all of them
+    if opts.sync || bundle.is_some_and(|b| b.synchronize) {
+        tmux.set_window_option(window, "synchronize-panes", "on")?;
+    }
+
+    // tmux-side hooks run once everything is in place, then local ones
+    if let Some(run_shell) = bundle.and_then(|b| b.run_shell.as_ref()) {
+        for cmd in run_shell.to_vec() {
+            tmux.run_shell(&cmd)?;
+        }
+    }
+    if let Some(post) = bundle.and_then(|b| b.post.as_ref()) {
+        hook::run_all_warn(post);
+    }
+
+    if let Some(focus) = opts.focus.or_else(|| bundle.and_then(|b| b.focus)) {
+        tmux.select_pane(tmux::logical_pane(&pane_indices, focus)?)?;
+    }
+
+    if let Some(start) = start_window
+        && !opts.stay
+    {
+        tmux.select_window(start)?;
+    }
+
+    Ok(())
+}
+
+/// Send commands to a pane, sleeping `delay_ms` between successive commands.
+fn send_commands(
+    tmux: &dyn TmuxBackend,
+    pane: u32,
+    commands: &[String],
+    delay_ms: Option<u64>,
+) -> Result<()> {
+    for (i, cmd) in commands.iter().enumerate() {
+        if i > 0
+            && let Some(ms) = delay_ms
+        {
+            thread::sleep(Duration::from_millis(ms));
+        }
+        send_command(tmux, pane, cmd)?;
+    }
+    Ok(())
+}
+
+/// Send one resolved command to a pane: typed with Enter, or pressed as
+/// keys if it's a [key sequence](tmux::key_sequence).
+fn send_command(tmux: &dyn TmuxBackend, pane: u32, command: &str) -> Result<()> {
+    match tmux::key_sequence(command) {
+        Some(keys) => tmux.send_key_sequence(pane, &keys),
+        None => tmux.send_keys(pane, command),
+    }
+}
+
+/// The first pane of the current window.
+fn first_pane(tmux: &dyn TmuxBackend) -> Result<u32> {
+    tmux.list_panes(None)?
+        .first()
+        .copied()
+        .ok_or_else(|| PanoutError::TmuxError("no panes in the current window".into()))
+}
+
+/// Check if resolved pane commands contain an SSH command targeting a known server.
+///
+/// Scans all resolved pane commands for `ssh <host>` patterns where `<host>`
+/// matches a server host from the config. Returns the first matching host.
+fn find_server_host(pane_commands: &[PaneCommands], config: &Config) -> Option<String> {
+    let known_hosts: Vec<&str> = config.servers.values().map(|s| s.host.as_str()).collect();
+
+    for entry in pane_commands {
+        for cmd in &entry.commands {
+            if let Some(rest) = cmd.strip_prefix("ssh ") {
+                let target = rest.trim();
+                if known_hosts
+                    .iter()
+                    .any(|h| target == *h || target.ends_with(h))
+                {
+                    return Some(target.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Connect to a server in the current pane and run its commands, using `tmux`.
+///
+/// `@ref`s in the server's `cmd` are expanded like bundle references, and
+/// `{user}`/`{ip}` are filled in from the server's host.
+pub fn run_server_with(tmux: &dyn TmuxBackend, config: &Config, name: &str) -> Result<()> {
+    let server = config
+        .servers
+        .get(name)
+        .ok_or_else(|| PanoutError::ServerNotFound(name.into()))?;
+
+    if let Some(ref cmd) = server.cmd {
+        cmd.check_platform(&Platform::current().os, &format!("server {}", name))?;
+    }
+    let commands = server.cmd.as_ref().map(|c| c.to_vec()).unwrap_or_default();
+    let commands = resolver::resolve_commands(config, &commands)?;
+
+    if !tmux.in_tmux() {
+        return Err(PanoutError::NotInTmux);
+    }
+    let pane = first_pane(tmux)?;
+    for cmd in ssh::server_commands(server, &commands) {
+        send_command(tmux, pane, &cmd)?;
+    }
+
+    Ok(())
+}
+
+/// Execute a workspace configuration (multiple windows with optional SSH)
+/// using `tmux`.
+///
+/// Remote workspaces (with `host` set) create a persistent named tmux
+/// session on the remote host via SSH, unless `disconnect` is set, in which
+/// case each pane connects, runs its commands, and exits. Local workspaces
+/// create windows and panes as before.
+pub fn run_workspace_with(
+    tmux: &dyn TmuxBackend,
+    config: &Config,
+    name: &str,
+    opts: &RunOptions,
+) -> Result<()> {
+    let workspace = config
+        .get_workspace(name)
+        .ok_or_else(|| PanoutError::WorkspaceNotFound(name.into()))?;
+
+    // Filter and expand up front so a bad --windows spec, unknown variable,
+    // or broken `@ref` fails before any tmux call
+    let workspace = match opts.windows {
+        Some(ref spec) => workspace.filter_windows(spec)?,
+        None => workspace.clone(),
+    };
+    let mut workspace = workspace.with_vars(&config.vars, name)?;
+    let os = &Platform::current().os;
+    for (i, win) in workspace.windows.iter_mut().enumerate() {
+        let what = format!("workspace {} window {}", name, i);
+        let pane_cmds = match win.panes {
+            Panes::Count(_) => &[][..],
+            Panes::List(ref panes) => panes,
+        };
+        for cmd in win
+            .cmd
+            .iter()
+            .chain(pane_cmds.iter().filter_map(|p| p.cmd.as_ref()))
+        {
+            cmd.check_platform(os, &what)?;
+        }
+        // Named before `@ref`s are expanded, while the window's `cmd` still shows them
+        if win.name.is_none() {
+            win.name = session::derive_window_name(win);
+        }
+        // `hosts` entries may name a server directly
+        for host in win.hosts.iter_mut().flatten() {
+            if config.servers.contains_key(host.as_str()) {
+                *host = format!("{}{}", ssh::SERVER_REF_PREFIX, host);
+            }
+        }
+    }
+    workspace.check_host_tokens(config, name)?;
+    workspace.windows = workspace
+        .windows
+        .iter()
+        .map(|win| resolver::resolve_window(config, win))
+        .collect::<Result<_>>()?;
+    let workspace = &workspace;
+
+    // One-time setup before any window exists
+    if let Some(ref before) = workspace.before {
+        hook::run_all(before)?;
+    }
+
+    match workspace.remote_session_host() {
+        Some(host) => {
+            // Remote session: SSH into host with named tmux session
+            let target = ssh::resolve_target(config, host)?;
+            let dir = workspace
+                .dir
+                .as_deref()
+                .map(|d| session::interpolate_host(d, &target.host));
+            let cmd = session::build_remote_session_cmd(
+                &target.host,
+                workspace.session.as_deref().unwrap_or(name),
+                dir.as_deref(),
+                &session::target_options(workspace, Some(&target)),
+            );
+            tmux.send_keys(first_pane(tmux)?, &cmd)?;
+        }
+        None => {
+            // Local, disconnecting, or per-window hosts: create windows/panes
+            match workspace.session {
+                Some(ref session_name) => {
+                    let session_name = session::sanitize_session_name(session_name);
+                    run_workspace_session(tmux, config, workspace, &session_name, opts.sync)?;
+                }
+                None => {
+                    let start_window = tmux.current_window()?;
+                    let start_pane = tmux.current_pane()?;
+                    let windows = run_workspace_windows(tmux, config, workspace, opts.sync, true)?;
+                    run_workspace_shell(tmux, workspace)?;
+                    if !select_focused_window(tmux, workspace, &windows)? && !workspace.stay {
+                        tmux.select_window(start_window)?;
+                        // The first window is built in the starting one; its
+                        // own focused pane wins over the one the user was in
+                        let first = workspace.windows.first();
+                        if first.is_none_or(|win| win.focused_pane().is_none()) {
+                            tmux.select_pane(start_pane)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(ref post) = workspace.post {
+        hook::run_all_warn(post);
+    }
+
+    Ok(())
+}
+
+/// Build a workspace's windows in its own session, then switch to it.
+///
+/// An existing session is reused, with the windows added to it, unless the
+/// workspace sets `recreate`, which kills and rebuilds it.
+fn run_workspace_session(
+    tmux: &dyn TmuxBackend,
+    config: &Config,
+    workspace: &Workspace,
+    session_name: &str,
+    sync: bool,
+) -> Result<()> {
+    let exists = tmux.has_session(session_name)?;
+    if exists && workspace.recreate {
+        // Killing the session panout runs in would kill panout with it
+        if tmux.current_session()? == session_name {
+            return Err(PanoutError::InvalidConfig(format!(
+                "can't recreate session {} from inside it",
+                session_name
+            )));
+        }
+        tmux.kill_session(session_name)?;
+    }
+    let fresh = !exists || workspace.recreate;
+    if fresh {
+        tmux.new_session(session_name)?;
+    }
+
+    // A fresh session's first window is empty and can hold the first window
+    tmux.use_session(Some(session_name));
+    let result = run_workspace_windows(tmux, config, workspace, sync, fresh)
+        .and_then(|windows| select_focused_window(tmux, workspace, &windows));
+    tmux.use_session(None);
+    result?;
+
+    run_workspace_shell(tmux, workspace)?;
+    tmux.switch_client(session_name)
+}
+
+/// Select the window that sets `focus = true`, if any.
+///
+/// `windows` holds the index of each of the workspace's windows. Returns
+/// whether a window was selected.
+fn select_focused_window(
+    tmux: &dyn TmuxBackend,
+    workspace: &Workspace,
+    windows: &[u32],
+) -> Result<bool> {
+    match workspace.windows.iter().position(WindowDef::is_focused) {
+        Some(i) => {
+            tmux.select_window(windows[i])?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Run a workspace's `run_shell` commands once every window is set up.
+fn run_workspace_shell(tmux: &dyn TmuxBackend, workspace: &Workspace) -> Result<()> {
+    if let Some(ref run_shell) = workspace.run_shell {
+        for cmd in run_shell.to_vec() {
+            tmux.run_shell(&cmd)?;
+        }
+    }
+    Ok(())
+}
+
+/// Size the main pane of a `main-*` layout from `[defaults]`.
+///
+/// Set on the window before its panes are laid out, since `select-layout`
+/// reads the option when it arranges them.
+fn set_main_pane_size(
+    tmux: &dyn TmuxBackend,
+    window: Option<u32>,
+    layout: Layout,
+    defaults: &Defaults,
+) -> Result<()> {
+    let (option, size) = match layout {
+        Layout::MainVertical => ("main-pane-width", defaults.main_pane_width),
+        Layout::MainHorizontal => ("main-pane-height", defaults.main_pane_height),
+        _ => return Ok(()),
+    };
+    match size {
+        Some(size) => tmux.set_window_option(window, option, &tmux::main_pane_value(size)),
+        None => Ok(()),
+    }
+}
+
+/// Wait `[defaults] settle_ms` before typing into panes, if any were
+/// `created`.
+///
+/// A pane that already existed has a running shell, so a run that only uses
+/// the current pane doesn't wait.
+fn settle(tmux: &dyn TmuxBackend, defaults: &Defaults, created: bool) -> Result<()> {
+    match defaults.settle_ms {
+        Some(ms) if created && ms > 0 => tmux.settle(ms),
+        _ => Ok(()),
+    }
+}
+
+/// Split sizes for a workspace window, from `sizes` or per-pane `size`.
+fn window_splits(win: &WindowDef) -> Result<Option<Vec<Option<PaneSize>>>> {
+    if let Some(ref weights) = win.sizes {
+        let splits = tmux::split_percents(weights)
+            .into_iter()
+            .map(|p| Some(PaneSize::Percent(p as u8)))
+            .collect();
+        return Ok(Some(splits));
+    }
+    win.pane_sizes()
+        .map(|sizes| tmux::split_sizes(&sizes))
+        .transpose()
+}
+
+/// Create all windows defined in a workspace.
+///
+/// With `sync`, every window mirrors input across its panes, as if each had
+/// `synchronize = true`. `config` supplies the
+/// [workspace shell](Config::workspace_shell) and the `[defaults]` layout.
+/// With `use_current`, the first window is built in the current window
+/// instead of a new one. Returns the index of each window, in order.
+fn run_workspace_windows(
+    tmux: &dyn TmuxBackend,
+    config: &Config,
+    workspace: &Workspace,
+    sync: bool,
+    use_current: bool,
+) -> Result<Vec<u32>> {
+    let shell = config.workspace_shell(workspace);
+    let mut windows = Vec::new();
+    for (i, win) in workspace.windows.iter().enumerate() {
+        let mut window = None;
+        if i > 0 || !use_current {
+            window = Some(tmux.new_window(win.name.as_deref())?);
+        } else if let Some(ref name) = win.name {
+            // The first window already exists; only rename it when asked to
+            tmux.rename_window(name)?;
+        }
+        windows.push(match window {
+            Some(index) => index,
+            None => tmux.current_window()?,
+        });
+
+        let pane_indices = match window_splits(win)? {
+            Some(splits) => tmux.create_sized_panes(window, &splits, &[], win.split_direction)?,
+            None => {
+                let layout = workspace.window_layout(win, &config.defaults);
+                set_main_pane_size(tmux, window, layout, &config.defaults)?;
+                tmux.create_panes(window, win.pane_count(), layout, &[], win.split_direction)?
+            }
+        };
+        if let Some(custom) = workspace.window_layout_string(win, &config.defaults) {
+            tmux.select_custom_layout(window, custom)?;
+        }
+        for (i, &pane) in pane_indices.iter().enumerate() {
+            if let Some(title) = win.pane_title(i) {
+                tmux.set_pane_title(pane, title)?;
+            }
+        }
+
+        settle(
+            tmux,
+            &config.defaults,
+            window.is_some() || pane_indices.len() > 1,
+        )?;
+        for (i, &pane) in pane_indices.iter().enumerate() {
+            let target = workspace.pane_target(config, win, i)?;
+            for cmd in session::workspace_pane_commands(workspace, win, i, target.as_ref(), shell) {
+                send_command(tmux, pane, &cmd)?;
+            }
+        }
+
+        if sync || win.synchronize {
+            tmux.set_window_option(window, "synchronize-panes", "on")?;
+        }
+
+        if let Some(focus) = win.focused_pane() {
+            tmux.select_pane(tmux::logical_pane(&pane_indices, focus)?)?;
+        }
+    }
+
+    Ok(windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::config::{SplitDir, editor_command};
+    use crate::tmux::{MockTmux, TmuxCall};
+    use clap::Parser;
+    use std::str::FromStr;
+
+    fn opts(args: &[&str]) -> RunOptions {
+        Cli::try_parse_from(std::iter::once("panout").chain(args.iter().copied()))
+            .unwrap()
+            .run_options()
+    }
+
+    fn split_sizes(calls: &[TmuxCall]) -> Vec<Option<PaneSize>> {
+        calls
+            .iter()
+            .filter_map(|c| match c {
+                TmuxCall::SplitWindow { size, .. } => Some(*size),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn config(toml: &str) -> Config {
+        Config::from_str(toml).unwrap()
+    }
+
+    const DEV: &str = r#"
+        [dev]
+        all = { cmd = ["@dev.web", "@dev.api"] }
+        web = { cmd = "npm run dev", pane = 0 }
+        api = { cmd = "cargo run", pane = 1 }
+    "#;
+
+    #[test]
+    fn test_run_bundle_sends_commands_to_each_pane() {
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts(&["dev.all"])).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![(0, "npm run dev".into()), (1, "cargo run".into())]
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_uses_actual_pane_indices() {
+        let tmux = MockTmux::new().with_pane_base_index(1);
+        let opts = opts(&["dev.all", "--focus", "1"]);
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![(1, "npm run dev".into()), (2, "cargo run".into())]
+        );
+        assert_eq!(tmux.calls().last(), Some(&TmuxCall::SelectPane(2)));
+    }
+
+    #[test]
+    fn test_run_bundle_explicit_panes_with_base_index_one() {
+        let config = config(
+            r#"
+            [dev]
+            all = { cmd = ["@dev.logs", "@dev.shell"] }
+            logs = { cmd = "tail -f log", pane = 2 }
+            shell = { cmd = "htop", pane = 1 }
+            "#,
+        );
+        let tmux = MockTmux::new().with_pane_base_index(1);
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all"])).unwrap();
+
+        // Logical panes 2 and 1 are actual panes 3 and 2; nothing lands on 1
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![(3, "tail -f log".into()), (2, "htop".into())]
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_by_alias() {
+        let config = config(&format!(
+            "{}\n[aliases]\nd = \"dev.all\"\n\n[ops.up]\ncmd = \"@d\"\n",
+            DEV
+        ));
+        for target in ["d", "ops.up"] {
+            let tmux = MockTmux::new();
+            run_bundle_with(&tmux, &config, target, &opts(&[target])).unwrap();
+            assert_eq!(
+                tmux.sent_keys(),
+                vec![(0, "npm run dev".into()), (1, "cargo run".into())]
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_bundle_num_is_a_floor() {
+        let config = config(
+            r#"
+            [dev]
+            logs = { cmd = "tail -f log", pane = 3 }
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.logs", &opts(&["dev.logs", "-n", "1"])).unwrap();
+
+        // Three splits of the current pane make 4 panes
+        assert_eq!(split_sizes(&tmux.calls()).len(), 3);
+        assert_eq!(tmux.sent_keys(), vec![(3, "tail -f log".into())]);
+    }
+
+    #[test]
+    fn test_run_workspace_focus_with_base_index_one() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = 3
+            cmd = "ls"
+            focus = 0
+            "#,
+        );
+        let tmux = MockTmux::new().with_pane_base_index(1);
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let panes: Vec<u32> = tmux.sent_keys().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(panes, vec![1, 2, 3]);
+        assert!(tmux.calls().contains(&TmuxCall::SelectPane(1)));
+    }
+
+    #[test]
+    fn test_run_bundle_in_new_window() {
+        let tmux = MockTmux::new();
+        let opts = opts(&["dev.all", "--new-window"]);
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(
+            calls[0],
+            TmuxCall::NewWindow {
+                name: Some("dev.all".into())
+            }
+        );
+        assert_eq!(
+            calls[1],
+            TmuxCall::SplitWindow {
+                window: Some(1),
+                start_dir: None,
+                size: None,
+                direction: None
+            }
+        );
+        assert_eq!(calls.last(), Some(&TmuxCall::SelectWindow(0)));
+    }
+
+    #[test]
+    fn test_run_bundle_in_named_new_window_and_stay() {
+        let tmux = MockTmux::new();
+        let opts = opts(&["dev.all", "--new-window=web", "--stay"]);
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(
+            calls[0],
+            TmuxCall::NewWindow {
+                name: Some("web".into())
+            }
+        );
+        assert!(!calls.iter().any(|c| matches!(c, TmuxCall::SelectWindow(_))));
+    }
+
+    #[test]
+    fn test_run_bundle_layout_option_wins() {
+        let config = config(&format!("{}\n[defaults]\nlayout = \"horizontal\"\n", DEV));
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all", "-v"])).unwrap();
+
+        assert!(
+            tmux.calls()
+                .contains(&TmuxCall::SelectLayout(Layout::Vertical))
+        );
+        assert!(
+            !tmux
+                .calls()
+                .contains(&TmuxCall::SelectLayout(Layout::Horizontal))
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_applies_layout_string_last() {
+        let custom = "5e3b,191x50,0,0{95x50,0,0,1,95x50,96,0,2}";
+        let config = config(&format!(
+            "{}\n[dev.pair]\ncmd = [\"@dev.web\", \"@dev.api\"]\nlayout_string = \"{}\"\n",
+            DEV, custom
+        ));
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.pair", &RunOptions::default()).unwrap();
+
+        let calls = tmux.calls();
+        let layouts: Vec<_> = calls
+            .iter()
+            .filter(|c| matches!(c, TmuxCall::SelectCustomLayout(_)))
+            .collect();
+        assert_eq!(layouts, vec![&TmuxCall::SelectCustomLayout(custom.into())]);
+        let applied = calls.iter().position(|c| *c == *layouts[0]).unwrap();
+        let last_split = calls
+            .iter()
+            .rposition(|c| matches!(c, TmuxCall::SplitWindow { .. }))
+            .unwrap();
+        assert!(applied > last_split);
+
+        // A layout flag wins over the string
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.pair", &opts(&["dev.pair", "-v"])).unwrap();
+        assert!(
+            !tmux
+                .calls()
+                .iter()
+                .any(|c| matches!(c, TmuxCall::SelectCustomLayout(_)))
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_layout_string_precedence() {
+        let custom = |tmux: &MockTmux| -> Vec<TmuxCall> {
+            tmux.calls()
+                .into_iter()
+                .filter(|c| matches!(c, TmuxCall::SelectCustomLayout(_)))
+                .collect()
+        };
+        let config = config(&format!(
+            "{}\n[dev.pair]\ncmd = [\"@dev.web\", \"@dev.api\"]\nlayout_string = \"own\"\n\
+             [defaults]\nlayout_string = \"fallback\"\n",
+            DEV
+        ));
+
+        // The bundle's own string wins over the default
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.pair", &RunOptions::default()).unwrap();
+        assert_eq!(
+            custom(&tmux),
+            vec![TmuxCall::SelectCustomLayout("own".into())]
+        );
+
+        // Bundles without one get the default
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all"])).unwrap();
+        assert_eq!(
+            custom(&tmux),
+            vec![TmuxCall::SelectCustomLayout("fallback".into())]
+        );
+
+        // A layout flag turns both off
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all", "-H"])).unwrap();
+        assert!(custom(&tmux).is_empty());
+    }
+
+    #[test]
+    fn test_run_bundle_sizes_main_pane() {
+        let config = config(&format!("{}\n[defaults]\nmain_pane_width = \"60%\"\n", DEV));
+        let tmux = MockTmux::new();
+        let main = opts(&["dev.all", "--layout", "main-vertical"]);
+        run_bundle_with(&tmux, &config, "dev.all", &main).unwrap();
+
+        let calls = tmux.calls();
+        let option = TmuxCall::SetWindowOption {
+            window: None,
+            name: "main-pane-width".into(),
+            value: "60%".into(),
+        };
+        let set = calls.iter().position(|c| *c == option).unwrap();
+        let layout = TmuxCall::SelectLayout(Layout::MainVertical);
+        assert!(set < calls.iter().position(|c| *c == layout).unwrap());
+
+        // Other layouts leave the option alone
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all", "-v"])).unwrap();
+        assert!(
+            !tmux
+                .calls()
+                .iter()
+                .any(|c| matches!(c, TmuxCall::SetWindowOption { .. }))
+        );
+    }
+
+    #[test]
+    fn test_run_size_threshold() {
+        let size = RunSize {
+            panes: 8,
+            windows: 2,
+        };
+        assert!(!size.needs_confirmation(None));
+        assert!(!size.needs_confirmation(Some(8)));
+        assert!(size.needs_confirmation(Some(7)));
+    }
+
+    #[test]
+    fn test_bundle_and_workspace_size() {
+        let config = config(&format!(
+            "{}\n[workspace.ws]\nwindows = [{{ panes = 3 }}, {{ panes = 2, name = \"b\" }}]\n",
+            DEV
+        ));
+
+        let bundle = bundle_size(&config, "dev.all", &opts(&["dev.all"])).unwrap();
+        assert_eq!(
+            bundle,
+            RunSize {
+                panes: 2,
+                windows: 1
+            }
+        );
+        let floor = bundle_size(&config, "dev.all", &opts(&["dev.all", "-n", "5"])).unwrap();
+        assert_eq!(floor.panes, 5);
+
+        let all = workspace_size(&config, "ws", &opts(&["ws"])).unwrap();
+        assert_eq!(
+            all,
+            RunSize {
+                panes: 5,
+                windows: 2
+            }
+        );
+        let some = workspace_size(&config, "ws", &opts(&["ws", "--windows", "b"])).unwrap();
+        assert_eq!(
+            some,
+            RunSize {
+                panes: 2,
+                windows: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_edit_quotes_file() {
+        let config = config(
+            r#"
+            [dev.notes]
+            cmd = { edit = "my notes.md" }
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.notes", &opts(&["dev.notes"])).unwrap();
+
+        let editor = std::env::var("EDITOR").ok();
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![(0, editor_command("my notes.md", editor.as_deref()))]
+        );
+        assert!(tmux.sent_keys()[0].1.ends_with(" 'my notes.md'"));
+    }
+
+    #[test]
+    fn test_run_bundle_sends_key_sequences() {
+        let config = config(
+            r#"
+            [reset.interrupt]
+            cmd = { keys = ["C-c", "C-l"] }
+
+            [dev.build]
+            cmd = ["@reset.interrupt", "make"]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.build", &opts(&["dev.build"])).unwrap();
+
+        let sent: Vec<_> = tmux
+            .calls()
+            .into_iter()
+            .filter(|c| {
+                matches!(
+                    c,
+                    TmuxCall::SendKeys { .. } | TmuxCall::SendKeySequence { .. }
+                )
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                TmuxCall::SendKeySequence {
+                    pane: 0,
+                    keys: vec!["C-c".into(), "C-l".into()],
+                },
+                TmuxCall::SendKeys {
+                    pane: 0,
+                    command: "make".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_broadcasts_to_every_pane() {
+        let tmux = MockTmux::new();
+        let opts = opts(&["dev.all", "-n", "3", "--broadcast", "source .envrc"]);
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts).unwrap();
+
+        let sent = tmux.sent_keys();
+        assert_eq!(
+            sent.iter()
+                .filter(|(_, cmd)| cmd == "source .envrc")
+                .count(),
+            3
+        );
+        // Sent once per pane, after the bundle's own commands
+        assert!(sent.ends_with(&[
+            (0, "source .envrc".into()),
+            (1, "source .envrc".into()),
+            (2, "source .envrc".into()),
+        ]));
+    }
+
+    #[test]
+    fn test_run_bundle_sets_pane_titles() {
+        let config = config(&DEV.replace("pane = 1", "pane = 1, title = \"api\""));
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all"])).unwrap();
+
+        assert!(tmux.calls().contains(&TmuxCall::SetPaneTitle {
+            pane: 1,
+            title: "api".into()
+        }));
+    }
+
+    #[test]
+    fn test_run_server_outside_tmux() {
+        let config = config(
+            r#"
+            [servers.box]
+            host = "me@10.0.0.1"
+            "#,
+        );
+        let tmux = MockTmux::new().outside_tmux();
+        assert!(matches!(
+            run_server_with(&tmux, &config, "box"),
+            Err(PanoutError::NotInTmux)
+        ));
+    }
+
+    #[test]
+    fn test_run_server_without_panes_is_an_error() {
+        let config = config(
+            r#"
+            [servers.box]
+            host = "me@10.0.0.1"
+            "#,
+        );
+        let tmux = MockTmux::new().without_panes();
+        match run_server_with(&tmux, &config, "box") {
+            Err(PanoutError::TmuxError(msg)) => {
+                assert_eq!(msg, "no panes in the current window")
+            }
+            other => panic!("expected TmuxError, got {:?}", other),
+        }
+        assert!(tmux.sent_keys().is_empty());
+    }
+
+    #[test]
+    fn test_run_workspace_creates_windows_and_returns() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            name = "edit"
+            panes = 1
+            cmd = "vim"
+            [[workspace.ws.windows]]
+            name = "logs"
+            panes = 2
+            cmd = "tail -f log"
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(calls[0], TmuxCall::RenameWindow("edit".into()));
+        assert!(calls.contains(&TmuxCall::NewWindow {
+            name: Some("logs".into())
+        }));
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![
+                (0, "vim".into()),
+                (0, "tail -f log".into()),
+                (1, "tail -f log".into())
+            ]
+        );
+        assert_eq!(
+            calls[calls.len() - 2..],
+            [TmuxCall::SelectWindow(0), TmuxCall::SelectPane(0)]
+        );
+    }
+
+    #[test]
+    fn test_run_bundle_sync_after_commands() {
+        let tmux = MockTmux::new();
+        run_bundle_with(
+            &tmux,
+            &config(DEV),
+            "dev.all",
+            &opts(&["dev.all", "--sync"]),
+        )
+        .unwrap();
+
+        let calls = tmux.calls();
+        let sync = TmuxCall::SetWindowOption {
+            window: None,
+            name: "synchronize-panes".into(),
+            value: "on".into(),
+        };
+        let sync_at = calls.iter().position(|c| *c == sync).unwrap();
+        let last_send = calls
+            .iter()
+            .rposition(|c| matches!(c, TmuxCall::SendKeys { .. }))
+            .unwrap();
+        assert!(sync_at > last_send);
+    }
+
+    #[test]
+    fn test_run_workspace_synchronizes_configured_window() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = 1
+            cmd = "vim"
+            [[workspace.ws.windows]]
+            panes = 3
+            cmd = "htop"
+            synchronize = true
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let synced: Vec<_> = tmux
+            .calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                TmuxCall::SetWindowOption { window, .. } => Some(window),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(synced, vec![Some(1)]);
+    }
+
+    #[test]
+    fn test_run_bundle_dir_must_exist() {
+        let tmp = tempfile::tempdir().unwrap();
+        let missing = tmp.path().join("missing").display().to_string();
+        let tmux = MockTmux::new();
+        let opts = opts(&["dev.all", "--dir", &missing]);
+
+        match run_bundle_with(&tmux, &config(DEV), "dev.all", &opts) {
+            Err(PanoutError::DirNotFound(dir)) => assert_eq!(dir, missing),
+            other => panic!("expected DirNotFound, got {:?}", other),
+        }
+        assert!(tmux.calls().is_empty());
+    }
+
+    #[test]
+    fn test_run_bundle_dir_starts_panes_there() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().display().to_string();
+        let tmux = MockTmux::new();
+        let opts = opts(&["dev.all", "--dir", &dir]);
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts).unwrap();
+
+        assert!(tmux.calls().contains(&TmuxCall::SplitWindow {
+            window: None,
+            start_dir: Some(dir.clone()),
+            size: None,
+            direction: None
+        }));
+        assert_eq!(tmux.sent_keys()[0], (0, format!("cd {}", dir)));
+    }
+
+    #[test]
+    fn test_run_bundle_dir_with_space_is_quoted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("My Projects");
+        std::fs::create_dir(&dir).unwrap();
+        let dir = dir.display().to_string();
+        let tmux = MockTmux::new();
+        let opts = opts(&["dev.all", "--dir", &dir]);
+        run_bundle_with(&tmux, &config(DEV), "dev.all", &opts).unwrap();
+
+        assert_eq!(tmux.sent_keys()[0], (0, format!("cd '{}'", dir)));
+    }
+
+    #[test]
+    fn test_run_bundle_panes_start_in_their_bundle_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let web = tmp.path().join("web");
+        let api = tmp.path().join("api");
+        std::fs::create_dir(&web).unwrap();
+        std::fs::create_dir(&api).unwrap();
+        let config = config(&format!(
+            r#"
+            [dev]
+            all = {{ cmd = ["@dev.web", "@dev.api"] }}
+            web = {{ cmd = "npm run dev", pane = 0, dir = "{}" }}
+            api = {{ cmd = "cargo run", pane = 1, dir = "{}" }}
+            "#,
+            web.display(),
+            api.display()
+        ));
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.all", &opts(&["dev.all"])).unwrap();
+
+        assert!(tmux.calls().contains(&TmuxCall::SplitWindow {
+            window: None,
+            start_dir: Some(api.display().to_string()),
+            size: None,
+            direction: None
+        }));
+        assert_eq!(tmux.sent_keys()[0], (0, format!("cd {}", web.display())));
+
+        // `--dir` wins over every bundle's own
+        let dir = tmp.path().display().to_string();
+        let tmux = MockTmux::new();
+        run_bundle_with(
+            &tmux,
+            &config,
+            "dev.all",
+            &opts(&["dev.all", "--dir", &dir]),
+        )
+        .unwrap();
+        assert!(tmux.calls().contains(&TmuxCall::SplitWindow {
+            window: None,
+            start_dir: Some(dir.clone()),
+            size: None,
+            direction: None
+        }));
+    }
+
+    #[test]
+    fn test_run_tag_fills_one_pane_per_bundle() {
+        let config = config(
+            r#"
+            [dev]
+            web = { cmd = "npm run dev", tags = ["work"] }
+            api = { cmd = "cargo run", tags = ["work"] }
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_tag_with(&tmux, &config, "work", &opts(&["--tag", "work"])).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![(0, "cargo run".to_string()), (1, "npm run dev".to_string())]
+        );
+        assert!(matches!(
+            run_tag_with(&MockTmux::new(), &config, "rust", &opts(&["--tag", "rust"])),
+            Err(PanoutError::BundleNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_settles_new_panes_before_sending() {
+        let config = config(
+            r#"
+            [defaults]
+            settle_ms = 150
+
+            [dev]
+            pair = { cmd = ["@dev.one", "@dev.two"] }
+            one = { cmd = "npm run dev", pane = 0 }
+            two = { cmd = "cargo run", pane = 1 }
+            solo = { cmd = "htop" }
+
+            [workspace.ws]
+            windows = [{ panes = 2, cmd = "ls" }]
+            "#,
+        );
+        let position = |calls: &[TmuxCall], f: fn(&TmuxCall) -> bool| calls.iter().position(f);
+        let is_settle = |c: &TmuxCall| matches!(c, TmuxCall::Settle(150));
+        let is_split = |c: &TmuxCall| matches!(c, TmuxCall::SplitWindow { .. });
+        let is_send = |c: &TmuxCall| matches!(c, TmuxCall::SendKeys { .. });
+
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.pair", &opts(&["dev.pair"])).unwrap();
+        let calls = tmux.calls();
+        let settle = position(&calls, is_settle).unwrap();
+        assert!(position(&calls, is_split).unwrap() < settle);
+        assert!(settle < position(&calls, is_send).unwrap());
+
+        // Nothing new to wait for in the current pane
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.solo", &opts(&["dev.solo"])).unwrap();
+        assert_eq!(position(&tmux.calls(), is_settle), None);
+
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+        let calls = tmux.calls();
+        assert!(position(&calls, is_settle).unwrap() < position(&calls, is_send).unwrap());
+    }
+
+    #[test]
+    fn test_run_workspace_window_sizes() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = 3
+            sizes = [60, 20, 20]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(
+            split_sizes(&calls),
+            vec![Some(PaneSize::Percent(40)), Some(PaneSize::Percent(50))]
+        );
+        assert!(!calls.iter().any(|c| matches!(c, TmuxCall::SelectLayout(_))));
+    }
+
+    #[test]
+    fn test_run_workspace_pane_cells() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = [{ cmd = "nvim ." }, { cmd = "htop", size = { cells = 10 } }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert_eq!(split_sizes(&tmux.calls()), vec![Some(PaneSize::Cells(10))]);
+    }
+
+    #[test]
+    fn test_run_bundle_layout_flag_ignores_sizes() {
+        let config = config(
+            r#"
+            [dev.editor]
+            cmd = "nvim ."
+            size = "70%"
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_bundle_with(
+            &tmux,
+            &config,
+            "dev.editor",
+            &opts(&["dev.editor", "-n", "2", "-v"]),
+        )
+        .unwrap();
+
+        assert_eq!(split_sizes(&tmux.calls()), vec![None]);
+        assert!(
+            tmux.calls()
+                .contains(&TmuxCall::SelectLayout(Layout::Vertical))
+        );
+    }
+
+    #[test]
+    fn test_run_workspace_derives_window_names() {
+        let ws = r#"
+            [workspace.ws]
+            windows = [{ panes = 2, cmd = "@dev.all" }, { panes = 1, cmd = "htop" }]
+        "#;
+        let config = config(&format!("{}{}", DEV, ws));
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(calls[0], TmuxCall::RenameWindow("all".into()));
+        assert!(calls.contains(&TmuxCall::NewWindow {
+            name: Some("htop".into())
+        }));
+    }
+
+    #[test]
+    fn test_run_workspace_per_platform_cmd_without_default() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 1 }, { panes = [{ cmd = { windows = "dir" } }] }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        let result = run_workspace_with(&tmux, &config, "ws", &opts(&["-w", "ws"]));
+
+        assert!(matches!(
+            result,
+            Err(PanoutError::InvalidConfig(msg)) if msg.starts_with("workspace ws window 1 has no")
+        ));
+        assert!(tmux.calls().is_empty());
+    }
+
+    #[test]
+    fn test_run_workspace_host_tokens_need_user() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            windows = [
+                { panes = 1, host = "deploy@app", cmd = "echo {user}" },
+                { panes = 1, host = "app", dir = "/home/{user}" },
+            ]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        let result = run_workspace_with(&tmux, &config, "ws", &opts(&["-w", "ws"]));
+
+        assert!(matches!(
+            result,
+            Err(PanoutError::InvalidConfig(msg))
+                if msg.starts_with("workspace ws window 1 pane 0 uses {user}/{host}")
+        ));
+        assert!(tmux.calls().is_empty());
+    }
+
+    #[test]
+    fn test_run_workspace_hosts_by_server_name() {
+        let config = config(
+            r#"
+            [servers.web1]
+            host = "ops@10.0.0.1"
+
+            [workspace.ws]
+            windows = [{ hosts = ["web1", "ops@10.0.0.2"], cmd = "uptime", synchronize = true }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![
+                (0, "ssh ops@10.0.0.1".into()),
+                (0, "uptime".into()),
+                (1, "ssh ops@10.0.0.2".into()),
+                (1, "uptime".into()),
+            ]
+        );
+        assert!(tmux.calls().contains(&TmuxCall::SetWindowOption {
+            window: None,
+            name: "synchronize-panes".into(),
+            value: "on".into(),
+        }));
+    }
+
+    #[test]
+    fn test_run_workspace_host_from_server() {
+        let config = config(
+            r#"
+            [servers.prod]
+            host = "admin@10.0.0.5"
+            port = 2222
+
+            [workspace.ws]
+            host = "@servers.prod"
+            disconnect = true
+            windows = [{ panes = 1, cmd = "uptime" }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys()[0],
+            (0, "ssh -p 2222 admin@10.0.0.5".into())
+        );
+    }
+
+    #[test]
+    fn test_run_workspace_ipv6_server_interpolates_user() {
+        let config = config(
+            r#"
+            [servers.lab]
+            host = "root@[::1]"
+            port = 2222
+
+            [workspace.ws]
+            host = "@servers.lab"
+            dir = "/home/{user}"
+            windows = [{ panes = 1, host = "@servers.lab", cmd = "echo {user}@{host}" }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![
+                (
+                    0,
+                    "ssh -p 2222 -t 'root@[::1]' 'cd /home/root && exec $SHELL -l'".into()
+                ),
+                (0, "echo root@::1".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_workspace_restores_active_pane() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 3 }, { panes = 2 }]
+            "#,
+        );
+        let tmux = MockTmux::new().with_pane_base_index(1);
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(
+            calls[calls.len() - 2..],
+            [TmuxCall::SelectWindow(0), TmuxCall::SelectPane(1)]
+        );
+    }
+
+    #[test]
+    fn test_run_workspace_first_window_focus_wins() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 3, focus = 2 }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert_eq!(tmux.calls().last(), Some(&TmuxCall::SelectWindow(0)));
+        assert!(tmux.calls().contains(&TmuxCall::SelectPane(2)));
+    }
+
+    #[test]
+    fn test_run_workspace_ends_on_focused_window() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 1 }, { panes = 2, focus = true, focus_pane = 1 }, { panes = 1 }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert!(calls.contains(&TmuxCall::SelectPane(1)));
+        assert_eq!(calls.last(), Some(&TmuxCall::SelectWindow(1)));
+    }
+
+    #[test]
+    fn test_run_workspace_stay_ends_on_last_window() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            stay = true
+            windows = [{ panes = 1 }, { panes = 1 }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert!(!calls.iter().any(|c| matches!(c, TmuxCall::SelectWindow(_))));
+        assert_eq!(tmux.current_window().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_run_workspace_window_split_direction() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = 2
+            split_direction = "vertical"
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert!(tmux.calls().contains(&TmuxCall::SplitWindow {
+            window: None,
+            start_dir: None,
+            size: None,
+            direction: Some(SplitDir::Vertical),
+        }));
+    }
+
+    #[test]
+    fn test_run_workspace_layout_precedence() {
+        let cases = [
+            // window > workspace > defaults > tiled
+            (
+                "layout = \"vertical\"",
+                "horizontal",
+                "horizontal",
+                Layout::Vertical,
+            ),
+            ("", "horizontal", "vertical", Layout::Horizontal),
+            ("", "", "vertical", Layout::Vertical),
+            ("", "", "", Layout::Tiled),
+        ];
+        for (window, workspace, defaults, expected) in cases {
+            let mut toml = String::new();
+            if !defaults.is_empty() {
+                toml.push_str(&format!("[defaults]\nlayout = \"{}\"\n", defaults));
+            }
+            toml.push_str("[workspace.ws]\n");
+            if !workspace.is_empty() {
+                toml.push_str(&format!("layout = \"{}\"\n", workspace));
+            }
+            toml.push_str(&format!(
+                "[[workspace.ws.windows]]\npanes = 2\n{}\n",
+                window
+            ));
+
+            let tmux = MockTmux::new();
+            run_workspace_with(&tmux, &config(&toml), "ws", &opts(&["ws"])).unwrap();
+            assert!(
+                tmux.calls().contains(&TmuxCall::SelectLayout(expected)),
+                "{:?}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_workspace_pre_failure_aborts() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            pre = "exit 3"
+            windows = [{ panes = 2 }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        let err = run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap_err();
+
+        assert!(matches!(
+            err,
+            PanoutError::HookFailed { ref status, .. } if status == "exit code 3"
+        ));
+        assert!(tmux.calls().is_empty());
+    }
+
+    const SESSION_WS: &str = r#"
+        [workspace.ws]
+        session = "api"
+        [[workspace.ws.windows]]
+        name = "edit"
+        panes = 1
+        [[workspace.ws.windows]]
+        name = "logs"
+        panes = 1
+    "#;
+
+    #[test]
+    fn test_run_workspace_new_session() {
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config(SESSION_WS), "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(calls[0], TmuxCall::NewSession("api".into()));
+        assert_eq!(calls[1], TmuxCall::UseSession(Some("api".into())));
+        assert_eq!(calls[2], TmuxCall::RenameWindow("edit".into()));
+        assert!(calls.contains(&TmuxCall::UseSession(None)));
+        assert_eq!(calls.last(), Some(&TmuxCall::SwitchClient("api".into())));
+    }
+
+    #[test]
+    fn test_run_workspace_session_selects_focused_window() {
+        let ws = SESSION_WS.replace("name = \"edit\"", "name = \"edit\"\nfocus = true");
+        let config = config(&ws);
+        let tmux = MockTmux::new();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        let select = calls
+            .iter()
+            .position(|c| *c == TmuxCall::SelectWindow(0))
+            .unwrap();
+        let release = calls
+            .iter()
+            .position(|c| *c == TmuxCall::UseSession(None))
+            .unwrap();
+        assert!(select < release);
+    }
+
+    #[test]
+    fn test_run_workspace_reuses_existing_session() {
+        let tmux = MockTmux::new().with_session("api");
+        run_workspace_with(&tmux, &config(SESSION_WS), "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert!(!calls.iter().any(|c| matches!(c, TmuxCall::NewSession(_))));
+        let new_windows = calls
+            .iter()
+            .filter(|c| matches!(c, TmuxCall::NewWindow { .. }))
+            .count();
+        assert_eq!(new_windows, 2);
+    }
+
+    #[test]
+    fn test_run_workspace_recreates_session() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            session = "api"
+            recreate = true
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        let tmux = MockTmux::new().with_session("api");
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        let calls = tmux.calls();
+        assert_eq!(calls[0], TmuxCall::KillSession("api".into()));
+        assert_eq!(calls[1], TmuxCall::NewSession("api".into()));
+    }
+
+    #[test]
+    fn test_run_workspace_refuses_to_recreate_current_session() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            session = "0"
+            recreate = true
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        let err = run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap_err();
+
+        assert!(matches!(err, PanoutError::InvalidConfig(_)));
+        assert!(tmux.calls().is_empty());
+    }
+
+    #[test]
+    fn test_run_post_failure_only_warns() {
+        let config = config(
+            r#"
+            [dev.web]
+            cmd = "npm run dev"
+            post = ["false"]
+
+            [workspace.ws]
+            post = "false"
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        let tmux = MockTmux::new();
+        run_bundle_with(&tmux, &config, "dev.web", &opts(&["dev.web"])).unwrap();
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+    }
+
+    #[test]
+    fn test_run_workspace_pane_list() {
+        let config = config(
+            r#"
+            [workspace.ws]
+            [[workspace.ws.windows]]
+            panes = [{ cmd = "nvim .", title = "edit" }, { cmd = "htop" }]
+            "#,
+        );
+        let tmux = MockTmux::new().with_pane_base_index(1);
+        run_workspace_with(&tmux, &config, "ws", &opts(&["ws"])).unwrap();
+
+        assert_eq!(
+            tmux.sent_keys(),
+            vec![(1, "nvim .".into()), (2, "htop".into())]
+        );
+        assert!(tmux.calls().contains(&TmuxCall::SetPaneTitle {
+            pane: 1,
+            title: "edit".into()
+        }));
+    }
+}
@@ -1,8 +1,16 @@
 //! Session name sanitization and remote SSH/tmux command builder.
 //!
 //! Provides utilities for creating valid tmux session names from
-//! workspace and bundle names, and constructing SSH commands that
-//! create or attach to remote tmux sessions.
+//! workspace and bundle names, constructing SSH commands that
+//! create or attach to remote tmux sessions, and building the
+//! per-pane command list for workspace windows.
+
+use crate::config::{Cmd, NO_SHELL, WindowDef, Workspace};
+use crate::interpolate;
+use crate::path;
+use crate::resolver::{self, ResolvedRef};
+use crate::shell;
+use crate::ssh::{self, SshTarget};
 
 /// Sanitize a workspace or bundle name for use as a tmux session name.
 ///
@@ -22,6 +30,34 @@ pub fn sanitize_session_name(name: &str) -> String {
     sanitized.trim_matches('-').to_string()
 }
 
+/// Name for a workspace window that doesn't set `name`.
+///
+/// A window whose `cmd` is a single `@group.name` reference is named after
+/// the bundle (`name`); otherwise the first word of its first command is
+/// used, without any leading path (`/usr/bin/htop` gives `htop`). The result
+/// is sanitized like a [session name](sanitize_session_name); `None` if
+/// nothing usable is left.
+pub fn derive_window_name(win: &WindowDef) -> Option<String> {
+    let name = match win.cmd {
+        Some(Cmd::Single(ref cmd)) => match resolver::parse_ref(cmd) {
+            ResolvedRef::BundleRef { name, .. } => Some(name),
+            _ => None,
+        },
+        _ => None,
+    };
+    let name = name.or_else(|| {
+        let cmds = win.commands_for_pane(0);
+        let first = cmds
+            .iter()
+            .find(|cmd| matches!(resolver::parse_ref(cmd), ResolvedRef::Command(_)))?;
+        let word = first.split_whitespace().next()?;
+        Some(word.rsplit('/').next().unwrap_or(word).to_string())
+    })?;
+
+    let name = sanitize_session_name(&name);
+    (!name.is_empty()).then_some(name)
+}
+
 /// Build the SSH command string for creating/attaching a remote tmux session.
 ///
 /// Uses `-t` for TTY allocation (SESS-04) and `new-session -A` for
@@ -29,27 +65,171 @@ pub fn sanitize_session_name(name: &str) -> String {
 /// `sanitize_session_name` internally to ensure the session name is
 /// tmux-compatible.
 ///
-/// With `dir`: `ssh -t {host} "cd {dir} && tmux new-session -A -s {name}"`
-/// Without `dir`: `ssh -t {host} "tmux new-session -A -s {name}"`
+/// With `dir`: `ssh -t {host} 'cd {dir} && tmux new-session -A -s {name}'`
+/// Without `dir`: `ssh -t {host} 'tmux new-session -A -s {name}'`
+///
+/// `options` (such as [`Workspace::ssh_options`]) go right after `ssh`. The
+/// host and `dir` are shell-quoted when needed (`'root@[::1]'`,
+/// `~/'My Projects'`), and the remote command is quoted as a whole so the
+/// local shell passes it through untouched.
 pub fn build_remote_session_cmd(
     host: &str,
     session_name: &str,
     dir: Option<&str>,
+    options: &[String],
 ) -> String {
     let safe_name = sanitize_session_name(session_name);
-    match dir {
-        Some(d) => format!(
-            "ssh -t {host} \"cd {d} && tmux new-session -A -s {safe_name}\""
-        ),
-        None => format!(
-            "ssh -t {host} \"tmux new-session -A -s {safe_name}\""
-        ),
+    let tmux = format!("tmux new-session -A -s {}", safe_name);
+    let remote = match dir {
+        Some(d) => format!("cd {} && {}", shell::quote_path(d), tmux),
+        None => tmux,
+    };
+    remote_command(options, host, &remote)
+}
+
+/// Build the command that connects to `host` and starts a shell in `dir`.
+///
+/// `shell` picks what runs after the `cd`:
+///
+/// - `None`: `ssh -t host 'cd dir && exec $SHELL -l'`
+/// - `Some("bash")`: `ssh -t host 'cd dir && exec bash -l'`
+/// - `Some("none")` ([`NO_SHELL`]): `ssh -t host 'cd dir && $SHELL'`
+///
+/// `options` (such as [`Workspace::ssh_options`]) go right after `ssh`, the
+/// host and `dir` are shell-quoted when needed, and the remote command is
+/// quoted as a whole.
+pub fn build_ssh_cd_cmd(host: &str, dir: &str, shell: Option<&str>, options: &[String]) -> String {
+    let dir = shell::quote_path(dir);
+    let remote = match shell {
+        Some(NO_SHELL) => format!("cd {} && $SHELL", dir),
+        Some(shell) => format!("cd {} && exec {} -l", dir, shell),
+        None => format!("cd {} && exec $SHELL -l", dir),
+    };
+    remote_command(options, host, &remote)
+}
+
+/// `ssh -t` to `host`, running `remote` there.
+///
+/// `remote` is quoted once for the local shell, so only the remote shell
+/// expands `$`, backticks, and the like in it.
+fn remote_command(options: &[String], host: &str, remote: &str) -> String {
+    format!(
+        "{} -t {} {}",
+        ssh_command(options),
+        shell::quote(host),
+        shell::quote(remote)
+    )
+}
+
+/// `ssh` followed by `options`, space-separated.
+fn ssh_command(options: &[String]) -> String {
+    std::iter::once("ssh")
+        .chain(options.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The workspace's [SSH options](Workspace::ssh_options) followed by
+/// `target`'s `-p`/`-i`/`-o` flags.
+pub fn target_options(workspace: &Workspace, target: Option<&SshTarget>) -> Vec<String> {
+    let mut options = workspace.ssh_options();
+    options.extend(target.into_iter().flat_map(SshTarget::ssh_options));
+    options
+}
+
+/// Fill in `{user}`/`{host}` in `text` from `host` (`user@host`).
+///
+/// Text for a host without a `user@` part is returned unchanged;
+/// [`Workspace::check_host_tokens`] rejects configs that would rely on it.
+pub fn interpolate_host(text: &str, host: &str) -> String {
+    match interpolate::parse_host(host) {
+        Some((user, host)) => interpolate::interpolate(text, &user, &host),
+        None => text.to_string(),
     }
 }
 
+/// Build the commands sent to each pane of a workspace window.
+///
+/// In order: connect and/or cd (depending on the pane's
+/// [host](Workspace::pane_host) and [directory](Workspace::pane_dir), the
+/// latter with `{user}`/`{host}` filled in),
+/// `export`s for the window's [environment](Workspace::window_env), the
+/// window's own commands for logical pane `pane` (see
+/// [`WindowDef::commands_for_pane`]) with `{user}`/`{host}` filled in from the
+/// pane's host. When the workspace sets `disconnect` and the pane connects to
+/// a host, everything after the connection is sent as one
+/// [chain ending in `exit`](ssh::chain_with_exit). `shell` is passed to
+/// [`build_ssh_cd_cmd`], and every `ssh` gets the workspace's
+/// [SSH options](Workspace::ssh_options) followed by the target's own.
+///
+/// `target` is the pane's resolved host (see [`Workspace::pane_target`]), or
+/// `None` for a local pane.
+pub fn workspace_pane_commands(
+    workspace: &Workspace,
+    win: &WindowDef,
+    pane: usize,
+    target: Option<&SshTarget>,
+    shell: Option<&str>,
+) -> Vec<String> {
+    let mut cmds = Vec::new();
+
+    let host = target.map(|t| t.host.as_str());
+    let options = target_options(workspace, target);
+    match (host, workspace.pane_dir(win, pane)) {
+        // SSH + cd: single command that connects and changes directory
+        (Some(host), Some(dir)) => {
+            let dir = interpolate_host(dir, host);
+            cmds.push(build_ssh_cd_cmd(host, &dir, shell, &options));
+        }
+        // SSH only
+        (Some(host), None) => {
+            cmds.push(format!("{} {}", ssh_command(&options), shell::quote(host)));
+        }
+        // Local cd only; `~` is expanded here, remote dirs keep the remote home
+        (None, Some(dir)) => cmds.push(format!("cd {}", shell::quote(&path::expand_tilde(dir)))),
+        // No host or dir
+        (None, None) => {}
+    }
+
+    cmds.extend(shell::export_commands(&workspace.window_env(win)));
+
+    // Window-specific commands, with `{user}`/`{host}` from the pane's host
+    let window_cmds = win.commands_for_pane(pane);
+    match host {
+        Some(host) => cmds.extend(window_cmds.iter().map(|c| interpolate_host(c, host))),
+        None => cmds.extend(window_cmds),
+    }
+
+    // The connection is the first command; the remote shell runs the rest
+    if host.is_some() && workspace.disconnect.unwrap_or(false) {
+        let remote = cmds.split_off(1);
+        cmds.push(ssh::chain_with_exit(&remote));
+    }
+
+    cmds
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
+    use std::str::FromStr;
+
+    /// [`workspace_pane_commands`] for a pane whose host needs no resolving.
+    fn pane_commands(
+        workspace: &Workspace,
+        win: &WindowDef,
+        pane: usize,
+        shell: Option<&str>,
+    ) -> Vec<String> {
+        let target = workspace.pane_host(win, pane).map(SshTarget::new);
+        workspace_pane_commands(workspace, win, pane, target.as_ref(), shell)
+    }
+
+    fn workspace(toml: &str) -> Workspace {
+        let config = Config::from_str(toml).unwrap();
+        config.get_workspace("ws").unwrap().clone()
+    }
 
     // --- sanitize_session_name tests ---
 
@@ -96,27 +276,74 @@ mod tests {
         assert_eq!(sanitize_session_name("a..b"), "a--b");
     }
 
+    // --- derive_window_name tests ---
+
+    fn window_names(windows: &str) -> Vec<Option<String>> {
+        let ws = workspace(&format!("[workspace.ws]\nwindows = [{}]", windows));
+        ws.windows.iter().map(derive_window_name).collect()
+    }
+
+    #[test]
+    fn derive_name_from_single_ref() {
+        assert_eq!(
+            window_names(r#"{ panes = 2, cmd = "@dev.api" }"#),
+            vec![Some("api".into())]
+        );
+    }
+
+    #[test]
+    fn derive_name_from_first_word() {
+        assert_eq!(
+            window_names(
+                r#"
+                { panes = 1, cmd = ["npm run dev", "ls"] },
+                { panes = 1, cmd = "/usr/bin/htop -d 5" },
+                { panes = 1, cmd = ["@dev.api", "tail -f log"] },
+                "#
+            ),
+            vec![Some("npm".into()), Some("htop".into()), Some("tail".into())]
+        );
+    }
+
+    #[test]
+    fn derive_name_from_pane_command() {
+        assert_eq!(
+            window_names(r#"{ panes = [{ cmd = "cargo.watch" }] }"#),
+            vec![Some("cargo-watch".into())]
+        );
+    }
+
+    #[test]
+    fn derive_name_none_without_commands() {
+        assert_eq!(
+            window_names(
+                r#"{ panes = 2 }, { panes = 1, cmd = "@dev" }, { panes = 1, cmd = "../" }"#
+            ),
+            vec![None, None, None]
+        );
+    }
+
     // --- build_remote_session_cmd tests ---
 
     #[test]
     fn build_cmd_without_dir() {
         assert_eq!(
-            build_remote_session_cmd("host", "myws", None),
-            "ssh -t host \"tmux new-session -A -s myws\""
+            build_remote_session_cmd("host", "myws", None, &[]),
+            "ssh -t host 'tmux new-session -A -s myws'"
         );
     }
 
     #[test]
     fn build_cmd_with_dir_and_sanitization() {
         assert_eq!(
-            build_remote_session_cmd("user@server", "dev.app", Some("~/src")),
-            "ssh -t user@server \"cd ~/src && tmux new-session -A -s dev-app\""
+            build_remote_session_cmd("user@server", "dev.app", Some("~/src"), &[]),
+            "ssh -t user@server 'cd ~/src && tmux new-session -A -s dev-app'"
         );
     }
 
     #[test]
     fn build_cmd_always_includes_tty_flag() {
-        let cmd = build_remote_session_cmd("host", "test", None);
+        let cmd = build_remote_session_cmd("host", "test", None, &[]);
         assert!(
             cmd.contains("ssh -t"),
             "SSH command must include -t for TTY allocation"
@@ -125,10 +352,405 @@ mod tests {
 
     #[test]
     fn build_cmd_always_uses_attach_or_create() {
-        let cmd = build_remote_session_cmd("host", "test", None);
+        let cmd = build_remote_session_cmd("host", "test", None, &[]);
         assert!(
             cmd.contains("new-session -A -s"),
             "Must use new-session -A -s for atomic attach-or-create"
         );
     }
+
+    // --- build_ssh_cd_cmd tests ---
+
+    #[test]
+    fn ssh_cd_cmd_shell_choices() {
+        assert_eq!(
+            build_ssh_cd_cmd("user@server", "~/src", None, &[]),
+            "ssh -t user@server 'cd ~/src && exec $SHELL -l'"
+        );
+        assert_eq!(
+            build_ssh_cd_cmd("user@server", "~/src", Some("bash"), &[]),
+            "ssh -t user@server 'cd ~/src && exec bash -l'"
+        );
+        assert_eq!(
+            build_ssh_cd_cmd("user@server", "~/src", Some("none"), &[]),
+            "ssh -t user@server 'cd ~/src && $SHELL'"
+        );
+    }
+
+    #[test]
+    fn ssh_cd_cmd_quotes_dir() {
+        assert_eq!(
+            build_ssh_cd_cmd("user@server", "~/My Projects", None, &[]),
+            "ssh -t user@server 'cd ~/'\\''My Projects'\\'' && exec $SHELL -l'"
+        );
+        assert_eq!(
+            build_remote_session_cmd("user@server", "ws", Some("/srv/my app"), &[]),
+            "ssh -t user@server 'cd '\\''/srv/my app'\\'' && tmux new-session -A -s ws'"
+        );
+    }
+
+    #[test]
+    fn ssh_cd_cmd_leaves_dollar_to_remote_shell() {
+        // Quoted once, so the local shell passes `$app` through to the remote
+        assert_eq!(
+            build_ssh_cd_cmd("u@h", "/srv/$app", None, &[]),
+            "ssh -t u@h 'cd '\\''/srv/$app'\\'' && exec $SHELL -l'"
+        );
+        assert_eq!(
+            build_remote_session_cmd("u@h", "ws", Some("/srv/`id`\"x\"\\"), &[]),
+            "ssh -t u@h 'cd '\\''/srv/`id`\"x\"\\'\\'' && tmux new-session -A -s ws'"
+        );
+    }
+
+    // --- workspace_pane_commands tests ---
+
+    #[test]
+    fn pane_commands_ssh_options() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            connect_timeout = 5
+            server_alive_interval = 30
+            windows = [{ panes = 1, dir = "~/src" }, { panes = 1, dir = "~" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec![
+                "ssh -o ConnectTimeout=5 -o ServerAliveInterval=30 -t user@server \
+                 'cd ~/src && exec $SHELL -l'"
+            ]
+        );
+
+        let mut ws = ws;
+        ws.dir = None;
+        ws.windows[1].dir = None;
+        ws.server_alive_interval = None;
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[1], 0, None),
+            vec!["ssh -o ConnectTimeout=5 user@server"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_ssh_with_dir() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            dir = "~/src"
+            windows = [{ panes = 1, cmd = "make" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["ssh -t user@server 'cd ~/src && exec $SHELL -l'", "make"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_export_env_before_cmd() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/srv/app"
+            windows = [{ panes = 1, cmd = "cargo run", env = { PORT = "8081" } }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["cd /srv/app", "export PORT=8081", "cargo run"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_window_env_overrides_workspace() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            env = { PORT = "8080", DATABASE_URL = "postgres://db/$app" }
+            windows = [{ panes = 1, env = { PORT = "8081" } }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec![
+                "export DATABASE_URL='postgres://db/$app'",
+                "export PORT=8081"
+            ]
+        );
+    }
+
+    #[test]
+    fn pane_commands_disconnect_sends_exit() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            disconnect = true
+            windows = [{ panes = 1, cmd = ["./backup.sh", "sync"] }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["ssh user@server", "./backup.sh && sync; exit"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_no_exit_without_disconnect() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            windows = [{ panes = 1, cmd = "top" }]
+            "#,
+        );
+        let cmds = pane_commands(&ws, &ws.windows[0], 0, None);
+        assert!(!cmds.iter().any(|c| c == "exit"));
+    }
+
+    #[test]
+    fn pane_commands_no_exit_without_host() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/srv/app"
+            disconnect = true
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["cd /srv/app"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_round_robin_keeps_prelude() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/srv"
+            windows = [{ panes = 2, cmd = ["a", "b"], distribute = "round_robin" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 1, None),
+            vec!["cd /srv", "b"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_quote_local_dir() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/srv/my app"
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["cd '/srv/my app'"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_expand_local_tilde_only() {
+        let local = workspace(
+            r#"
+            [workspace.ws]
+            dir = "~/src"
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&local, &local.windows[0], 0, None),
+            vec![format!("cd {}", shell::quote(&path::expand_tilde("~/src")))]
+        );
+
+        let remote = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            dir = "~/src"
+            windows = [{ panes = 1 }]
+            "#,
+        );
+        let cmds = pane_commands(&remote, &remote.windows[0], 0, None);
+        assert!(cmds[0].contains("cd ~/src"));
+    }
+
+    #[test]
+    fn pane_commands_window_dir_overrides_workspace() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            dir = "~/src/api"
+            windows = [{ panes = 1 }, { panes = 1, dir = "/var/log/app" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["ssh -t user@server 'cd ~/src/api && exec $SHELL -l'"]
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[1], 0, None),
+            vec!["ssh -t user@server 'cd /var/log/app && exec $SHELL -l'"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_window_dir_without_workspace_dir() {
+        let remote = workspace(
+            r#"
+            [workspace.ws]
+            host = "user@server"
+            windows = [{ panes = 1 }, { panes = 1, dir = "/srv" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&remote, &remote.windows[0], 0, None),
+            vec!["ssh user@server"]
+        );
+        assert_eq!(
+            pane_commands(&remote, &remote.windows[1], 0, None),
+            vec!["ssh -t user@server 'cd /srv && exec $SHELL -l'"]
+        );
+
+        let local = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/srv/app"
+            windows = [{ panes = 1, dir = "/tmp" }, { panes = 1 }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&local, &local.windows[0], 0, None),
+            vec!["cd /tmp"]
+        );
+        assert_eq!(
+            pane_commands(&local, &local.windows[1], 0, None),
+            vec!["cd /srv/app"]
+        );
+
+        let neither = workspace(
+            r#"
+            [workspace.ws]
+            windows = [{ panes = 1, cmd = "ls" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&neither, &neither.windows[0], 0, None),
+            vec!["ls"]
+        );
+    }
+
+    #[test]
+    fn pane_commands_hosts_per_pane() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "deploy@app"
+            windows = [
+                { hosts = ["ops@10.0.0.1", "local", "root@10.0.0.2"], cmd = "echo {user}@{ip}" },
+            ]
+            "#,
+        );
+        let win = &ws.windows[0];
+        assert_eq!(win.pane_count(), 3);
+        assert_eq!(
+            pane_commands(&ws, win, 0, None),
+            vec!["ssh ops@10.0.0.1", "echo ops@10.0.0.1"]
+        );
+        assert_eq!(pane_commands(&ws, win, 1, None), vec!["echo {user}@{ip}"]);
+        assert_eq!(
+            pane_commands(&ws, win, 2, None),
+            vec!["ssh root@10.0.0.2", "echo root@10.0.0.2"]
+        );
+        assert_eq!(ws.remote_session_host(), None);
+    }
+
+    #[test]
+    fn pane_commands_window_host_overrides_workspace() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "deploy@app"
+            dir = "/srv"
+            windows = [
+                { panes = 1 },
+                { panes = 1, host = "deploy@db" },
+                { panes = 1, host = "local", cmd = "htop" },
+            ]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["ssh -t deploy@app 'cd /srv && exec $SHELL -l'"]
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[1], 0, None),
+            vec!["ssh -t deploy@db 'cd /srv && exec $SHELL -l'"]
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[2], 0, None),
+            vec!["cd /srv", "htop"]
+        );
+        assert_eq!(ws.remote_session_host(), None);
+    }
+
+    #[test]
+    fn pane_commands_interpolate_host_in_dir_and_cmd() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/home/{user}/src"
+            windows = [{ panes = 1, host = "deploy@10.0.0.5", cmd = "ping -c1 {ip}" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec![
+                "ssh -t deploy@10.0.0.5 'cd /home/deploy/src && exec $SHELL -l'",
+                "ping -c1 10.0.0.5"
+            ]
+        );
+    }
+
+    #[test]
+    fn pane_commands_local_window_skips_disconnect() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            host = "deploy@app"
+            disconnect = true
+            windows = [{ panes = 1, host = "local", cmd = "ls" }]
+            "#,
+        );
+        assert_eq!(pane_commands(&ws, &ws.windows[0], 0, None), vec!["ls"]);
+    }
+
+    #[test]
+    fn pane_commands_pane_list_dirs() {
+        let ws = workspace(
+            r#"
+            [workspace.ws]
+            dir = "/srv"
+            [[workspace.ws.windows]]
+            panes = [{ cmd = "nvim ." }, { cmd = "make test", dir = "/srv/api" }]
+            "#,
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 0, None),
+            vec!["cd /srv", "nvim ."]
+        );
+        assert_eq!(
+            pane_commands(&ws, &ws.windows[0], 1, None),
+            vec!["cd /srv/api", "make test"]
+        );
+    }
 }
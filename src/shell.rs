@@ -0,0 +1,119 @@
+//! Shell quoting helpers.
+//!
+//! Commands are typed into panes via `send-keys`, so any value spliced into
+//! a command line has to survive a POSIX shell parse intact.
+
+use std::collections::HashMap;
+
+/// Quote a string for a POSIX shell.
+///
+/// Strings made only of safe characters are returned as-is. Anything else
+/// is wrapped in single quotes, with embedded single quotes written as `'\''`.
+///
+/// # Examples
+///
+/// ```
+/// use panout::shell::quote;
+///
+/// assert_eq!(quote("debug"), "debug");
+/// assert_eq!(quote("hello world"), "'hello world'");
+/// assert_eq!(quote("it's"), "'it'\\''s'");
+/// ```
+pub fn quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+/// [Quote](quote) a path, leaving a leading `~` or `~/` outside the quotes
+/// so the shell that runs it still expands its home directory.
+///
+/// # Examples
+///
+/// ```
+/// use panout::shell::quote_path;
+///
+/// assert_eq!(quote_path("~/My Projects"), "~/'My Projects'");
+/// assert_eq!(quote_path("/srv/app"), "/srv/app");
+/// ```
+pub fn quote_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) if !rest.is_empty() => format!("~/{}", quote(rest)),
+        _ if path == "~" || path == "~/" => path.to_string(),
+        _ => quote(path),
+    }
+}
+
+/// Build `export KEY=VALUE` commands for an environment map.
+///
+/// Keys are emitted in sorted order so the output is deterministic, and
+/// values are quoted with [`quote`].
+pub fn export_commands(env: &HashMap<String, String>) -> Vec<String> {
+    let mut keys: Vec<_> = env.keys().collect();
+    keys.sort();
+    keys.into_iter()
+        .map(|key| format!("export {}={}", key, quote(&env[key])))
+        .collect()
+}
+
+/// Check whether `key` is a valid environment variable name.
+///
+/// Names must start with a letter or underscore and contain only ASCII
+/// letters, digits, and underscores.
+pub fn is_valid_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("8081"), "8081");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("two words"), "'two words'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_quote_path() {
+        assert_eq!(quote_path("~"), "~");
+        assert_eq!(quote_path("~/src"), "~/src");
+        assert_eq!(quote_path("~/My Projects"), "~/'My Projects'");
+        assert_eq!(quote_path("/home/u/My Projects"), "'/home/u/My Projects'");
+        assert_eq!(quote_path("~other/x"), "'~other/x'");
+    }
+
+    #[test]
+    fn test_export_commands_sorted_and_quoted() {
+        let env = HashMap::from([
+            ("RUST_LOG".to_string(), "debug".to_string()),
+            ("GREETING".to_string(), "hello world".to_string()),
+        ]);
+        assert_eq!(
+            export_commands(&env),
+            vec!["export GREETING='hello world'", "export RUST_LOG=debug"]
+        );
+    }
+
+    #[test]
+    fn test_is_valid_env_key() {
+        assert!(is_valid_env_key("RUST_LOG"));
+        assert!(is_valid_env_key("_private"));
+        assert!(!is_valid_env_key(""));
+        assert!(!is_valid_env_key("1ABC"));
+        assert!(!is_valid_env_key("MY-VAR"));
+    }
+}
@@ -3,9 +3,16 @@
 //! This module provides helpers for managing SSH connections within tmux panes.
 //! SSH is handled by sending `ssh user@host` commands to panes via [`crate::tmux::send_keys`].
 
-use crate::error::Result;
+use crate::config::{Config, ServerConfig};
+use crate::error::{PanoutError, Result};
+use crate::interpolate;
+use crate::path;
+use crate::shell;
 use crate::tmux;
 
+/// Command sent to a pane to close its SSH session.
+pub const DISCONNECT_CMD: &str = "exit";
+
 /// Start an SSH session in the specified pane.
 ///
 /// Sends `ssh <host>` to the pane. The host should be in `user@ip` format.
@@ -16,13 +23,282 @@ use crate::tmux;
 /// ssh::connect(0, "admin@192.168.1.100")?;
 /// ```
 pub fn connect(pane: u32, host: &str) -> Result<()> {
-    let ssh_cmd = format!("ssh {}", host);
-    tmux::send_keys(pane, &ssh_cmd)
+    tmux::send_keys(pane, &connect_command(host))
+}
+
+/// Build the `ssh <host>` command used to connect.
+pub fn connect_command(host: &str) -> String {
+    format!("ssh {}", host)
+}
+
+/// Prefix of a host that names a `[servers.*]` entry, as in `@servers.prod`.
+pub const SERVER_REF_PREFIX: &str = "@servers.";
+
+/// Where to connect and how: a `user@host` plus the `ssh` flags to use.
+///
+/// The host is kept as written so `{user}`/`{host}` can be read from it;
+/// the flags come from a `[servers.*]` entry's `port`, `identity`, and
+/// `options`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SshTarget {
+    /// SSH host in `user@ip` format.
+    pub host: String,
+    /// SSH port (`ssh -p`).
+    pub port: Option<u16>,
+    /// Identity file (`ssh -i`); `~` is expanded.
+    pub identity: Option<String>,
+    /// Extra `ssh -o` options.
+    pub options: Vec<String>,
+}
+
+impl SshTarget {
+    /// A target with no flags of its own.
+    pub fn new(host: impl Into<String>) -> SshTarget {
+        SshTarget {
+            host: host.into(),
+            ..SshTarget::default()
+        }
+    }
+
+    /// The target described by a `[servers.*]` entry.
+    pub fn from_server(server: &ServerConfig) -> SshTarget {
+        SshTarget {
+            host: server.host.clone(),
+            port: server.port,
+            identity: server.identity.clone(),
+            options: server.options.clone(),
+        }
+    }
+
+    /// The `-p`, `-i`, and `-o` flags for this target, shell-quoted, one
+    /// flag and its value per entry (`-p 2222`).
+    pub fn ssh_options(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(port) = self.port {
+            args.push(format!("-p {}", port));
+        }
+        if let Some(ref identity) = self.identity {
+            args.push(format!(
+                "-i {}",
+                shell::quote(&path::expand_tilde(identity))
+            ));
+        }
+        for option in &self.options {
+            args.push(format!("-o {}", shell::quote(option)));
+        }
+        args
+    }
+
+    /// The full `ssh` command: flags, then the shell-quoted host.
+    pub fn command(&self) -> String {
+        std::iter::once("ssh".to_string())
+            .chain(self.ssh_options())
+            .chain(std::iter::once(shell::quote(&self.host)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Build the `ssh` command that connects to `server`.
+///
+/// The server's `port`, `identity`, and `options` become `-p`, `-i`, and
+/// `-o` flags, shell-quoted, before the host: `ssh -p 2222 -i
+/// /home/me/.ssh/work -o StrictHostKeyChecking=accept-new admin@10.0.0.5`.
+pub fn build_command(server: &ServerConfig) -> String {
+    SshTarget::from_server(server).command()
+}
+
+/// Resolve a workspace host: `@servers.name` becomes that server's
+/// [target](SshTarget::from_server); any other host is a target without
+/// flags.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::ServerNotFound`] if the referenced server
+/// doesn't exist.
+pub fn resolve_target(config: &Config, host: &str) -> Result<SshTarget> {
+    match host.strip_prefix(SERVER_REF_PREFIX) {
+        Some(name) => config
+            .servers
+            .get(name)
+            .map(SshTarget::from_server)
+            .ok_or_else(|| PanoutError::ServerNotFound(name.into())),
+        None => Ok(SshTarget::new(host)),
+    }
 }
 
 /// Disconnect from SSH in the specified pane.
 ///
 /// Sends `exit` to the pane to close the SSH session.
 pub fn disconnect(pane: u32) -> Result<()> {
-    tmux::send_keys(pane, "exit")
+    tmux::send_keys(pane, DISCONNECT_CMD)
+}
+
+/// Chain `commands` into one line that exits the shell once they're done.
+///
+/// `send-keys` returns as soon as a line is typed, so a separate `exit`
+/// could close the session while a command still runs (or be swallowed by
+/// it). Instead the remote shell gets `cmd1 && cmd2; exit`: it runs the
+/// commands in order, stops at the first failure, and exits either way.
+/// Commands with their own `;`, `&`, or `|` are grouped as `{ cmd; }` so
+/// the chain applies to them as a whole.
+pub fn chain_with_exit(commands: &[String]) -> String {
+    if commands.is_empty() {
+        return DISCONNECT_CMD.to_string();
+    }
+    let chain = commands
+        .iter()
+        .map(|cmd| match cmd.contains([';', '&', '|', '\n']) {
+            true => format!("{{ {}; }}", cmd),
+            false => cmd.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" && ");
+    format!("{}; {}", chain, DISCONNECT_CMD)
+}
+
+/// Build the full command sequence for running a server entry in one pane.
+///
+/// Connects with `ssh <host>`, then runs `commands` with `{user}`/`{host}`
+/// interpolated from the host. With `disconnect = true` they're sent as one
+/// [chain ending in `exit`](chain_with_exit). Commands are expected to be
+/// resolved already (no `@ref`s).
+pub fn server_commands(server: &ServerConfig, commands: &[String]) -> Vec<String> {
+    let commands: Vec<String> = match interpolate::parse_host(&server.host) {
+        Some((user, host)) => commands
+            .iter()
+            .map(|c| interpolate::interpolate(c, &user, &host))
+            .collect(),
+        None => commands.to_vec(),
+    };
+
+    let mut cmds = vec![build_command(server)];
+    if server.disconnect {
+        cmds.push(chain_with_exit(&commands));
+    } else {
+        cmds.extend(commands);
+    }
+    cmds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn server(host: &str, disconnect: bool) -> ServerConfig {
+        ServerConfig {
+            host: host.to_string(),
+            port: None,
+            identity: None,
+            options: Vec::new(),
+            disconnect,
+            cmd: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_build_command_flags() {
+        let mut work = server("admin@10.0.0.5", false);
+        work.port = Some(2222);
+        work.identity = Some("/keys/my key".into());
+        work.options = vec!["StrictHostKeyChecking=accept-new".into()];
+        assert_eq!(
+            build_command(&work),
+            "ssh -p 2222 -i '/keys/my key' -o StrictHostKeyChecking=accept-new admin@10.0.0.5"
+        );
+        assert_eq!(
+            build_command(&server("admin@10.0.0.5", false)),
+            "ssh admin@10.0.0.5"
+        );
+        assert_eq!(
+            build_command(&server("root@[::1]", false)),
+            "ssh 'root@[::1]'"
+        );
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let config = Config::from_str(
+            r#"
+            [servers.prod]
+            host = "admin@10.0.0.5"
+            port = 2222
+            "#,
+        )
+        .unwrap();
+        let prod = resolve_target(&config, "@servers.prod").unwrap();
+        assert_eq!(prod.host, "admin@10.0.0.5");
+        assert_eq!(prod.ssh_options(), vec!["-p 2222"]);
+        assert_eq!(
+            resolve_target(&config, "me@box").unwrap(),
+            SshTarget::new("me@box")
+        );
+        assert!(matches!(
+            resolve_target(&config, "@servers.nope"),
+            Err(PanoutError::ServerNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_server_commands_interpolates() {
+        let cmds = server_commands(
+            &server("admin@10.0.0.5", false),
+            &[
+                "cd /home/{user}/src".to_string(),
+                "ping -c1 {ip}".to_string(),
+            ],
+        );
+        assert_eq!(
+            cmds,
+            vec![
+                "ssh admin@10.0.0.5",
+                "cd /home/admin/src",
+                "ping -c1 10.0.0.5"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_commands_disconnect() {
+        let commands = ["cd /srv".to_string(), "./backup.sh".to_string()];
+        let cmds = server_commands(&server("admin@10.0.0.5", true), &commands);
+        assert_eq!(
+            cmds,
+            vec!["ssh admin@10.0.0.5", "cd /srv && ./backup.sh; exit"]
+        );
+    }
+
+    #[test]
+    fn test_server_commands_without_disconnect() {
+        let commands = ["cd /srv".to_string(), "./backup.sh".to_string()];
+        let cmds = server_commands(&server("admin@10.0.0.5", false), &commands);
+        assert_eq!(cmds, vec!["ssh admin@10.0.0.5", "cd /srv", "./backup.sh"]);
+    }
+
+    #[test]
+    fn test_chain_with_exit() {
+        let chain = |cmds: &[&str]| {
+            chain_with_exit(&cmds.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(chain(&[]), "exit");
+        assert_eq!(chain(&["uptime"]), "uptime; exit");
+        // Quotes pass through untouched
+        assert_eq!(
+            chain(&["echo 'a b'", "grep \"x y\" log"]),
+            "echo 'a b' && grep \"x y\" log; exit"
+        );
+        // Compound commands are grouped so `&&` covers all of them
+        assert_eq!(
+            chain(&["make || true", "ps aux | grep app", "cd /tmp; ls"]),
+            "{ make || true; } && { ps aux | grep app; } && { cd /tmp; ls; }; exit"
+        );
+    }
+
+    #[test]
+    fn test_server_commands_host_without_user() {
+        let cmds = server_commands(&server("prod-box", false), &["echo {user}".to_string()]);
+        assert_eq!(cmds, vec!["ssh prod-box", "echo {user}"]);
+    }
 }
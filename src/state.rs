@@ -0,0 +1,374 @@
+//! State persisted between runs.
+//!
+//! After every successful run panout records how it was invoked, so
+//! `panout last` can repeat it. State lives under the XDG state directory
+//! (`~/.local/state/panout/` by default).
+//!
+//! The file carries a `version` number. Fields added later default when
+//! missing and unknown fields are ignored, so the version only changes for
+//! incompatible format changes.
+
+use crate::cli::{Cli, Target};
+use crate::config::Layout;
+use crate::error::{PanoutError, Result};
+use crate::path;
+use crate::shell;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Current version of the state file format.
+pub const STATE_VERSION: u32 = 1;
+
+/// A recorded invocation, replayable with `panout last`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastRun {
+    /// State format version.
+    pub version: u32,
+    /// Bundle that was run.
+    pub bundle: Option<String>,
+    /// Workspace that was run.
+    pub workspace: Option<String>,
+    /// Server that was connected to.
+    pub server: Option<String>,
+    /// Tag whose bundles were run.
+    pub tag: Option<String>,
+    /// `--windows` filter.
+    pub windows: Option<String>,
+    /// `-n` pane count.
+    pub num: Option<u32>,
+    /// `--clamp`.
+    pub clamp: bool,
+    /// Layout flag (`-v`/`-H`/`--layout`).
+    pub layout: Option<Layout>,
+    /// `--delay` in milliseconds.
+    pub delay: Option<u64>,
+    /// `--dir`, made absolute so the replay uses the same directory.
+    pub dir: Option<String>,
+    /// `--env` assignments as `KEY=VALUE`, in command-line order.
+    pub env: Vec<String>,
+    /// `--focus`.
+    pub focus: Option<u32>,
+    /// `--broadcast` commands, in command-line order.
+    pub broadcast: Vec<String>,
+    /// `--sync`.
+    pub sync: bool,
+    /// `--new-window`.
+    pub new_window: bool,
+    /// Name given with `--new-window=NAME`.
+    pub window_name: Option<String>,
+    /// `--stay`.
+    pub stay: bool,
+    /// `--strict`.
+    pub strict: bool,
+    /// `--yes`.
+    pub yes: bool,
+    /// `--tmux-timeout` in seconds, when not the default.
+    pub tmux_timeout: Option<u64>,
+    /// `--config`, made absolute so the replay finds the same file.
+    pub config: Option<PathBuf>,
+}
+
+impl LastRun {
+    /// Record a run of `target` with the options from `cli`.
+    ///
+    /// The resolved target is stored explicitly, so a bundle picked from
+    /// the menu or given positionally replays as `-b <bundle>`.
+    pub fn from_cli(cli: &Cli, target: &Target) -> Self {
+        let mut run = LastRun {
+            version: STATE_VERSION,
+            windows: cli.windows.clone(),
+            num: cli.num,
+            clamp: cli.clamp,
+            layout: cli.layout(),
+            delay: cli.delay,
+            dir: cli
+                .dir
+                .as_deref()
+                .map(|dir| path::working_dir(dir).unwrap_or_else(|_| dir.to_string())),
+            env: cli
+                .env
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect(),
+            focus: cli.focus,
+            broadcast: cli.broadcast.clone(),
+            sync: cli.sync,
+            new_window: cli.new_window.is_some(),
+            window_name: cli.new_window.clone().flatten(),
+            stay: cli.stay,
+            strict: cli.strict,
+            yes: cli.yes,
+            tmux_timeout: Some(cli.tmux_timeout)
+                .filter(|&secs| secs != crate::tmux::DEFAULT_TIMEOUT.as_secs()),
+            config: cli
+                .config
+                .as_deref()
+                .map(|path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())),
+            ..LastRun::default()
+        };
+        let name = Some(match target {
+            Target::Bundle(name) | Target::Tag(name) => name.clone(),
+            Target::Workspace(name) | Target::Server(name) => name.clone(),
+        });
+        match target {
+            Target::Bundle(_) => run.bundle = name,
+            Target::Tag(_) => run.tag = name,
+            Target::Workspace(_) => run.workspace = name,
+            Target::Server(_) => run.server = name,
+        }
+        run
+    }
+
+    /// Command-line arguments (without the program name) that repeat this run.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |flag: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push(flag.to_string());
+                args.push(value);
+            }
+        };
+
+        push("-b", self.bundle.clone());
+        push("-w", self.workspace.clone());
+        push("-s", self.server.clone());
+        push("--tag", self.tag.clone());
+        push("--windows", self.windows.clone());
+        push("-n", self.num.map(|n| n.to_string()));
+        push("--delay", self.delay.map(|d| d.to_string()));
+        push("--dir", self.dir.clone());
+        for var in &self.env {
+            push("--env", Some(var.clone()));
+        }
+        push("--focus", self.focus.map(|f| f.to_string()));
+        push("--tmux-timeout", self.tmux_timeout.map(|t| t.to_string()));
+        for cmd in &self.broadcast {
+            push("--broadcast", Some(cmd.clone()));
+        }
+        push(
+            "--config",
+            self.config.as_ref().map(|p| p.display().to_string()),
+        );
+
+        if self.clamp {
+            args.push("--clamp".into());
+        }
+        if self.sync {
+            args.push("--sync".into());
+        }
+        match (self.new_window, &self.window_name) {
+            (true, Some(name)) => args.push(format!("--new-window={}", name)),
+            (true, None) => args.push("--new-window".into()),
+            (false, _) => {}
+        }
+        if self.stay {
+            args.push("--stay".into());
+        }
+        match self.layout {
+            Some(Layout::Vertical) => args.push("-v".into()),
+            Some(Layout::Horizontal) => args.push("-H".into()),
+            Some(layout) => args.push(format!("--layout={}", layout.name())),
+            None => {}
+        }
+        if self.strict {
+            args.push("--strict".into());
+        }
+        if self.yes {
+            args.push("--yes".into());
+        }
+        args
+    }
+
+    /// The run as a shell command line, e.g. `panout -b dev.all -n 3`.
+    pub fn command_line(&self) -> String {
+        std::iter::once("panout".to_string())
+            .chain(self.to_args().iter().map(|a| shell::quote(a)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Parse the recorded arguments back into a [`Cli`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::InvalidLastRun`] if the file was written by a
+    /// newer, incompatible panout or its options no longer parse.
+    pub fn to_cli(&self) -> Result<Cli> {
+        if self.version > STATE_VERSION {
+            return Err(PanoutError::InvalidLastRun(format!(
+                "state format version {} is newer than supported version {}",
+                self.version, STATE_VERSION
+            )));
+        }
+        let args = std::iter::once("panout".to_string()).chain(self.to_args());
+        Cli::try_parse_from(args).map_err(|e| PanoutError::InvalidLastRun(e.to_string()))
+    }
+}
+
+/// Directory holding panout's state files.
+///
+/// Uses `$XDG_STATE_HOME/panout`, falling back to `~/.local/state/panout`
+/// on platforms without a state directory.
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("state")))
+        .map(|dir| dir.join("panout"))
+}
+
+/// Path of the last-run state file.
+pub fn last_run_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("last.toml"))
+}
+
+/// Write `run` to `path`, creating parent directories as needed.
+pub fn save_last_run(path: &Path, run: &LastRun) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = toml::to_string(run).map_err(std::io::Error::other)?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Read the last run from `path`.
+///
+/// # Errors
+///
+/// - [`PanoutError::NoLastRun`] if nothing has been recorded yet
+/// - [`PanoutError::InvalidLastRun`] if the file can't be parsed
+pub fn load_last_run(path: &Path) -> Result<LastRun> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(PanoutError::NoLastRun);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    toml::from_str(&contents).map_err(|e| PanoutError::InvalidLastRun(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("panout").chain(args.iter().copied())).unwrap()
+    }
+
+    #[test]
+    fn test_last_run_round_trips_through_args() {
+        let original = cli(&[
+            "dev.all",
+            "-n",
+            "3",
+            "--clamp",
+            "-v",
+            "--env",
+            "A=1",
+            "--env",
+            "B=x y",
+            "--new-window=web",
+            "--tmux-timeout",
+            "30",
+            "--config",
+            "/etc/panout.toml",
+        ]);
+        let run = LastRun::from_cli(&original, &Target::Bundle("dev.all".into()));
+        let replay = run.to_cli().unwrap();
+
+        assert_eq!(replay.bundle.as_deref(), Some("dev.all"));
+        assert_eq!(replay.num, Some(3));
+        assert!(replay.clamp);
+        assert_eq!(replay.layout(), Some(Layout::Vertical));
+        assert_eq!(replay.env, original.env);
+        assert_eq!(replay.new_window, Some(Some("web".into())));
+        assert_eq!(replay.config, Some(PathBuf::from("/etc/panout.toml")));
+        assert_eq!(replay.tmux_timeout, 30);
+        assert_eq!(
+            run.command_line(),
+            "panout -b dev.all -n 3 --env A=1 --env 'B=x y' --tmux-timeout 30 \
+             --config /etc/panout.toml --clamp --new-window=web -v"
+        );
+    }
+
+    #[test]
+    fn test_last_run_stores_absolute_dir() {
+        let run = LastRun::from_cli(
+            &cli(&["dev.all", "--dir", "."]),
+            &Target::Bundle("dev.all".into()),
+        );
+
+        let dir = PathBuf::from(run.dir.unwrap());
+        assert!(dir.is_absolute(), "{}", dir.display());
+        assert_eq!(
+            dir.canonicalize().unwrap(),
+            std::env::current_dir().unwrap().canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_last_run_replays_layout_option() {
+        let original = cli(&["dev.all", "--layout", "main-horizontal"]);
+        let run = LastRun::from_cli(&original, &Target::Bundle("dev.all".into()));
+
+        assert_eq!(run.to_cli().unwrap().layout(), Some(Layout::MainHorizontal));
+        assert_eq!(
+            run.command_line(),
+            "panout -b dev.all --layout=main-horizontal"
+        );
+    }
+
+    #[test]
+    fn test_last_run_replays_tag() {
+        let run = LastRun::from_cli(&cli(&["--tag", "rust"]), &Target::Tag("rust".into()));
+
+        assert_eq!(run.to_cli().unwrap().tag.as_deref(), Some("rust"));
+        assert_eq!(run.command_line(), "panout --tag rust");
+    }
+
+    #[test]
+    fn test_save_and_load_last_run() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("state").join("last.toml");
+        let run = LastRun::from_cli(&cli(&["-w", "ws"]), &Target::Workspace("ws".into()));
+
+        save_last_run(&path, &run).unwrap();
+        assert_eq!(load_last_run(&path).unwrap(), run);
+    }
+
+    #[test]
+    fn test_load_last_run_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            load_last_run(&tmp.path().join("last.toml")),
+            Err(PanoutError::NoLastRun)
+        ));
+    }
+
+    #[test]
+    fn test_load_last_run_ignores_unknown_fields() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("last.toml");
+        fs::write(
+            &path,
+            "version = 1\nserver = \"box\"\nfuture_field = true\n",
+        )
+        .unwrap();
+
+        let run = load_last_run(&path).unwrap();
+        assert_eq!(run.to_args(), vec!["-s", "box"]);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let run = LastRun {
+            version: STATE_VERSION + 1,
+            bundle: Some("dev.all".into()),
+            ..Default::default()
+        };
+        assert!(matches!(run.to_cli(), Err(PanoutError::InvalidLastRun(_))));
+    }
+}
@@ -3,52 +3,841 @@
 //! Provides functions to create and manage tmux panes and windows via
 //! shell commands. All operations require being run inside a tmux session.
 //!
+//! # Backends
+//!
+//! Every tmux interaction goes through the [`TmuxBackend`] trait.
+//! [`ProcessTmux`] runs the real `tmux` binary; [`MockTmux`] records calls in
+//! memory so orchestration logic can be tested without a tmux server, and
+//! [`RecordingTmux`] wraps another backend to log what it did. The free
+//! functions in this module are thin wrappers over [`ProcessTmux`].
+//!
 //! # Pane Indices
 //!
 //! Tmux allows configuring `pane-base-index`, so panes might start at 0 or 1.
 //! Functions in this module handle this by querying actual pane indices from tmux.
 
-use crate::config::Layout;
+mod mock;
+mod recording;
+
+pub use mock::{MockTmux, TmuxCall};
+pub use recording::RecordingTmux;
+
+use crate::config::{Layout, PaneSize, SplitDir};
 use crate::error::{PanoutError, Result};
-use std::process::Command;
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a single tmux command may run before it's killed.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The tmux operations panout needs.
+///
+/// Implementors provide the primitive commands; composite operations such
+/// as [`create_panes`](TmuxBackend::create_panes) are built on top of them.
+pub trait TmuxBackend {
+    /// Whether we're running inside a tmux session.
+    fn in_tmux(&self) -> bool;
+
+    /// Installed tmux version (e.g. `"3.4"`).
+    fn version(&self) -> Result<String>;
+
+    /// Check that tmux can be run at all, before doing any real work.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PanoutError::TmuxNotInstalled`] if the executable is missing,
+    /// or [`PanoutError::TmuxError`] if it runs but `tmux -V` fails.
+    fn ensure_available(&self) -> Result<()> {
+        self.version().map(|_| ())
+    }
+
+    /// Read a global option (`show-options -gv`, or `-gwv` for window options).
+    fn global_option(&self, name: &str, window: bool) -> Result<String>;
+
+    /// Split a pane, optionally starting the new pane in `start_dir`.
+    ///
+    /// Splits the active pane of `window`, or of the current window if `None`.
+    /// With `size`, the new pane takes that much of the split pane
+    /// (`split-window -p` for a percentage, `-l` for cells). With
+    /// `direction`, the split is forced to `-h` or `-v`.
+    fn split_window(
+        &self,
+        window: Option<u32>,
+        start_dir: Option<&str>,
+        size: Option<PaneSize>,
+        direction: Option<SplitDir>,
+    ) -> Result<()>;
+
+    /// Type `command` into `pane` and press Enter.
+    fn send_keys(&self, pane: u32, command: &str) -> Result<()>;
+
+    /// Type `text` into `pane` without pressing Enter.
+    fn send_keys_raw(&self, pane: u32, text: &str) -> Result<()>;
+
+    /// Press tmux keys such as `C-c` or `Escape` in `pane`, without Enter.
+    fn send_key_sequence(&self, pane: u32, keys: &[&str]) -> Result<()>;
+
+    /// Apply a layout to `window`, or to the current window if `None`.
+    fn select_layout(&self, window: Option<u32>, layout: Layout) -> Result<()>;
+
+    /// Apply a raw layout string (`#{window_layout}`) to `window`, or to the
+    /// current window if `None`.
+    fn select_custom_layout(&self, window: Option<u32>, layout: &str) -> Result<()>;
+
+    /// Focus a pane in the current window.
+    fn select_pane(&self, pane: u32) -> Result<()>;
+
+    /// Set a pane's title (`select-pane -T`).
+    fn set_pane_title(&self, pane: u32, title: &str) -> Result<()>;
+
+    /// Actual pane indices in `window`, or in the current window if `None`.
+    fn list_panes(&self, window: Option<u32>) -> Result<Vec<u32>>;
+
+    /// Create a new window, which becomes the active one, and return its index.
+    fn new_window(&self, name: Option<&str>) -> Result<u32>;
+
+    /// Rename the current window.
+    fn rename_window(&self, name: &str) -> Result<()>;
+
+    /// Switch to a window by index.
+    fn select_window(&self, index: u32) -> Result<()>;
+
+    /// Index of the active window.
+    fn current_window(&self) -> Result<u32>;
+
+    /// Actual index of the active pane in the active window.
+    fn current_pane(&self) -> Result<u32>;
+
+    /// Run a command in tmux's own context (`run-shell`).
+    fn run_shell(&self, command: &str) -> Result<()>;
+
+    /// Wait `ms` milliseconds for the shells in freshly created panes to
+    /// start, so the first keys sent don't arrive before the prompt.
+    fn settle(&self, ms: u64) -> Result<()>;
+
+    /// Set a window option on `window`, or on the current window if `None`.
+    fn set_window_option(&self, window: Option<u32>, name: &str, value: &str) -> Result<()>;
+
+    /// Name of the session panout was run from.
+    fn current_session(&self) -> Result<String>;
+
+    /// Whether a session called exactly `name` exists (`has-session`).
+    ///
+    /// Only a missing session is `Ok(false)`; a tmux that fails any other
+    /// way, or times out, is an error.
+    fn has_session(&self, name: &str) -> Result<bool>;
+
+    /// Create a detached session with a single window (`new-session -d -s`).
+    fn new_session(&self, name: &str) -> Result<()>;
+
+    /// Kill the session called exactly `name` (`kill-session`).
+    fn kill_session(&self, name: &str) -> Result<()>;
+
+    /// Aim later window and pane commands at session `name` instead of the
+    /// current one; `None` goes back to the current session.
+    ///
+    /// Windows and panes keep their indices, but are looked up in that
+    /// session (`name:window`, `name:.pane` for its active window).
+    fn use_session(&self, name: Option<&str>);
+
+    /// Move this client to session `name` (`switch-client`).
+    fn switch_client(&self, name: &str) -> Result<()>;
+
+    /// Create N panes in `window` (or the current window) with the specified layout.
+    ///
+    /// The first pane is the existing pane; additional panes are created via
+    /// `split-window`. Layout is applied after each split to maintain balance.
+    ///
+    /// `start_dirs[i]` is where logical pane `i` starts (`split-window -c`);
+    /// missing entries and `None` use tmux's default. The existing first pane
+    /// is left alone, so entry 0 is ignored; callers should `cd` it themselves.
+    /// `direction` is passed to every split before the layout is applied.
+    ///
+    /// Returns the actual pane indices (accounting for `pane-base-index` config).
+    ///
+    /// # Errors
+    ///
+    /// - [`PanoutError::NotInTmux`] if not running inside tmux
+    /// - [`PanoutError::TmuxError`] if a tmux command fails
+    fn create_panes(
+        &self,
+        window: Option<u32>,
+        num: u32,
+        layout: Layout,
+        start_dirs: &[Option<&str>],
+        direction: Option<SplitDir>,
+    ) -> Result<Vec<u32>> {
+        if !self.in_tmux() {
+            return Err(PanoutError::NotInTmux);
+        }
+
+        for pane in 1..num as usize {
+            let start_dir = start_dirs.get(pane).copied().flatten();
+            self.split_window(window, start_dir, None, direction)?;
+            self.select_layout(window, layout)?;
+        }
+
+        self.list_panes(window)
+    }
+
+    /// Create one pane per split in `splits`, plus the existing one.
+    ///
+    /// `splits[i]` is the size passed when splitting off pane `i + 1` (see
+    /// [`split_sizes`] and [`split_percents`]). Each split divides the pane
+    /// created before it, so no layout is applied afterwards; it would even
+    /// the sizes out again. `start_dirs` and `direction` are handled as in
+    /// [`create_panes`](TmuxBackend::create_panes).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`create_panes`](TmuxBackend::create_panes).
+    fn create_sized_panes(
+        &self,
+        window: Option<u32>,
+        splits: &[Option<PaneSize>],
+        start_dirs: &[Option<&str>],
+        direction: Option<SplitDir>,
+    ) -> Result<Vec<u32>> {
+        if !self.in_tmux() {
+            return Err(PanoutError::NotInTmux);
+        }
+
+        for (i, &size) in splits.iter().enumerate() {
+            let start_dir = start_dirs.get(i + 1).copied().flatten();
+            self.split_window(window, start_dir, size, direction)?;
+        }
+
+        self.list_panes(window)
+    }
+}
+
+/// Backend that runs the real `tmux` binary.
+///
+/// Failed commands surface tmux's stderr in [`PanoutError::TmuxError`], e.g.
+/// `select-pane 7 failed: can't find pane: 7`. A command that doesn't finish
+/// within the timeout (a wedged tmux server) is killed and reported the same
+/// way instead of hanging panout.
+#[derive(Debug, Clone)]
+pub struct ProcessTmux {
+    program: OsString,
+    timeout: Duration,
+    /// Session set by [`TmuxBackend::use_session`].
+    session: RefCell<Option<String>>,
+}
+
+impl Default for ProcessTmux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessTmux {
+    /// Use the `tmux` found on `PATH`.
+    pub fn new() -> Self {
+        Self::with_program("tmux")
+    }
+
+    /// Use a specific tmux executable.
+    pub fn with_program(program: impl Into<OsString>) -> Self {
+        ProcessTmux {
+            program: program.into(),
+            timeout: DEFAULT_TIMEOUT,
+            session: RefCell::new(None),
+        }
+    }
+
+    /// Kill tmux commands that run longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Target for `window`, or the current window, honoring `use_session`.
+    fn window_arg(&self, window: Option<u32>) -> Option<String> {
+        match (self.session.borrow().as_deref(), window) {
+            (Some(session), Some(index)) => Some(format!("{}{}", session_target(session), index)),
+            (Some(session), None) => Some(session_target(session)),
+            (None, Some(index)) => Some(window_target(index)),
+            (None, None) => None,
+        }
+    }
+
+    /// Target for pane `pane` of the current window, honoring `use_session`.
+    fn pane_arg(&self, pane: u32) -> String {
+        match self.session.borrow().as_deref() {
+            Some(session) => format!("{}.{}", session_target(session), pane),
+            None => pane.to_string(),
+        }
+    }
+
+    /// Run tmux with `args` and return its stdout.
+    ///
+    /// `what` names the operation in the error when tmux exits non-zero or
+    /// times out.
+    fn run(&self, args: &[&str], what: &str) -> Result<String> {
+        let (success, stdout, stderr) = self.exec(args, what)?;
+        if !success {
+            return Err(PanoutError::TmuxError(failure_message(what, &stderr)));
+        }
+
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+
+    /// Run tmux with `args` and return whether it succeeded, with its stdout
+    /// and stderr.
+    ///
+    /// Only a failure to start tmux, or a timeout, is an error here.
+    fn exec(&self, args: &[&str], what: &str) -> Result<(bool, Vec<u8>, Vec<u8>)> {
+        let mut child = Command::new(&self.program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => PanoutError::TmuxNotInstalled,
+                _ => PanoutError::TmuxError(e.to_string()),
+            })?;
+
+        // Drain the pipes on their own threads so a chatty tmux can't block
+        // on a full pipe while we wait for it to exit
+        let stdout = child.stdout.take().map(read_to_end);
+        let stderr = child.stderr.take().map(read_to_end);
+
+        let deadline = Instant::now() + self.timeout;
+        let mut poll = Duration::from_millis(1);
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .map_err(|e| PanoutError::TmuxError(e.to_string()))?
+            {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(PanoutError::TmuxError(format!(
+                    "{} timed out after {:?}",
+                    what, self.timeout
+                )));
+            }
+            thread::sleep(poll);
+            poll = (poll * 2).min(Duration::from_millis(50));
+        };
+
+        let stdout = stdout
+            .map(|h| h.join().unwrap_or_default())
+            .unwrap_or_default();
+        let stderr = stderr
+            .map(|h| h.join().unwrap_or_default())
+            .unwrap_or_default();
+        Ok((status.success(), stdout, stderr))
+    }
+}
+
+/// Read a pipe to the end on a background thread.
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// Run tmux with `args`, killing it if it takes longer than `timeout`.
+///
+/// Returns tmux's stdout. Every tmux command panout issues goes through
+/// the same logic via [`ProcessTmux`].
+///
+/// # Errors
+///
+/// Returns [`PanoutError::TmuxError`] if tmux can't be started, exits
+/// non-zero (with its stderr), or times out (naming the command).
+pub fn run_tmux(args: &[&str], timeout: Duration) -> Result<String> {
+    let what = args.first().copied().unwrap_or("tmux");
+    ProcessTmux::new().with_timeout(timeout).run(args, what)
+}
+
+/// Format a failed-command message, appending tmux's stderr when it has any.
+fn failure_message(what: &str, stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let stderr = stderr.trim();
+    if stderr.is_empty() {
+        format!("{} failed", what)
+    } else {
+        format!("{} failed: {}", what, stderr)
+    }
+}
+
+impl TmuxBackend for ProcessTmux {
+    /// Checks for the `TMUX` environment variable, which tmux sets when active.
+    fn in_tmux(&self) -> bool {
+        std::env::var("TMUX").is_ok()
+    }
+
+    fn version(&self) -> Result<String> {
+        let text = self.run(&["-V"], "tmux -V")?;
+        parse_version(&text)
+            .ok_or_else(|| PanoutError::TmuxError(format!("unrecognized version: {}", text.trim())))
+    }
+
+    fn global_option(&self, name: &str, window: bool) -> Result<String> {
+        let flags = if window { "-gwv" } else { "-gv" };
+        let value = self.run(
+            &["show-options", flags, name],
+            &format!("show-options {}", name),
+        )?;
+        Ok(value.trim().to_string())
+    }
+
+    fn split_window(
+        &self,
+        window: Option<u32>,
+        start_dir: Option<&str>,
+        size: Option<PaneSize>,
+        direction: Option<SplitDir>,
+    ) -> Result<()> {
+        let target = self.window_arg(window);
+        let size = size.map(size_flag);
+        let size = size.as_ref().map(|(flag, value)| (*flag, value.as_str()));
+        let direction = direction.map(|d| d.to_tmux_flag());
+        self.run(
+            &split_window_args(target.as_deref(), start_dir, size, direction),
+            "split-window",
+        )?;
+        Ok(())
+    }
+
+    fn send_keys(&self, pane: u32, command: &str) -> Result<()> {
+        let pane_target = self.pane_arg(pane);
+        self.run(
+            &send_keys_args(&pane_target, command, true),
+            &format!("send-keys to pane {}", pane),
+        )?;
+        Ok(())
+    }
+
+    fn send_keys_raw(&self, pane: u32, text: &str) -> Result<()> {
+        let pane_target = self.pane_arg(pane);
+        self.run(
+            &send_keys_args(&pane_target, text, false),
+            &format!("send-keys to pane {}", pane),
+        )?;
+        Ok(())
+    }
+
+    fn send_key_sequence(&self, pane: u32, keys: &[&str]) -> Result<()> {
+        let pane_target = self.pane_arg(pane);
+        self.run(
+            &send_key_sequence_args(&pane_target, keys),
+            &format!("send-keys {} to pane {}", keys.join(" "), pane),
+        )?;
+        Ok(())
+    }
+
+    fn select_layout(&self, window: Option<u32>, layout: Layout) -> Result<()> {
+        let layout_name = layout.to_tmux_layout();
+        let mut args = vec!["select-layout"];
+        let target = self.window_arg(window);
+        if let Some(ref t) = target {
+            args.extend(["-t", t]);
+        }
+        args.push(layout_name);
+        self.run(&args, &format!("select-layout {}", layout_name))?;
+        Ok(())
+    }
+
+    fn select_custom_layout(&self, window: Option<u32>, layout: &str) -> Result<()> {
+        let mut args = vec!["select-layout"];
+        let target = self.window_arg(window);
+        if let Some(ref t) = target {
+            args.extend(["-t", t]);
+        }
+        args.push(layout);
+        self.run(&args, &format!("select-layout '{}'", layout))?;
+        Ok(())
+    }
+
+    fn select_pane(&self, pane: u32) -> Result<()> {
+        let pane_target = self.pane_arg(pane);
+        self.run(
+            &["select-pane", "-t", &pane_target],
+            &format!("select-pane {}", pane),
+        )?;
+        Ok(())
+    }
+
+    fn set_pane_title(&self, pane: u32, title: &str) -> Result<()> {
+        let pane_target = self.pane_arg(pane);
+        self.run(
+            &set_pane_title_args(&pane_target, title),
+            &format!("select-pane -T on pane {}", pane),
+        )?;
+        Ok(())
+    }
+
+    fn list_panes(&self, window: Option<u32>) -> Result<Vec<u32>> {
+        let mut args = vec!["list-panes", "-F", "#{pane_index}"];
+        let target = self.window_arg(window);
+        if let Some(ref t) = target {
+            args.extend(["-t", t]);
+        }
+        let output = self.run(&args, "list-panes")?;
+        let indices: Vec<u32> = output
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect();
+
+        Ok(indices)
+    }
+
+    fn new_window(&self, name: Option<&str>) -> Result<u32> {
+        let mut args = vec!["new-window", "-P", "-F", "#{window_index}"];
+        // A bare `session:` target picks the session's next free index
+        let target = self.window_arg(None);
+        if let Some(ref t) = target {
+            args.extend(["-t", t]);
+        }
+        if let Some(n) = name {
+            args.push("-n");
+            args.push(n);
+        }
+
+        let index = self.run(&args, "new-window")?;
+        index
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| PanoutError::TmuxError("failed to parse new window index".into()))
+    }
+
+    fn rename_window(&self, name: &str) -> Result<()> {
+        let what = format!("rename-window {}", name);
+        match self.window_arg(None) {
+            Some(target) => self.run(&["rename-window", "-t", &target, name], &what)?,
+            None => self.run(&rename_window_args(name), &what)?,
+        };
+        Ok(())
+    }
+
+    fn select_window(&self, index: u32) -> Result<()> {
+        let target = match self.session.borrow().as_deref() {
+            Some(session) => format!("{}{}", session_target(session), index),
+            None => index.to_string(),
+        };
+        self.run(
+            &["select-window", "-t", &target],
+            &format!("select-window {}", index),
+        )?;
+        Ok(())
+    }
+
+    fn current_window(&self) -> Result<u32> {
+        let mut args = vec!["display-message", "-p"];
+        let target = self.window_arg(None);
+        if let Some(ref t) = target {
+            args.extend(["-t", t]);
+        }
+        args.push("#{window_index}");
+        let index_str = self.run(&args, "display-message")?;
+        index_str
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| PanoutError::TmuxError("failed to parse window index".into()))
+    }
+
+    fn current_pane(&self) -> Result<u32> {
+        let mut args = vec!["display-message", "-p"];
+        let target = self.window_arg(None);
+        if let Some(ref t) = target {
+            args.extend(["-t", t]);
+        }
+        args.push("#{pane_index}");
+        let index_str = self.run(&args, "display-message")?;
+        index_str
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| PanoutError::TmuxError("failed to parse pane index".into()))
+    }
+
+    fn run_shell(&self, command: &str) -> Result<()> {
+        self.run(
+            &run_shell_args(command),
+            &format!("run-shell '{}'", command),
+        )?;
+        Ok(())
+    }
+
+    fn settle(&self, ms: u64) -> Result<()> {
+        thread::sleep(Duration::from_millis(ms));
+        Ok(())
+    }
+
+    fn set_window_option(&self, window: Option<u32>, name: &str, value: &str) -> Result<()> {
+        let target = self.window_arg(window);
+        self.run(
+            &set_window_option_args(target.as_deref(), name, value),
+            &format!("set-window-option {}", name),
+        )?;
+        Ok(())
+    }
+
+    fn current_session(&self) -> Result<String> {
+        let name = self.run(
+            &["display-message", "-p", "#{session_name}"],
+            "display-message",
+        )?;
+        Ok(name.trim().to_string())
+    }
+
+    fn has_session(&self, name: &str) -> Result<bool> {
+        // has-session reports a missing session through its exit status;
+        // any other failure is a real error
+        let target = format!("={}", name);
+        let (success, _, stderr) = self.exec(&["has-session", "-t", &target], "has-session")?;
+        if success {
+            Ok(true)
+        } else if String::from_utf8_lossy(&stderr).contains("can't find session") {
+            Ok(false)
+        } else {
+            Err(PanoutError::TmuxError(failure_message(
+                "has-session",
+                &stderr,
+            )))
+        }
+    }
+
+    fn new_session(&self, name: &str) -> Result<()> {
+        self.run(
+            &["new-session", "-d", "-s", name],
+            &format!("new-session {}", name),
+        )?;
+        Ok(())
+    }
+
+    fn kill_session(&self, name: &str) -> Result<()> {
+        let target = format!("={}", name);
+        self.run(
+            &["kill-session", "-t", &target],
+            &format!("kill-session {}", name),
+        )?;
+        Ok(())
+    }
+
+    fn use_session(&self, name: Option<&str>) {
+        *self.session.borrow_mut() = name.map(String::from);
+    }
+
+    fn switch_client(&self, name: &str) -> Result<()> {
+        let target = format!("={}", name);
+        self.run(
+            &["switch-client", "-t", &target],
+            &format!("switch-client {}", name),
+        )?;
+        Ok(())
+    }
+}
 
 /// Check if we're running inside a tmux session.
 ///
 /// Checks for the `TMUX` environment variable, which tmux sets when active.
 pub fn in_tmux() -> bool {
-    std::env::var("TMUX").is_ok()
+    ProcessTmux::new().in_tmux()
+}
+
+/// Get the installed tmux version (e.g. `"3.4"`).
+///
+/// Runs `tmux -V`, which works outside a tmux session.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::TmuxError`] if tmux can't be run or its output
+/// isn't recognized.
+pub fn version() -> Result<String> {
+    ProcessTmux::new().version()
+}
+
+/// Check that the `tmux` on `PATH` can be run.
+///
+/// See [`TmuxBackend::ensure_available`].
+pub fn ensure_available() -> Result<()> {
+    ProcessTmux::new().ensure_available()
+}
+
+/// Extract the version from `tmux -V` output such as `tmux 3.4` or `tmux next-3.5`.
+pub fn parse_version(output: &str) -> Option<String> {
+    let version = output.trim().strip_prefix("tmux ")?.trim();
+    let version = version.strip_prefix("next-").unwrap_or(version);
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+/// Read a global tmux option value (`show-options -gv`).
+///
+/// Set `window` for window options such as `pane-base-index`.
+pub fn global_option(name: &str, window: bool) -> Result<String> {
+    ProcessTmux::new().global_option(name, window)
 }
 
 /// Create N panes in the current window with the specified layout.
 ///
-/// The first pane is the existing pane; additional panes are created via `split-window`.
-/// Layout is applied after each split to maintain balance.
+/// See [`TmuxBackend::create_panes`].
+pub fn create_panes(num: u32, layout: Layout, start_dir: Option<&str>) -> Result<Vec<u32>> {
+    let start_dirs = vec![start_dir; num as usize];
+    ProcessTmux::new().create_panes(None, num, layout, &start_dirs, None)
+}
+
+/// Target string for a window of the current session (`:N`).
+///
+/// A bare number would be read as a pane index by pane-targeting commands.
+fn window_target(index: u32) -> String {
+    format!(":{}", index)
+}
+
+/// Target prefix for windows of session `name` (`=name:`).
 ///
-/// Returns the actual pane indices (accounting for `pane-base-index` config).
+/// The `=` makes tmux match the name exactly rather than as a prefix.
+fn session_target(name: &str) -> String {
+    format!("={}:", name)
+}
+
+/// Build the `split-window` argument list.
+fn split_window_args<'a>(
+    target: Option<&'a str>,
+    start_dir: Option<&'a str>,
+    size: Option<(&'a str, &'a str)>,
+    direction: Option<&'a str>,
+) -> Vec<&'a str> {
+    let mut args = vec!["split-window"];
+    if let Some(direction) = direction {
+        args.push(direction);
+    }
+    if let Some(target) = target {
+        args.push("-t");
+        args.push(target);
+    }
+    if let Some(dir) = start_dir {
+        args.push("-c");
+        args.push(dir);
+    }
+    if let Some((flag, value)) = size {
+        args.push(flag);
+        args.push(value);
+    }
+    args
+}
+
+/// The `split-window` flag and value for a pane size.
 ///
-/// # Errors
+/// Percentages are clamped to 1-99, the range tmux accepts; cell counts are
+/// at least 1.
+fn size_flag(size: PaneSize) -> (&'static str, String) {
+    match size {
+        PaneSize::Percent(p) => ("-p", p.clamp(1, 99).to_string()),
+        PaneSize::Cells(cells) => ("-l", cells.max(1).to_string()),
+    }
+}
+
+/// The `main-pane-width`/`main-pane-height` value for a pane size.
 ///
-/// - [`PanoutError::NotInTmux`] if not running inside tmux
-/// - [`PanoutError::TmuxError`] if a tmux command fails
-pub fn create_panes(num: u32, layout: Layout) -> Result<Vec<u32>> {
-    if !in_tmux() {
-        return Err(PanoutError::NotInTmux);
+/// Clamped like [`split-window` sizes](size_flag).
+pub fn main_pane_value(size: PaneSize) -> String {
+    match size {
+        PaneSize::Percent(p) => format!("{}%", p.clamp(1, 99)),
+        PaneSize::Cells(cells) => cells.max(1).to_string(),
     }
+}
 
-    for _ in 1..num {
-        let status = Command::new("tmux")
-            .args(["split-window"])
-            .status()
-            .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+/// Split sizes that carve panes of the given `sizes` out of one window.
+///
+/// Returns one entry per split (one fewer than `sizes`), for
+/// [`TmuxBackend::create_sized_panes`].
+///
+/// - Percentages: panes without a size share what's left of 100% evenly
+///   (at least 1% each), then [`split_percents`] turns them into splits.
+/// - Cells: a split gets a cell count when it and every pane after it are
+///   sized in cells, counting the borders between them; other splits halve.
+///
+/// # Errors
+///
+/// Returns [`PanoutError::InvalidConfig`] if percentages and cells are mixed.
+pub fn split_sizes(sizes: &[Option<PaneSize>]) -> Result<Vec<Option<PaneSize>>> {
+    let has_cells = sizes.iter().any(|s| matches!(s, Some(PaneSize::Cells(_))));
+    let has_percent = sizes
+        .iter()
+        .any(|s| matches!(s, Some(PaneSize::Percent(_))));
 
-        if !status.success() {
-            return Err(PanoutError::TmuxError("split-window failed".into()));
-        }
+    if has_cells && has_percent {
+        return Err(PanoutError::InvalidConfig(
+            "pane sizes mix percentages and cells".into(),
+        ));
+    }
 
-        set_layout(layout)?;
+    if has_cells {
+        let last = sizes.len().saturating_sub(1) as u32;
+        return Ok((1..sizes.len())
+            .map(|i| {
+                let cells: Option<Vec<u32>> = sizes[i..]
+                    .iter()
+                    .map(|s| match s {
+                        Some(PaneSize::Cells(c)) => Some(*c),
+                        _ => None,
+                    })
+                    .collect();
+                cells.map(|c| PaneSize::Cells(c.iter().sum::<u32>() + last - i as u32))
+            })
+            .collect());
     }
 
-    pane_indices()
+    let used: u32 = sizes
+        .iter()
+        .flatten()
+        .map(|s| match s {
+            PaneSize::Percent(p) => u32::from(*p),
+            PaneSize::Cells(_) => 0,
+        })
+        .sum();
+    let unsized_count = sizes.iter().filter(|s| s.is_none()).count() as u32;
+    let share = 100u32
+        .saturating_sub(used)
+        .checked_div(unsized_count)
+        .unwrap_or(0)
+        .max(1);
+    let weights: Vec<u32> = sizes
+        .iter()
+        .map(|s| match s {
+            Some(PaneSize::Percent(p)) => u32::from(*p),
+            _ => share,
+        })
+        .collect();
+
+    Ok(split_percents(&weights)
+        .into_iter()
+        .map(|p| Some(PaneSize::Percent(p as u8)))
+        .collect())
+}
+
+/// `split-window -p` values that carve panes of relative `sizes` out of one.
+///
+/// Each split divides the most recent pane, so the new pane's share is the
+/// weight of the panes still to come over the weight of the pane being
+/// split. For `[60, 20, 20]` that's `[40, 50]`. Values are clamped to
+/// 1..=99, which tmux accepts.
+pub fn split_percents(sizes: &[u32]) -> Vec<u32> {
+    (1..sizes.len())
+        .map(|i| {
+            let rest: u64 = sizes[i..].iter().map(|&s| u64::from(s)).sum();
+            let whole = rest + u64::from(sizes[i - 1]);
+            let percent = (rest * 100 + whole / 2).checked_div(whole).unwrap_or(50);
+            percent.clamp(1, 99) as u32
+        })
+        .collect()
 }
 
 /// Send keystrokes to a specific pane.
@@ -60,58 +849,138 @@ pub fn create_panes(num: u32, layout: Layout) -> Result<Vec<u32>> {
 /// * `pane` - The pane index (as returned by [`pane_indices`])
 /// * `command` - The command string to send
 pub fn send_keys(pane: u32, command: &str) -> Result<()> {
-    let pane_target = format!("{}", pane);
-    let status = Command::new("tmux")
-        .args(["send-keys", "-t", &pane_target, command, "Enter"])
-        .status()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+    ProcessTmux::new().send_keys(pane, command)
+}
+
+/// Send keystrokes to a pane without pressing Enter.
+///
+/// For partial lines, prompts, and TUIs that shouldn't get an immediate
+/// newline, e.g. typing `:wq` into vim for the user to confirm.
+pub fn send_keys_raw(pane: u32, text: &str) -> Result<()> {
+    ProcessTmux::new().send_keys_raw(pane, text)
+}
 
-    if !status.success() {
-        return Err(PanoutError::TmuxError(format!(
-            "send-keys to pane {} failed",
-            pane
-        )));
+/// Press tmux keys in a pane, e.g. `["C-c"]` to interrupt what's running.
+///
+/// Each entry is a tmux key name passed as its own `send-keys` argument, so
+/// `C-c`, `Escape`, `Enter`, `Up`, or `M-x` are pressed rather than typed;
+/// anything that isn't a key name is typed as text. No Enter is added.
+pub fn send_key_sequence(pane: u32, keys: &[&str]) -> Result<()> {
+    ProcessTmux::new().send_key_sequence(pane, keys)
+}
+
+/// Build the `send-keys` argument list for a sequence of key names.
+fn send_key_sequence_args<'a>(pane_target: &'a str, keys: &[&'a str]) -> Vec<&'a str> {
+    let mut args = vec!["send-keys", "-t", pane_target];
+    args.extend(keys);
+    args
+}
+
+/// Marker starting a command string that stands for a key sequence.
+///
+/// Commands travel as plain strings through resolution, so a `keys` entry
+/// is carried as this marker followed by its space-separated key names.
+const KEYS_MARKER: char = '\0';
+
+/// Encode a key sequence as a command string (see [`key_sequence`]).
+pub fn key_sequence_command(keys: &[String]) -> String {
+    format!("{}{}", KEYS_MARKER, keys.join(" "))
+}
+
+/// The key names in a command built by [`key_sequence_command`], or `None`
+/// for an ordinary command.
+pub fn key_sequence(command: &str) -> Option<Vec<&str>> {
+    command
+        .strip_prefix(KEYS_MARKER)
+        .map(|keys| keys.split(' ').filter(|k| !k.is_empty()).collect())
+}
+
+/// Build the `send-keys` argument list, with a trailing `Enter` if `enter`.
+fn send_keys_args<'a>(pane_target: &'a str, text: &'a str, enter: bool) -> Vec<&'a str> {
+    let mut args = vec!["send-keys", "-t", pane_target, text];
+    if enter {
+        args.push("Enter");
     }
+    args
+}
+
+/// Run a shell command in tmux's own context (`tmux run-shell`).
+///
+/// Unlike [`send_keys`], the command doesn't go to any pane; tmux runs it
+/// in the background, which suits plugin triggers and notifications.
+pub fn run_shell(command: &str) -> Result<()> {
+    ProcessTmux::new().run_shell(command)
+}
 
-    Ok(())
+/// Build the `run-shell` argument list.
+fn run_shell_args(command: &str) -> [&str; 2] {
+    ["run-shell", command]
 }
 
 /// Apply a layout to the current window.
 ///
 /// Uses tmux's `select-layout` command with the appropriate layout name.
 pub fn set_layout(layout: Layout) -> Result<()> {
-    let layout_name = layout.to_tmux_layout();
-    let status = Command::new("tmux")
-        .args(["select-layout", layout_name])
-        .status()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
-
-    if !status.success() {
-        return Err(PanoutError::TmuxError(format!(
-            "select-layout {} failed",
-            layout_name
-        )));
-    }
-
-    Ok(())
+    ProcessTmux::new().select_layout(None, layout)
 }
 
 /// Select (focus) a specific pane.
 pub fn select_pane(pane: u32) -> Result<()> {
-    let pane_target = format!("{}", pane);
-    let status = Command::new("tmux")
-        .args(["select-pane", "-t", &pane_target])
-        .status()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+    ProcessTmux::new().select_pane(pane)
+}
 
-    if !status.success() {
-        return Err(PanoutError::TmuxError(format!(
-            "select-pane {} failed",
-            pane
-        )));
+/// Set the title shown in a pane's border.
+///
+/// Titles are only displayed when tmux's `pane-border-status` option is
+/// `top` or `bottom`.
+pub fn set_pane_title(pane: u32, title: &str) -> Result<()> {
+    ProcessTmux::new().set_pane_title(pane, title)
+}
+
+/// Build the `select-pane -T` argument list.
+fn set_pane_title_args<'a>(pane_target: &'a str, title: &'a str) -> [&'a str; 5] {
+    ["select-pane", "-t", pane_target, "-T", title]
+}
+
+/// Set a window option on the current window (`set-window-option`).
+///
+/// Used for options such as `synchronize-panes` that only make sense per
+/// window.
+pub fn set_window_option(name: &str, value: &str) -> Result<()> {
+    ProcessTmux::new().set_window_option(None, name, value)
+}
+
+/// Build the `set-window-option` argument list.
+fn set_window_option_args<'a>(
+    target: Option<&'a str>,
+    name: &'a str,
+    value: &'a str,
+) -> Vec<&'a str> {
+    let mut args = vec!["set-window-option"];
+    if let Some(target) = target {
+        args.push("-t");
+        args.push(target);
     }
+    args.push(name);
+    args.push(value);
+    args
+}
 
-    Ok(())
+/// Translate a logical (0-based) pane index into an actual tmux pane index.
+///
+/// `indices` is the list returned by [`pane_indices`] or [`create_panes`].
+///
+/// # Errors
+///
+/// Returns [`PanoutError::PaneOutOfRange`] if `logical` is past the last pane.
+pub fn logical_pane(indices: &[u32], logical: u32) -> Result<u32> {
+    indices
+        .get(logical as usize)
+        .copied()
+        .ok_or(PanoutError::PaneOutOfRange {
+            pane: logical,
+            count: indices.len(),
+        })
 }
 
 /// Get the number of panes in the current window.
@@ -124,77 +993,428 @@ pub fn pane_count() -> Result<u32> {
 /// This queries tmux directly and handles configurations where
 /// `pane-base-index` is set to 1 instead of 0.
 pub fn pane_indices() -> Result<Vec<u32>> {
-    let output = Command::new("tmux")
-        .args(["list-panes", "-F", "#{pane_index}"])
-        .output()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+    ProcessTmux::new().list_panes(None)
+}
 
-    if !output.status.success() {
-        return Err(PanoutError::TmuxError("list-panes failed".into()));
-    }
+/// Create a new tmux window, optionally with a name.
+///
+/// The new window becomes the active window. Returns its index.
+pub fn create_window(name: Option<&str>) -> Result<u32> {
+    ProcessTmux::new().new_window(name)
+}
 
-    let indices: Vec<u32> = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .filter_map(|line| line.trim().parse().ok())
-        .collect();
+/// Rename the current window.
+pub fn rename_window(name: &str) -> Result<()> {
+    ProcessTmux::new().rename_window(name)
+}
 
-    Ok(indices)
+/// Build the `rename-window` argument list.
+fn rename_window_args(name: &str) -> [&str; 2] {
+    ["rename-window", name]
 }
 
-/// Create a new tmux window, optionally with a name.
-///
-/// The new window becomes the active window.
-pub fn create_window(name: Option<&str>) -> Result<()> {
-    let mut args = vec!["new-window"];
-    if let Some(n) = name {
-        args.push("-n");
-        args.push(n);
+/// Switch to a specific window by index.
+pub fn select_window(index: u32) -> Result<()> {
+    ProcessTmux::new().select_window(index)
+}
+
+/// Get the index of the currently active window.
+pub fn current_window() -> Result<u32> {
+    ProcessTmux::new().current_window()
+}
+
+/// Get the index of the active pane in the active window.
+pub fn current_pane() -> Result<u32> {
+    ProcessTmux::new().current_pane()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logical_pane_maps_through_indices() {
+        assert_eq!(logical_pane(&[0, 1, 2], 1).unwrap(), 1);
+        assert_eq!(logical_pane(&[1, 2, 3], 0).unwrap(), 1);
     }
 
-    let status = Command::new("tmux")
-        .args(&args)
-        .status()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("tmux 3.4\n"), Some("3.4".into()));
+        assert_eq!(parse_version("tmux 3.3a"), Some("3.3a".into()));
+        assert_eq!(parse_version("tmux next-3.5"), Some("3.5".into()));
+        assert_eq!(parse_version("screen 4.0"), None);
+    }
 
-    if !status.success() {
-        return Err(PanoutError::TmuxError("new-window failed".into()));
+    #[test]
+    fn test_failure_message_includes_stderr() {
+        assert_eq!(failure_message("split-window", b""), "split-window failed");
+        assert_eq!(
+            failure_message("select-pane 7", b"can't find pane: 7\n"),
+            "select-pane 7 failed: can't find pane: 7"
+        );
     }
 
-    Ok(())
-}
+    #[cfg(unix)]
+    #[test]
+    fn test_process_tmux_error_carries_stderr() {
+        use std::os::unix::fs::PermissionsExt;
 
-/// Switch to a specific window by index.
-pub fn select_window(index: u32) -> Result<()> {
-    let target = format!("{}", index);
-    let status = Command::new("tmux")
-        .args(["select-window", "-t", &target])
-        .status()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+        let tmp = tempfile::tempdir().unwrap();
+        let fake = tmp.path().join("tmux");
+        std::fs::write(&fake, "#!/bin/sh\necho \"can't find layout\" >&2\nexit 1\n").unwrap();
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
 
-    if !status.success() {
-        return Err(PanoutError::TmuxError(format!(
-            "select-window {} failed",
-            index
-        )));
+        let tmux = ProcessTmux::with_program(&fake);
+        match tmux.select_layout(None, Layout::Vertical) {
+            Err(PanoutError::TmuxError(msg)) => {
+                assert_eq!(
+                    msg,
+                    "select-layout even-horizontal failed: can't find layout"
+                );
+            }
+            other => panic!("expected TmuxError, got {:?}", other),
+        }
+        match tmux.select_custom_layout(None, "bad,layout") {
+            Err(PanoutError::TmuxError(msg)) => {
+                assert_eq!(msg, "select-layout 'bad,layout' failed: can't find layout");
+            }
+            other => panic!("expected TmuxError, got {:?}", other),
+        }
     }
 
-    Ok(())
-}
+    #[cfg(unix)]
+    #[test]
+    fn test_process_tmux_times_out() {
+        use std::os::unix::fs::PermissionsExt;
 
-/// Get the index of the currently active window.
-pub fn current_window() -> Result<u32> {
-    let output = Command::new("tmux")
-        .args(["display-message", "-p", "#{window_index}"])
-        .output()
-        .map_err(|e| PanoutError::TmuxError(e.to_string()))?;
+        let tmp = tempfile::tempdir().unwrap();
+        let fake = tmp.path().join("tmux");
+        std::fs::write(&fake, "#!/bin/sh\nexec sleep 10\n").unwrap();
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let tmux = ProcessTmux::with_program(&fake).with_timeout(Duration::from_millis(100));
+        let started = Instant::now();
+        match tmux.list_panes(None) {
+            Err(PanoutError::TmuxError(msg)) => {
+                assert_eq!(msg, "list-panes timed out after 100ms");
+            }
+            other => panic!("expected TmuxError, got {:?}", other),
+        }
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_tmux_has_session_only_missing_is_false() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let fake = tmp.path().join("tmux");
+        std::fs::write(
+            &fake,
+            "#!/bin/sh\n\
+             case \"$3\" in\n\
+             =here) exit 0 ;;\n\
+             =gone) echo \"can't find session: gone\" >&2; exit 1 ;;\n\
+             =slow) exec sleep 10 ;;\n\
+             *) echo \"lost server\" >&2; exit 1 ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let tmux = ProcessTmux::with_program(&fake).with_timeout(Duration::from_millis(100));
+        assert!(tmux.has_session("here").unwrap());
+        assert!(!tmux.has_session("gone").unwrap());
+        match tmux.has_session("slow") {
+            Err(PanoutError::TmuxError(msg)) => {
+                assert_eq!(msg, "has-session timed out after 100ms");
+            }
+            other => panic!("expected TmuxError, got {:?}", other),
+        }
+        match tmux.has_session("other") {
+            Err(PanoutError::TmuxError(msg)) => {
+                assert_eq!(msg, "has-session failed: lost server");
+            }
+            other => panic!("expected TmuxError, got {:?}", other),
+        }
+        assert!(matches!(
+            ProcessTmux::with_program("/nonexistent/panout-test/tmux").has_session("here"),
+            Err(PanoutError::TmuxNotInstalled)
+        ));
+    }
+
+    #[test]
+    fn test_missing_binary_is_not_installed() {
+        let tmux = ProcessTmux::with_program("/nonexistent/panout-test/tmux");
+        assert!(matches!(
+            tmux.ensure_available(),
+            Err(PanoutError::TmuxNotInstalled)
+        ));
+    }
+
+    #[test]
+    fn test_ensure_available_via_backend() {
+        assert!(MockTmux::new().ensure_available().is_ok());
+        assert!(matches!(
+            MockTmux::new().not_installed().ensure_available(),
+            Err(PanoutError::TmuxNotInstalled)
+        ));
+    }
 
-    if !output.status.success() {
-        return Err(PanoutError::TmuxError("display-message failed".into()));
+    #[test]
+    fn test_send_key_sequence_args() {
+        assert_eq!(
+            send_key_sequence_args("1", &["C-c", "Escape"]),
+            vec!["send-keys", "-t", "1", "C-c", "Escape"]
+        );
     }
 
-    let index_str = String::from_utf8_lossy(&output.stdout);
-    index_str
-        .trim()
-        .parse::<u32>()
-        .map_err(|_| PanoutError::TmuxError("failed to parse window index".into()))
+    #[test]
+    fn test_key_sequence_round_trip() {
+        let command = key_sequence_command(&["C-c".into(), "Up".into()]);
+        assert_eq!(key_sequence(&command), Some(vec!["C-c", "Up"]));
+        assert_eq!(key_sequence("C-c"), None);
+    }
+
+    #[test]
+    fn test_send_keys_args() {
+        assert_eq!(
+            send_keys_args("1", "make", true),
+            vec!["send-keys", "-t", "1", "make", "Enter"]
+        );
+        assert_eq!(
+            send_keys_args("1", ":wq", false),
+            vec!["send-keys", "-t", "1", ":wq"]
+        );
+    }
+
+    #[test]
+    fn test_set_pane_title_args() {
+        assert_eq!(
+            set_pane_title_args("2", "frontend"),
+            ["select-pane", "-t", "2", "-T", "frontend"]
+        );
+    }
+
+    #[test]
+    fn test_split_window_args() {
+        assert_eq!(
+            split_window_args(None, None, None, None),
+            vec!["split-window"]
+        );
+        assert_eq!(
+            split_window_args(None, Some("/srv/app"), None, None),
+            vec!["split-window", "-c", "/srv/app"]
+        );
+        assert_eq!(
+            split_window_args(Some(":3"), None, Some(("-p", "40")), None),
+            vec!["split-window", "-t", ":3", "-p", "40"]
+        );
+        assert_eq!(
+            split_window_args(None, None, Some(("-l", "10")), None),
+            vec!["split-window", "-l", "10"]
+        );
+        assert_eq!(
+            split_window_args(Some(":3"), None, None, Some("-h")),
+            vec!["split-window", "-h", "-t", ":3"]
+        );
+    }
+
+    #[test]
+    fn test_size_flag_clamps() {
+        assert_eq!(size_flag(PaneSize::Percent(30)), ("-p", "30".to_string()));
+        assert_eq!(size_flag(PaneSize::Percent(0)), ("-p", "1".to_string()));
+        assert_eq!(size_flag(PaneSize::Percent(150)), ("-p", "99".to_string()));
+        assert_eq!(size_flag(PaneSize::Cells(0)), ("-l", "1".to_string()));
+        assert_eq!(size_flag(PaneSize::Cells(12)), ("-l", "12".to_string()));
+    }
+
+    #[test]
+    fn test_main_pane_value() {
+        assert_eq!(main_pane_value(PaneSize::Percent(60)), "60%");
+        assert_eq!(main_pane_value(PaneSize::Percent(0)), "1%");
+        assert_eq!(main_pane_value(PaneSize::Cells(80)), "80");
+    }
+
+    #[test]
+    fn test_split_sizes_percent_fill() {
+        let sizes = [Some(PaneSize::Percent(60)), None, None];
+        assert_eq!(
+            split_sizes(&sizes).unwrap(),
+            vec![Some(PaneSize::Percent(40)), Some(PaneSize::Percent(50))]
+        );
+    }
+
+    #[test]
+    fn test_split_sizes_cells() {
+        let sizes = [None, Some(PaneSize::Cells(8)), Some(PaneSize::Cells(10))];
+        assert_eq!(
+            split_sizes(&sizes).unwrap(),
+            vec![Some(PaneSize::Cells(19)), Some(PaneSize::Cells(10))]
+        );
+
+        let sizes = [None, None, Some(PaneSize::Cells(10))];
+        assert_eq!(
+            split_sizes(&sizes).unwrap(),
+            vec![None, Some(PaneSize::Cells(10))]
+        );
+    }
+
+    #[test]
+    fn test_split_sizes_rejects_mix() {
+        let sizes = [Some(PaneSize::Percent(50)), Some(PaneSize::Cells(10))];
+        assert!(split_sizes(&sizes).is_err());
+    }
+
+    #[test]
+    fn test_split_percents() {
+        assert_eq!(split_percents(&[60, 20, 20]), vec![40, 50]);
+        assert_eq!(split_percents(&[1, 1, 1, 1]), vec![75, 67, 50]);
+        assert_eq!(split_percents(&[100, 0]), vec![1]);
+        assert!(split_percents(&[100]).is_empty());
+    }
+
+    #[test]
+    fn test_create_sized_panes_skips_layout() {
+        let tmux = MockTmux::new();
+        let splits = [Some(PaneSize::Percent(40)), Some(PaneSize::Percent(50))];
+        let panes = tmux.create_sized_panes(None, &splits, &[], None).unwrap();
+
+        assert_eq!(panes, vec![0, 1, 2]);
+        assert_eq!(
+            tmux.calls(),
+            vec![
+                TmuxCall::SplitWindow {
+                    window: None,
+                    start_dir: None,
+                    size: Some(PaneSize::Percent(40)),
+                    direction: None
+                },
+                TmuxCall::SplitWindow {
+                    window: None,
+                    start_dir: None,
+                    size: Some(PaneSize::Percent(50)),
+                    direction: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_shell_args() {
+        assert_eq!(
+            run_shell_args("notify-send 'panes up'"),
+            ["run-shell", "notify-send 'panes up'"]
+        );
+    }
+
+    #[test]
+    fn test_rename_window_args() {
+        assert_eq!(rename_window_args("code"), ["rename-window", "code"]);
+    }
+
+    #[test]
+    fn test_set_window_option_args() {
+        assert_eq!(
+            set_window_option_args(None, "synchronize-panes", "on"),
+            vec!["set-window-option", "synchronize-panes", "on"]
+        );
+        assert_eq!(
+            set_window_option_args(Some(":2"), "synchronize-panes", "on"),
+            vec!["set-window-option", "-t", ":2", "synchronize-panes", "on"]
+        );
+    }
+
+    #[test]
+    fn test_create_panes_splits_and_lays_out() {
+        let tmux = MockTmux::new();
+        let dirs = [Some("/home"), Some("/srv"), Some("/opt")];
+        let panes = tmux
+            .create_panes(None, 3, Layout::Vertical, &dirs, None)
+            .unwrap();
+
+        assert_eq!(panes, vec![0, 1, 2]);
+        assert_eq!(
+            tmux.calls(),
+            vec![
+                TmuxCall::SplitWindow {
+                    window: None,
+                    start_dir: Some("/srv".into()),
+                    size: None,
+                    direction: None
+                },
+                TmuxCall::SelectLayout(Layout::Vertical),
+                TmuxCall::SplitWindow {
+                    window: None,
+                    start_dir: Some("/opt".into()),
+                    size: None,
+                    direction: None
+                },
+                TmuxCall::SelectLayout(Layout::Vertical),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create_panes_passes_split_direction() {
+        let tmux = MockTmux::new();
+        tmux.create_panes(None, 3, Layout::Tiled, &[], Some(SplitDir::Horizontal))
+            .unwrap();
+
+        let directions: Vec<_> = tmux
+            .calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                TmuxCall::SplitWindow { direction, .. } => Some(direction),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(directions, vec![Some(SplitDir::Horizontal); 2]);
+    }
+
+    #[test]
+    fn test_create_panes_respects_pane_base_index() {
+        let tmux = MockTmux::new().with_pane_base_index(1);
+        assert_eq!(
+            tmux.create_panes(None, 3, Layout::Tiled, &[], None)
+                .unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_create_panes_in_new_window() {
+        let tmux = MockTmux::new().with_base_index(1);
+        let window = tmux.new_window(Some("build")).unwrap();
+        tmux.select_window(1).unwrap();
+
+        assert_eq!(window, 2);
+        assert_eq!(
+            tmux.create_panes(Some(window), 2, Layout::Tiled, &[], None)
+                .unwrap(),
+            vec![0, 1]
+        );
+        // The window we were in is untouched
+        assert_eq!(tmux.list_panes(None).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_create_panes_outside_tmux() {
+        let tmux = MockTmux::new().outside_tmux();
+        assert!(matches!(
+            tmux.create_panes(None, 2, Layout::Tiled, &[], None),
+            Err(PanoutError::NotInTmux)
+        ));
+        assert!(tmux.calls().is_empty());
+    }
+
+    #[test]
+    fn test_logical_pane_out_of_range() {
+        assert!(matches!(
+            logical_pane(&[1, 2], 2),
+            Err(PanoutError::PaneOutOfRange { pane: 2, count: 2 })
+        ));
+    }
 }
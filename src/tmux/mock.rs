@@ -0,0 +1,419 @@
+//! In-memory tmux backend.
+
+use super::TmuxBackend;
+use crate::config::{Layout, PaneSize, SplitDir};
+use crate::error::{PanoutError, Result};
+use std::cell::RefCell;
+
+/// A state-changing tmux call recorded by [`MockTmux`].
+///
+/// Read-only queries (`list_panes`, `current_window`, ...) aren't recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TmuxCall {
+    /// `split-window [-h|-v] [-t :window] [-c dir] [-p N|-l N]`
+    SplitWindow {
+        /// Window that was split; `None` for the current window.
+        window: Option<u32>,
+        /// Start directory for the new pane.
+        start_dir: Option<String>,
+        /// Size of the new pane (`-p` or `-l`).
+        size: Option<PaneSize>,
+        /// Forced split direction (`-h` or `-v`).
+        direction: Option<SplitDir>,
+    },
+    /// `send-keys -t pane command Enter`
+    SendKeys {
+        /// Target pane index.
+        pane: u32,
+        /// Text typed into the pane.
+        command: String,
+    },
+    /// `send-keys -t pane text` (no Enter)
+    SendKeysRaw {
+        /// Target pane index.
+        pane: u32,
+        /// Text typed into the pane.
+        text: String,
+    },
+    /// `send-keys -t pane key...` with tmux key names (no Enter)
+    SendKeySequence {
+        /// Target pane index.
+        pane: u32,
+        /// Key names, in order.
+        keys: Vec<String>,
+    },
+    /// `select-layout`
+    SelectLayout(Layout),
+    /// `select-layout <layout string>`
+    SelectCustomLayout(String),
+    /// `select-pane -t pane`
+    SelectPane(u32),
+    /// `select-pane -t pane -T title`
+    SetPaneTitle {
+        /// Target pane index.
+        pane: u32,
+        /// New title.
+        title: String,
+    },
+    /// `new-window [-n name]`
+    NewWindow {
+        /// Window name.
+        name: Option<String>,
+    },
+    /// `rename-window name`
+    RenameWindow(String),
+    /// `select-window -t index`
+    SelectWindow(u32),
+    /// `run-shell command`
+    RunShell(String),
+    /// A wait for new panes' shells, in milliseconds (not a tmux command).
+    Settle(u64),
+    /// `set-window-option [-t :window] name value`
+    SetWindowOption {
+        /// Target window; `None` for the current window.
+        window: Option<u32>,
+        /// Option name.
+        name: String,
+        /// Option value.
+        value: String,
+    },
+    /// `new-session -d -s name`
+    NewSession(String),
+    /// `kill-session -t =name`
+    KillSession(String),
+    /// Later commands aimed at this session (`None`: the current one).
+    UseSession(Option<String>),
+    /// `switch-client -t =name`
+    SwitchClient(String),
+}
+
+#[derive(Debug)]
+struct State {
+    calls: Vec<TmuxCall>,
+    /// Pane count per window, indexed from `base_index`.
+    windows: Vec<u32>,
+    /// Active window, as an offset into `windows`.
+    current: usize,
+    /// Names of every session, the current one first.
+    sessions: Vec<String>,
+    /// Pane last picked with `select_pane`; the first pane until then.
+    active_pane: Option<u32>,
+}
+
+/// Backend that simulates a tmux server in memory and records every call.
+///
+/// Starts with a single window holding a single pane, like a fresh tmux
+/// session. Useful for tests and for previewing what a run would do.
+#[derive(Debug)]
+pub struct MockTmux {
+    state: RefCell<State>,
+    installed: bool,
+    in_tmux: bool,
+    base_index: u32,
+    pane_base_index: u32,
+}
+
+impl Default for MockTmux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockTmux {
+    /// A session with one window and one pane, using tmux's default indices.
+    pub fn new() -> Self {
+        MockTmux {
+            state: RefCell::new(State {
+                calls: Vec::new(),
+                windows: vec![1],
+                current: 0,
+                sessions: vec!["0".into()],
+                active_pane: None,
+            }),
+            installed: true,
+            in_tmux: true,
+            base_index: 0,
+            pane_base_index: 0,
+        }
+    }
+
+    /// Simulate `set -g base-index N`.
+    pub fn with_base_index(mut self, base: u32) -> Self {
+        self.base_index = base;
+        self
+    }
+
+    /// Simulate `setw -g pane-base-index N`.
+    pub fn with_pane_base_index(mut self, base: u32) -> Self {
+        self.pane_base_index = base;
+        self
+    }
+
+    /// Simulate another session called `name` already running.
+    ///
+    /// Sessions only matter to the session calls; windows and panes are
+    /// simulated as one shared set.
+    pub fn with_session(self, name: &str) -> Self {
+        self.state.borrow_mut().sessions.push(name.to_string());
+        self
+    }
+
+    /// Simulate a machine without the tmux executable.
+    pub fn not_installed(mut self) -> Self {
+        self.installed = false;
+        self
+    }
+
+    /// Simulate running outside of tmux.
+    pub fn outside_tmux(mut self) -> Self {
+        self.in_tmux = false;
+        self
+    }
+
+    /// Simulate a current window that lists no panes, as when tmux's
+    /// output can't be read.
+    pub fn without_panes(self) -> Self {
+        self.state.borrow_mut().windows[0] = 0;
+        self
+    }
+
+    /// Every recorded call, in order.
+    pub fn calls(&self) -> Vec<TmuxCall> {
+        self.state.borrow().calls.clone()
+    }
+
+    /// Just the `send-keys` calls, as `(pane, command)` pairs.
+    pub fn sent_keys(&self) -> Vec<(u32, String)> {
+        self.state
+            .borrow()
+            .calls
+            .iter()
+            .filter_map(|c| match c {
+                TmuxCall::SendKeys { pane, command } => Some((*pane, command.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, call: TmuxCall) {
+        self.state.borrow_mut().calls.push(call);
+    }
+
+    /// Offset into the window list for `window`, or the current window.
+    fn window_offset(&self, window: Option<u32>) -> Result<usize> {
+        let state = self.state.borrow();
+        match window {
+            None => Ok(state.current),
+            Some(index) => index
+                .checked_sub(self.base_index)
+                .map(|o| o as usize)
+                .filter(|&o| o < state.windows.len())
+                .ok_or_else(|| PanoutError::TmuxError(format!("can't find window: {}", index))),
+        }
+    }
+}
+
+impl TmuxBackend for MockTmux {
+    fn in_tmux(&self) -> bool {
+        self.in_tmux
+    }
+
+    fn version(&self) -> Result<String> {
+        if !self.installed {
+            return Err(PanoutError::TmuxNotInstalled);
+        }
+        Ok("3.4".into())
+    }
+
+    fn global_option(&self, name: &str, _window: bool) -> Result<String> {
+        match name {
+            "base-index" => Ok(self.base_index.to_string()),
+            "pane-base-index" => Ok(self.pane_base_index.to_string()),
+            _ => Err(PanoutError::TmuxError(format!(
+                "show-options {} failed",
+                name
+            ))),
+        }
+    }
+
+    fn split_window(
+        &self,
+        window: Option<u32>,
+        start_dir: Option<&str>,
+        size: Option<PaneSize>,
+        direction: Option<SplitDir>,
+    ) -> Result<()> {
+        let offset = self.window_offset(window)?;
+        let mut state = self.state.borrow_mut();
+        state.windows[offset] += 1;
+        state.calls.push(TmuxCall::SplitWindow {
+            window,
+            start_dir: start_dir.map(String::from),
+            size,
+            direction,
+        });
+        Ok(())
+    }
+
+    fn send_keys(&self, pane: u32, command: &str) -> Result<()> {
+        self.record(TmuxCall::SendKeys {
+            pane,
+            command: command.to_string(),
+        });
+        Ok(())
+    }
+
+    fn send_keys_raw(&self, pane: u32, text: &str) -> Result<()> {
+        self.record(TmuxCall::SendKeysRaw {
+            pane,
+            text: text.to_string(),
+        });
+        Ok(())
+    }
+
+    fn send_key_sequence(&self, pane: u32, keys: &[&str]) -> Result<()> {
+        self.record(TmuxCall::SendKeySequence {
+            pane,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+        });
+        Ok(())
+    }
+
+    fn select_layout(&self, window: Option<u32>, layout: Layout) -> Result<()> {
+        self.window_offset(window)?;
+        self.record(TmuxCall::SelectLayout(layout));
+        Ok(())
+    }
+
+    fn select_custom_layout(&self, window: Option<u32>, layout: &str) -> Result<()> {
+        self.window_offset(window)?;
+        self.record(TmuxCall::SelectCustomLayout(layout.to_string()));
+        Ok(())
+    }
+
+    fn select_pane(&self, pane: u32) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        state.active_pane = Some(pane);
+        state.calls.push(TmuxCall::SelectPane(pane));
+        Ok(())
+    }
+
+    fn set_pane_title(&self, pane: u32, title: &str) -> Result<()> {
+        self.record(TmuxCall::SetPaneTitle {
+            pane,
+            title: title.to_string(),
+        });
+        Ok(())
+    }
+
+    fn list_panes(&self, window: Option<u32>) -> Result<Vec<u32>> {
+        let offset = self.window_offset(window)?;
+        let count = self.state.borrow().windows[offset];
+        Ok((self.pane_base_index..self.pane_base_index + count).collect())
+    }
+
+    fn new_window(&self, name: Option<&str>) -> Result<u32> {
+        let mut state = self.state.borrow_mut();
+        state.windows.push(1);
+        state.current = state.windows.len() - 1;
+        state.calls.push(TmuxCall::NewWindow {
+            name: name.map(String::from),
+        });
+        Ok(self.base_index + state.current as u32)
+    }
+
+    fn rename_window(&self, name: &str) -> Result<()> {
+        self.record(TmuxCall::RenameWindow(name.to_string()));
+        Ok(())
+    }
+
+    fn select_window(&self, index: u32) -> Result<()> {
+        let offset = self.window_offset(Some(index))?;
+        let mut state = self.state.borrow_mut();
+        state.current = offset;
+        state.calls.push(TmuxCall::SelectWindow(index));
+        Ok(())
+    }
+
+    fn current_window(&self) -> Result<u32> {
+        Ok(self.base_index + self.state.borrow().current as u32)
+    }
+
+    fn current_pane(&self) -> Result<u32> {
+        Ok(self
+            .state
+            .borrow()
+            .active_pane
+            .unwrap_or(self.pane_base_index))
+    }
+
+    fn run_shell(&self, command: &str) -> Result<()> {
+        self.record(TmuxCall::RunShell(command.to_string()));
+        Ok(())
+    }
+
+    /// Records the wait without sleeping.
+    fn settle(&self, ms: u64) -> Result<()> {
+        self.record(TmuxCall::Settle(ms));
+        Ok(())
+    }
+
+    fn set_window_option(&self, window: Option<u32>, name: &str, value: &str) -> Result<()> {
+        self.window_offset(window)?;
+        self.record(TmuxCall::SetWindowOption {
+            window,
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+        Ok(())
+    }
+
+    fn current_session(&self) -> Result<String> {
+        Ok(self.state.borrow().sessions[0].clone())
+    }
+
+    fn has_session(&self, name: &str) -> Result<bool> {
+        Ok(self.state.borrow().sessions.iter().any(|s| s == name))
+    }
+
+    fn new_session(&self, name: &str) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        if state.sessions.iter().any(|s| s == name) {
+            return Err(PanoutError::TmuxError(format!(
+                "duplicate session: {}",
+                name
+            )));
+        }
+        state.sessions.push(name.to_string());
+        state.calls.push(TmuxCall::NewSession(name.to_string()));
+        Ok(())
+    }
+
+    fn kill_session(&self, name: &str) -> Result<()> {
+        let mut state = self.state.borrow_mut();
+        let Some(pos) = state.sessions.iter().position(|s| s == name) else {
+            return Err(PanoutError::TmuxError(format!(
+                "can't find session: {}",
+                name
+            )));
+        };
+        state.sessions.remove(pos);
+        state.calls.push(TmuxCall::KillSession(name.to_string()));
+        Ok(())
+    }
+
+    fn use_session(&self, name: Option<&str>) {
+        self.record(TmuxCall::UseSession(name.map(String::from)));
+    }
+
+    fn switch_client(&self, name: &str) -> Result<()> {
+        if !self.has_session(name)? {
+            return Err(PanoutError::TmuxError(format!(
+                "can't find session: {}",
+                name
+            )));
+        }
+        self.record(TmuxCall::SwitchClient(name.to_string()));
+        Ok(())
+    }
+}
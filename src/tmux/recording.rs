@@ -0,0 +1,208 @@
+//! Backend decorator that records successful calls.
+
+use super::{TmuxBackend, TmuxCall};
+use crate::config::{Layout, PaneSize, SplitDir};
+use crate::error::Result;
+use std::cell::RefCell;
+
+/// Forwards every call to another backend and records the ones that succeed.
+///
+/// Used to report what a run actually did (see [`crate::report`]).
+#[derive(Debug)]
+pub struct RecordingTmux<B> {
+    inner: B,
+    calls: RefCell<Vec<TmuxCall>>,
+}
+
+impl<B: TmuxBackend> RecordingTmux<B> {
+    /// Wrap `inner`.
+    pub fn new(inner: B) -> Self {
+        RecordingTmux {
+            inner,
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Every successful state-changing call, in order.
+    pub fn calls(&self) -> Vec<TmuxCall> {
+        self.calls.borrow().clone()
+    }
+
+    fn record(&self, result: Result<()>, call: impl FnOnce() -> TmuxCall) -> Result<()> {
+        if result.is_ok() {
+            self.calls.borrow_mut().push(call());
+        }
+        result
+    }
+}
+
+impl<B: TmuxBackend> TmuxBackend for RecordingTmux<B> {
+    fn in_tmux(&self) -> bool {
+        self.inner.in_tmux()
+    }
+
+    fn version(&self) -> Result<String> {
+        self.inner.version()
+    }
+
+    fn ensure_available(&self) -> Result<()> {
+        self.inner.ensure_available()
+    }
+
+    fn global_option(&self, name: &str, window: bool) -> Result<String> {
+        self.inner.global_option(name, window)
+    }
+
+    fn split_window(
+        &self,
+        window: Option<u32>,
+        start_dir: Option<&str>,
+        size: Option<PaneSize>,
+        direction: Option<SplitDir>,
+    ) -> Result<()> {
+        self.record(
+            self.inner.split_window(window, start_dir, size, direction),
+            || TmuxCall::SplitWindow {
+                window,
+                start_dir: start_dir.map(String::from),
+                size,
+                direction,
+            },
+        )
+    }
+
+    fn send_keys(&self, pane: u32, command: &str) -> Result<()> {
+        self.record(self.inner.send_keys(pane, command), || TmuxCall::SendKeys {
+            pane,
+            command: command.to_string(),
+        })
+    }
+
+    fn send_keys_raw(&self, pane: u32, text: &str) -> Result<()> {
+        self.record(self.inner.send_keys_raw(pane, text), || {
+            TmuxCall::SendKeysRaw {
+                pane,
+                text: text.to_string(),
+            }
+        })
+    }
+
+    fn send_key_sequence(&self, pane: u32, keys: &[&str]) -> Result<()> {
+        self.record(self.inner.send_key_sequence(pane, keys), || {
+            TmuxCall::SendKeySequence {
+                pane,
+                keys: keys.iter().map(|k| k.to_string()).collect(),
+            }
+        })
+    }
+
+    fn select_layout(&self, window: Option<u32>, layout: Layout) -> Result<()> {
+        self.record(self.inner.select_layout(window, layout), || {
+            TmuxCall::SelectLayout(layout)
+        })
+    }
+
+    fn select_custom_layout(&self, window: Option<u32>, layout: &str) -> Result<()> {
+        self.record(self.inner.select_custom_layout(window, layout), || {
+            TmuxCall::SelectCustomLayout(layout.to_string())
+        })
+    }
+
+    fn select_pane(&self, pane: u32) -> Result<()> {
+        self.record(self.inner.select_pane(pane), || TmuxCall::SelectPane(pane))
+    }
+
+    fn set_pane_title(&self, pane: u32, title: &str) -> Result<()> {
+        self.record(self.inner.set_pane_title(pane, title), || {
+            TmuxCall::SetPaneTitle {
+                pane,
+                title: title.to_string(),
+            }
+        })
+    }
+
+    fn list_panes(&self, window: Option<u32>) -> Result<Vec<u32>> {
+        self.inner.list_panes(window)
+    }
+
+    fn new_window(&self, name: Option<&str>) -> Result<u32> {
+        let index = self.inner.new_window(name)?;
+        self.calls.borrow_mut().push(TmuxCall::NewWindow {
+            name: name.map(String::from),
+        });
+        Ok(index)
+    }
+
+    fn rename_window(&self, name: &str) -> Result<()> {
+        self.record(self.inner.rename_window(name), || {
+            TmuxCall::RenameWindow(name.to_string())
+        })
+    }
+
+    fn select_window(&self, index: u32) -> Result<()> {
+        self.record(self.inner.select_window(index), || {
+            TmuxCall::SelectWindow(index)
+        })
+    }
+
+    fn current_window(&self) -> Result<u32> {
+        self.inner.current_window()
+    }
+
+    fn current_pane(&self) -> Result<u32> {
+        self.inner.current_pane()
+    }
+
+    fn run_shell(&self, command: &str) -> Result<()> {
+        self.record(self.inner.run_shell(command), || {
+            TmuxCall::RunShell(command.to_string())
+        })
+    }
+
+    fn settle(&self, ms: u64) -> Result<()> {
+        self.record(self.inner.settle(ms), || TmuxCall::Settle(ms))
+    }
+
+    fn set_window_option(&self, window: Option<u32>, name: &str, value: &str) -> Result<()> {
+        self.record(self.inner.set_window_option(window, name, value), || {
+            TmuxCall::SetWindowOption {
+                window,
+                name: name.to_string(),
+                value: value.to_string(),
+            }
+        })
+    }
+
+    fn current_session(&self) -> Result<String> {
+        self.inner.current_session()
+    }
+
+    fn has_session(&self, name: &str) -> Result<bool> {
+        self.inner.has_session(name)
+    }
+
+    fn new_session(&self, name: &str) -> Result<()> {
+        self.record(self.inner.new_session(name), || {
+            TmuxCall::NewSession(name.to_string())
+        })
+    }
+
+    fn kill_session(&self, name: &str) -> Result<()> {
+        self.record(self.inner.kill_session(name), || {
+            TmuxCall::KillSession(name.to_string())
+        })
+    }
+
+    fn use_session(&self, name: Option<&str>) {
+        self.inner.use_session(name);
+        self.calls
+            .borrow_mut()
+            .push(TmuxCall::UseSession(name.map(String::from)));
+    }
+
+    fn switch_client(&self, name: &str) -> Result<()> {
+        self.record(self.inner.switch_client(name), || {
+            TmuxCall::SwitchClient(name.to_string())
+        })
+    }
+}